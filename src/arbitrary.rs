@@ -0,0 +1,129 @@
+//! Random [`Cpuid`] for property-based testing and fuzzing.
+
+use ::arbitrary::{Arbitrary, Result, Unstructured};
+
+use crate::features::FLAGS_REGISTERS;
+use crate::{
+    AdditionalInformation, Avx512Profile, Cpuid, Leaf0x8000_0008_SubLeaf0_Eax,
+    Leaf0x8000_0008_SubLeaf0_Ecx, Leaf2CacheAndTlb, Leaf6SubLeaf0Ebx, ProcessorVersionInformation,
+    SocVendorAttributes,
+};
+
+/// The characters of generated manufacturer ids and brand strings.
+const MANUFACTURER_ID_CHARACTERS: &[u8] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789 ";
+
+/// Generates [`Cpuid`] with an ASCII manufacturer id, only named feature flags set, no AVX-512
+/// subfeatures without the foundation and no reserved bits set, such that
+/// [`Cpuid::decode_warnings`] is empty.
+///
+/// [`Cpuid::misc`] holds arbitrary registers for arbitrary leaves not otherwise described.
+impl<'a> Arbitrary<'a> for Cpuid {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let mut cpuid = Cpuid::default();
+
+        let leaf0 = &mut cpuid.leaf0x00_highest_function_parameter_an_manufacturer_id;
+        for byte in &mut leaf0.manufacturer_id.0 {
+            *byte = *u.choose(MANUFACTURER_ID_CHARACTERS)?;
+        }
+        leaf0.highest_calling_parameter = u.arbitrary()?;
+
+        let leaf1 = &mut cpuid.leaf0x01_process_info_and_feature_bits;
+        leaf1.processor_version_information = ProcessorVersionInformation(
+            u.arbitrary::<u32>()? & !ProcessorVersionInformation::RESERVED,
+        );
+        leaf1.additional_information = AdditionalInformation {
+            brand_index: u.arbitrary()?,
+            clflush_line_size: u.arbitrary()?,
+            maximum_addressable_logical_processor_ids: u.arbitrary()?,
+            local_apic_id: u.arbitrary()?,
+        };
+
+        cpuid.leaf0x02_cache_and_tlb = Leaf2CacheAndTlb {
+            eax: u.arbitrary()?,
+            ebx: u.arbitrary()?,
+            ecx: u.arbitrary()?,
+            edx: u.arbitrary()?,
+        };
+
+        let leaf0x17 = &mut cpuid.leaf0x17_soc_vendor_attributes;
+        leaf0x17.max_sub_leaf = u.arbitrary()?;
+        leaf0x17.vendor = u.arbitrary::<u32>()? & !SocVendorAttributes::RESERVED;
+        leaf0x17.project_id = u.arbitrary()?;
+        leaf0x17.stepping_id = u.arbitrary()?;
+        if leaf0x17.max_sub_leaf >= 3 {
+            for byte in &mut leaf0x17.brand_string.0 {
+                *byte = *u.choose(MANUFACTURER_ID_CHARACTERS)?;
+            }
+        }
+        for byte in &mut cpuid.leaf0x8000_0002_brand_string.brand_string.0 {
+            *byte = *u.choose(MANUFACTURER_ID_CHARACTERS)?;
+        }
+
+        // Bits 4 to 31 are reserved.
+        cpuid
+            .leaf0x06_thermal_and_power_management
+            .number_of_interrupt_thresholds = Leaf6SubLeaf0Ebx(u.arbitrary::<u32>()? & 0b1111);
+
+        let leaf0x8000_0008 = &mut cpuid.leaf0x8000_0008_virtual_and_physical_address_sizes;
+        leaf0x8000_0008.eax = Leaf0x8000_0008_SubLeaf0_Eax(
+            u.arbitrary::<u32>()? & !Leaf0x8000_0008_SubLeaf0_Eax::RESERVED,
+        );
+        leaf0x8000_0008.ecx = Leaf0x8000_0008_SubLeaf0_Ecx(
+            u.arbitrary::<u32>()? & !Leaf0x8000_0008_SubLeaf0_Ecx::RESERVED,
+        );
+
+        for register in &FLAGS_REGISTERS {
+            *(register.get_mut)(&mut cpuid) = u.arbitrary::<u32>()? & register.named_bits();
+        }
+        // AVX-512 subfeatures require the foundation.
+        if !cpuid.avx512_profile().f {
+            let leaf7 = &mut cpuid.leaf0x07_extended_features;
+            let subfeatures = Avx512Profile::SUBFEATURES;
+            leaf7.sub_leaf0.ebx.remove(subfeatures.sub_leaf0.ebx);
+            leaf7.sub_leaf0.ecx.remove(subfeatures.sub_leaf0.ecx);
+            leaf7.sub_leaf0.edx.remove(subfeatures.sub_leaf0.edx);
+            leaf7.sub_leaf1.remove(subfeatures.sub_leaf1);
+        }
+
+        let described = cpuid.to_leaf_map();
+        for entry in u.arbitrary_iter::<((u32, u32), (u32, u32, u32, u32))>()? {
+            let (key, registers) = entry?;
+            if !described.contains_key(&key) {
+                cpuid.misc.insert(key, registers);
+            }
+        }
+        Ok(cpuid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serialize_deserialize() {
+        // xorshift64
+        let mut state = 0x9E37_79B9_7F4A_7C15_u64;
+        let mut next = || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+        for _ in 0..1000 {
+            let bytes = (0..128)
+                .flat_map(|_| next().to_le_bytes())
+                .collect::<Vec<_>>();
+            let cpuid = Cpuid::arbitrary(&mut Unstructured::new(&bytes)).unwrap();
+            assert!(cpuid.decode_warnings().is_empty());
+            assert!(cpuid
+                .leaf0x00_highest_function_parameter_an_manufacturer_id
+                .manufacturer_id
+                .0
+                .is_ascii());
+            let serialized = serde_json::to_string(&cpuid).unwrap();
+            assert_eq!(serde_json::from_str::<Cpuid>(&serialized).unwrap(), cpuid);
+        }
+    }
+}
@@ -0,0 +1,278 @@
+//! The intersection of the features of a fleet of hosts, the baseline a guest can migrate across.
+
+use std::fmt;
+
+use crate::features::FLAGS_REGISTERS;
+use crate::{
+    Cpuid, Leaf0x8000_0008_SubLeaf0_Eax, Leaf0x8000_0008_SubLeaf0_Ecx, Leaf2CacheAndTlb,
+    Leaf6SubLeaf0Ebx,
+};
+
+/// Error type for [`Cpuid::intersection`] and [`Cpuid::try_intersect_all`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum BaselineError {
+    /// The hosts have different manufacturer ids.
+    VendorMismatch,
+    /// The hosts differ in the given value, which [`Cpuid::covers`] requires equal.
+    Mismatch(&'static str),
+}
+impl fmt::Display for BaselineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::VendorMismatch => write!(f, "Manufacturer ids differ"),
+            Self::Mismatch(field) => write!(f, "{field} differs"),
+        }
+    }
+}
+impl std::error::Error for BaselineError {}
+
+/// Returns the bits of `a` and `b` under `mask` with the lesser field, for a field of contiguous
+/// bits.
+fn min_field(a: u32, b: u32, mask: u32) -> u32 {
+    (a & mask).min(b & mask)
+}
+
+/// Clears the descriptors of `leaf2` not in `other`, in place, registers with bit 31 set hold no
+/// descriptors.
+fn intersect_descriptors(leaf2: &mut Leaf2CacheAndTlb, other: &Leaf2CacheAndTlb) {
+    let other_descriptors = other.descriptor_bytes();
+    for (i, register) in [
+        &mut leaf2.eax,
+        &mut leaf2.ebx,
+        &mut leaf2.ecx,
+        &mut leaf2.edx,
+    ]
+    .into_iter()
+    .enumerate()
+    .filter(|(_, register)| **register & (1 << 31) == 0)
+    {
+        let mut bytes = register.to_le_bytes();
+        // The low byte of `eax` is the times to query.
+        for byte in &mut bytes[usize::from(i == 0)..] {
+            if !other_descriptors.contains(byte) {
+                *byte = 0;
+            }
+        }
+        *register = u32::from_le_bytes(bytes);
+    }
+}
+
+impl Cpuid {
+    /// Returns the greatest [`Cpuid`] both `self` and `other` cover (see [`Cpuid::covers`]), such
+    /// that a guest given it can run on either.
+    ///
+    /// Features are those set in both, scalars compared with `>=` are the lesser of both,
+    /// [`Cpuid::misc`] holds the entries equal in both. Values not compared (e.g. the local APIC
+    /// id) are those of `self`.
+    ///
+    /// # Errors
+    ///
+    /// When `self` and `other` differ in a value [`Cpuid::covers`] requires equal.
+    pub fn intersection(&self, other: &Self) -> Result<Self, BaselineError> {
+        let (leaf0, other_leaf0) = (
+            &self.leaf0x00_highest_function_parameter_an_manufacturer_id,
+            &other.leaf0x00_highest_function_parameter_an_manufacturer_id,
+        );
+        if leaf0.manufacturer_id != other_leaf0.manufacturer_id {
+            return Err(BaselineError::VendorMismatch);
+        }
+        let (leaf1, other_leaf1) = (
+            &self.leaf0x01_process_info_and_feature_bits,
+            &other.leaf0x01_process_info_and_feature_bits,
+        );
+        if leaf1.processor_version_information != other_leaf1.processor_version_information {
+            return Err(BaselineError::Mismatch("processor_version_information"));
+        }
+        if leaf1.additional_information.brand_index
+            != other_leaf1.additional_information.brand_index
+        {
+            return Err(BaselineError::Mismatch("brand_index"));
+        }
+        if self.leaf0x8000_0002_brand_string != other.leaf0x8000_0002_brand_string {
+            return Err(BaselineError::Mismatch("brand_string"));
+        }
+        if leaf1.additional_information.clflush_line_size
+            != other_leaf1.additional_information.clflush_line_size
+        {
+            return Err(BaselineError::Mismatch("clflush_line_size"));
+        }
+        if self.leaf0x07_extended_features.sub_leaf0.ecx.mawau()
+            != other.leaf0x07_extended_features.sub_leaf0.ecx.mawau()
+        {
+            return Err(BaselineError::Mismatch("mawau"));
+        }
+
+        let mut baseline = self.clone();
+        for register in &FLAGS_REGISTERS {
+            *(register.get_mut)(&mut baseline) &= (register.get)(other);
+        }
+
+        let baseline_leaf0 = &mut baseline.leaf0x00_highest_function_parameter_an_manufacturer_id;
+        baseline_leaf0.highest_calling_parameter = leaf0
+            .highest_calling_parameter
+            .min(other_leaf0.highest_calling_parameter);
+
+        let additional = &mut baseline
+            .leaf0x01_process_info_and_feature_bits
+            .additional_information;
+        additional.maximum_addressable_logical_processor_ids =
+            additional.maximum_addressable_logical_processor_ids.min(
+                other_leaf1
+                    .additional_information
+                    .maximum_addressable_logical_processor_ids,
+            );
+
+        let thresholds = &mut baseline
+            .leaf0x06_thermal_and_power_management
+            .number_of_interrupt_thresholds;
+        // Bits 4 to 31 are reserved.
+        *thresholds = Leaf6SubLeaf0Ebx(
+            (thresholds.0 & !0b1111)
+                | min_field(
+                    thresholds.0,
+                    other
+                        .leaf0x06_thermal_and_power_management
+                        .number_of_interrupt_thresholds
+                        .0,
+                    0b1111,
+                ),
+        );
+
+        intersect_descriptors(
+            &mut baseline.leaf0x02_cache_and_tlb,
+            &other.leaf0x02_cache_and_tlb,
+        );
+
+        let (sizes, other_sizes) = (
+            &mut baseline.leaf0x8000_0008_virtual_and_physical_address_sizes,
+            &other.leaf0x8000_0008_virtual_and_physical_address_sizes,
+        );
+        sizes.eax = Leaf0x8000_0008_SubLeaf0_Eax([0x0000_00FF, 0x0000_FF00].iter().fold(
+            sizes.eax.0 & Leaf0x8000_0008_SubLeaf0_Eax::RESERVED,
+            |eax, mask| eax | min_field(sizes.eax.0, other_sizes.eax.0, *mask),
+        ));
+        sizes.ecx =
+            Leaf0x8000_0008_SubLeaf0_Ecx([0x0000_00FF, 0x0000_F000, 0x0003_0000].iter().fold(
+                sizes.ecx.0 & Leaf0x8000_0008_SubLeaf0_Ecx::RESERVED,
+                |ecx, mask| ecx | min_field(sizes.ecx.0, other_sizes.ecx.0, *mask),
+            ));
+
+        baseline
+            .misc
+            .retain(|key, registers| other.misc.get(key) == Some(registers));
+        baseline.absent_leaves |= other.absent_leaves;
+        Ok(baseline)
+    }
+
+    /// Folds [`Cpuid::intersection`] over `iter`, returning the baseline of a fleet of hosts, or
+    /// `None` when `iter` is empty.
+    ///
+    /// # Errors
+    ///
+    /// When any two hosts differ in a value [`Cpuid::covers`] requires equal, see
+    /// [`Cpuid::intersection`].
+    pub fn try_intersect_all<I: IntoIterator<Item = Cpuid>>(
+        iter: I,
+    ) -> Result<Option<Cpuid>, BaselineError> {
+        let mut iter = iter.into_iter();
+        let Some(first) = iter.next() else {
+            return Ok(None);
+        };
+        iter.try_fold(first, |baseline, host| baseline.intersection(&host))
+            .map(Some)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Leaf0x1_SubLeaf0_Ecx, Leaf0x7_SubLeaf0_Ebx, Leaf2CacheAndTlb};
+
+    #[test]
+    fn try_intersect_all() {
+        assert_eq!(Cpuid::try_intersect_all(Vec::new()), Ok(None));
+
+        let host = Cpuid::new();
+        let mut lacks_avx2 = host.clone();
+        lacks_avx2
+            .leaf0x07_extended_features
+            .sub_leaf0
+            .ebx
+            .remove(Leaf0x7_SubLeaf0_Ebx::avx2);
+        let mut fewer_bits = host.clone();
+        fewer_bits
+            .leaf0x8000_0008_virtual_and_physical_address_sizes
+            .eax
+            .set_number_of_physical_address_bits(24);
+        fewer_bits
+            .leaf0x01_process_info_and_feature_bits
+            .feature_information
+            .ecx
+            .remove(Leaf0x1_SubLeaf0_Ecx::sse3);
+        let hosts = [host.clone(), lacks_avx2.clone(), fewer_bits.clone()];
+
+        let baseline = Cpuid::try_intersect_all(hosts.iter().cloned())
+            .unwrap()
+            .unwrap();
+        for host in &hosts {
+            assert!(host.covers(&baseline));
+        }
+        assert!(!baseline.has_feature("avx2"));
+        assert!(!baseline.has_feature("sse3"));
+        assert_eq!(
+            baseline
+                .leaf0x8000_0008_virtual_and_physical_address_sizes
+                .eax
+                .number_of_physical_address_bits(),
+            24
+        );
+        assert_eq!(
+            baseline
+                .leaf0x8000_0008_virtual_and_physical_address_sizes
+                .eax
+                .number_of_linear_address_bits(),
+            host.leaf0x8000_0008_virtual_and_physical_address_sizes
+                .eax
+                .number_of_linear_address_bits()
+        );
+        // Only the cache and TLB descriptors of every host.
+        let mut other_caches = host.clone();
+        other_caches.leaf0x02_cache_and_tlb = Leaf2CacheAndTlb {
+            eax: 0x0000_5501,
+            ebx: 0x0000_2C30,
+            ecx: 0,
+            edx: 0,
+        };
+        let mut caches = host.clone();
+        caches.leaf0x02_cache_and_tlb = Leaf2CacheAndTlb {
+            eax: 0x0000_2C01,
+            ebx: 0x00B4_F055,
+            ecx: 0,
+            edx: 0x8000_0030,
+        };
+        let baseline = caches.intersection(&other_caches).unwrap();
+        assert_eq!(
+            baseline.leaf0x02_cache_and_tlb.descriptor_bytes(),
+            [0x2C, 0x55]
+        );
+        assert_eq!(baseline.leaf0x02_cache_and_tlb.edx, 0x8000_0030);
+        assert!(caches.covers(&baseline) && other_caches.covers(&baseline));
+        assert_eq!(
+            Cpuid::try_intersect_all([host.clone()]),
+            Ok(Some(host.clone()))
+        );
+
+        let mut other_brand = host.clone();
+        other_brand.set_brand_string("Bogus Processor").unwrap();
+        assert_eq!(
+            host.intersection(&other_brand),
+            Err(BaselineError::Mismatch("brand_string"))
+        );
+
+        let other_vendor = host.clone().with_manufacturer("Bogus Vendor").unwrap();
+        assert_eq!(
+            Cpuid::try_intersect_all([host, lacks_avx2, other_vendor, fewer_bits]),
+            Err(BaselineError::VendorMismatch)
+        );
+    }
+}
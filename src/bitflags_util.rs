@@ -23,6 +23,33 @@ bitflags_serde!(Leaf0x8000_001F_SubLeaf0_Eax, p);
 
 const NIBBLE_SEPARATOR: char = '_';
 
+/// Formats `bits` as the binary string used to serialize bit flags, most significant bit first,
+/// e.g. `0b101` as `"0000_0000_0000_0000_0000_0000_0000_0101"`.
+pub fn to_binary_string(bits: u32) -> String {
+    // We format the bits in binary
+    let mut base = format!("{bits:032b}");
+    // We insert a nibble separator
+    // TODO Use https://doc.rust-lang.org/std/iter/struct.Intersperse.html when
+    // stabilized.
+    for (offset, i) in (4..32).step_by(4).enumerate() {
+        base.insert(i + offset, NIBBLE_SEPARATOR);
+    }
+    base
+}
+
+/// Parses the binary string used to serialize bit flags, the inverse of [`to_binary_string`].
+///
+/// The binary string is the canonical form of bit flags, including when no flags are set, but an
+/// empty string is also accepted as no flags set.
+pub fn from_binary_string(s: &str) -> Result<u32, std::num::ParseIntError> {
+    // Removes nibble separator
+    let replaced = s.replace(NIBBLE_SEPARATOR, "");
+    if replaced.is_empty() {
+        return Ok(0);
+    }
+    u32::from_str_radix(&replaced, 2)
+}
+
 /// Internal macro for serde bit flag implementations.
 #[macro_export]
 macro_rules! bitflags_serde {
@@ -36,17 +63,7 @@ macro_rules! bitflags_serde {
             where
                 S: Serializer,
             {
-                // We format the bits in binary
-                let mut base = format!("{:032b}", date.bits());
-                // We insert a nibble separator
-                // TODO Use https://doc.rust-lang.org/std/iter/struct.Intersperse.html when
-                // stabilized.
-                let mut offset = 0;
-                for i in (4..32).step_by(4) {
-                    base.insert(i + offset, $crate::bitflags_util::NIBBLE_SEPARATOR);
-                    offset += 1;
-                }
-                base.serialize(serializer)
+                $crate::bitflags_util::to_binary_string(date.bits()).serialize(serializer)
             }
 
             pub fn deserialize<'de, D>(deserializer: D) -> Result<Flags, D::Error>
@@ -54,10 +71,7 @@ macro_rules! bitflags_serde {
                 D: Deserializer<'de>,
             {
                 let raw = String::deserialize(deserializer)?;
-                // Removes nibble separator
-                let replaced =
-                    raw.replace(&$crate::bitflags_util::NIBBLE_SEPARATOR.to_string(), "");
-                let number = u32::from_str_radix(&replaced, 2)
+                let number = $crate::bitflags_util::from_binary_string(&raw)
                     .map_err(|_| serde::de::Error::custom("radix fail"))?;
 
                 // We use `from_bits_unchecked` over `from_bits` here as this allows unlabelled bits
@@ -69,112 +83,257 @@ macro_rules! bitflags_serde {
     };
 }
 
-pub mod processor_version_information_mod {
-    use std::collections::HashMap;
+/// Serde for the reserved bits of the scalar registers, as a binary string like bit flags.
+mod reserved_mod {
+    use serde::{self, Deserialize, Deserializer, Serialize, Serializer};
+
+    #[allow(clippy::trivially_copy_pass_by_ref)]
+    pub fn serialize<S>(bits: &u32, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        super::to_binary_string(*bits).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<u32, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let raw = String::deserialize(deserializer)?;
+        super::from_binary_string(&raw).map_err(|_| serde::de::Error::custom("radix fail"))
+    }
+}
+
+/// Serde for [`crate::Cpuid::misc`].
+///
+/// JSON object keys must be strings, so each `(leaf, sub_leaf)` key is serialized as
+/// `"<leaf>,<sub_leaf>"` in hex e.g. `"0x40000000,0x0"`, ordered by leaf then sub-leaf.
+pub mod misc_mod {
+    use std::collections::{BTreeMap, HashMap};
+
+    use serde::{self, Deserialize, Deserializer, Serializer};
 
+    type Misc = HashMap<(u32, u32), (u32, u32, u32, u32)>;
+
+    fn parse(s: &str) -> Option<u32> {
+        u32::from_str_radix(s.strip_prefix("0x")?, 16).ok()
+    }
+
+    pub fn serialize<S>(misc: &Misc, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let sorted = misc.iter().collect::<BTreeMap<_, _>>();
+        serializer.collect_map(
+            sorted.into_iter().map(|((leaf, sub_leaf), registers)| {
+                (format!("{leaf:#x},{sub_leaf:#x}"), registers)
+            }),
+        )
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Misc, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        HashMap::<String, (u32, u32, u32, u32)>::deserialize(deserializer)?
+            .into_iter()
+            .map(|(key, registers)| {
+                let (leaf, sub_leaf) = key
+                    .split_once(',')
+                    .and_then(|(leaf, sub_leaf)| Some((parse(leaf)?, parse(sub_leaf)?)))
+                    .ok_or_else(|| serde::de::Error::custom(format!("invalid leaf key {key:?}")))?;
+                Ok(((leaf, sub_leaf), registers))
+            })
+            .collect()
+    }
+}
+
+// The scalar registers serialize their decoded fields alongside their reserved bits, such that a
+// round-trip preserves the register exactly. `reserved` defaults to zero so snapshots from before
+// it was serialized can still be loaded.
+
+pub mod processor_version_information_mod {
     use serde::{self, Deserialize, Deserializer, Serialize, Serializer};
 
     use crate::ProcessorVersionInformation;
     type Flags = ProcessorVersionInformation;
 
+    #[derive(Serialize, Deserialize)]
+    struct Fields {
+        stepping_id: u8,
+        model: u8,
+        family_id: u8,
+        processor_type: u8,
+        extended_model_id: u8,
+        extended_family_id: u8,
+        #[serde(default, with = "super::reserved_mod")]
+        reserved: u32,
+    }
+
     pub fn serialize<S>(date: &Flags, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        let map = [
-            ("stepping_id", date.stepping_id()),
-            ("model", date.model()),
-            ("family_id", date.family_id()),
-            ("processor_type", date.processor_type()),
-            ("extended_model_id", date.extended_model_id()),
-            ("extended_family_id", date.extended_family_id()),
-        ]
-        .into_iter()
-        .collect::<HashMap<&str, u8>>();
-        map.serialize(serializer)
+        Fields {
+            stepping_id: date.stepping_id(),
+            model: date.model(),
+            family_id: date.family_id(),
+            processor_type: date.processor_type(),
+            extended_model_id: date.extended_model_id(),
+            extended_family_id: date.extended_family_id(),
+            reserved: date.reserved(),
+        }
+        .serialize(serializer)
     }
 
     pub fn deserialize<'de, D>(deserializer: D) -> Result<Flags, D::Error>
     where
         D: Deserializer<'de>,
     {
-        let raw = HashMap::<&str, u8>::deserialize(deserializer)?;
-        ProcessorVersionInformation::try_from(raw)
-            .map_err(|_| serde::de::Error::custom("Unexpected flags value {:?}"))
+        let fields = Fields::deserialize(deserializer)?;
+        let mut base = ProcessorVersionInformation(0);
+        base.set_stepping_id(fields.stepping_id)
+            .map_err(serde::de::Error::custom)?;
+        base.set_model(fields.model)
+            .map_err(serde::de::Error::custom)?;
+        base.set_family_id(fields.family_id)
+            .map_err(serde::de::Error::custom)?;
+        base.set_processor_type(fields.processor_type)
+            .map_err(serde::de::Error::custom)?;
+        base.set_extended_model_id(fields.extended_model_id)
+            .map_err(serde::de::Error::custom)?;
+        base.set_extended_family_id(fields.extended_family_id);
+        base.set_reserved(fields.reserved)
+            .map_err(serde::de::Error::custom)?;
+        Ok(base)
     }
 }
 
 pub mod leaf0x8000_0008_sub_leaf0_eax_mod {
-    use std::collections::HashMap;
-
     use serde::{self, Deserialize, Deserializer, Serialize, Serializer};
 
     use crate::Leaf0x8000_0008_SubLeaf0_Eax;
     type Flags = Leaf0x8000_0008_SubLeaf0_Eax;
 
+    #[derive(Serialize, Deserialize)]
+    struct Fields {
+        number_of_physical_address_bits: u8,
+        number_of_linear_address_bits: u8,
+        #[serde(default, with = "super::reserved_mod")]
+        reserved: u32,
+    }
+
     pub fn serialize<S>(date: &Flags, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        let map = [
-            (
-                "number_of_physical_address_bits",
-                date.number_of_physical_address_bits(),
-            ),
-            (
-                "number_of_linear_address_bits",
-                date.number_of_linear_address_bits(),
-            ),
-        ]
-        .into_iter()
-        .collect::<HashMap<&str, u8>>();
-        map.serialize(serializer)
+        Fields {
+            number_of_physical_address_bits: date.number_of_physical_address_bits(),
+            number_of_linear_address_bits: date.number_of_linear_address_bits(),
+            reserved: date.reserved(),
+        }
+        .serialize(serializer)
     }
 
     pub fn deserialize<'de, D>(deserializer: D) -> Result<Flags, D::Error>
     where
         D: Deserializer<'de>,
     {
-        let raw = HashMap::<&str, u8>::deserialize(deserializer)?;
-        Leaf0x8000_0008_SubLeaf0_Eax::try_from(raw)
-            .map_err(|_| serde::de::Error::custom("Unexpected flags value {:?}"))
+        let fields = Fields::deserialize(deserializer)?;
+        let mut base = Leaf0x8000_0008_SubLeaf0_Eax(0);
+        base.set_number_of_physical_address_bits(fields.number_of_physical_address_bits);
+        base.set_number_of_linear_address_bits(fields.number_of_linear_address_bits);
+        base.set_reserved(fields.reserved)
+            .map_err(serde::de::Error::custom)?;
+        Ok(base)
     }
 }
 
 pub mod leaf0x8000_0008_sub_leaf0_ecx_mod {
-    use std::collections::HashMap;
-
     use serde::{self, Deserialize, Deserializer, Serialize, Serializer};
 
     use crate::Leaf0x8000_0008_SubLeaf0_Ecx;
     type Flags = Leaf0x8000_0008_SubLeaf0_Ecx;
 
+    #[derive(Serialize, Deserialize)]
+    struct Fields {
+        number_of_physical_cores_minus_1: u8,
+        log2_of_maximum_apic_id: u8,
+        performance_timestamp_counter_size: u8,
+        #[serde(default, with = "super::reserved_mod")]
+        reserved: u32,
+    }
+
     pub fn serialize<S>(date: &Flags, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        let map = [
-            (
-                "number_of_physical_cores_minus_1",
-                date.number_of_physical_cores_minus_1(),
-            ),
-            ("log2_of_maximum_apic_id", date.log2_of_maximum_apic_id()),
-            (
-                "performance_timestamp_counter_size",
-                date.performance_timestamp_counter_size(),
-            ),
-        ]
-        .into_iter()
-        .collect::<HashMap<&str, u8>>();
-        map.serialize(serializer)
+        Fields {
+            number_of_physical_cores_minus_1: date.number_of_physical_cores_minus_1(),
+            log2_of_maximum_apic_id: date.log2_of_maximum_apic_id(),
+            performance_timestamp_counter_size: date.performance_timestamp_counter_size(),
+            reserved: date.reserved(),
+        }
+        .serialize(serializer)
     }
 
     pub fn deserialize<'de, D>(deserializer: D) -> Result<Flags, D::Error>
     where
         D: Deserializer<'de>,
     {
-        let raw = HashMap::<&str, u8>::deserialize(deserializer)?;
-        Leaf0x8000_0008_SubLeaf0_Ecx::try_from(raw)
-            .map_err(|_| serde::de::Error::custom("Unexpected flags value {:?}"))
+        let fields = Fields::deserialize(deserializer)?;
+        let mut base = Leaf0x8000_0008_SubLeaf0_Ecx(0);
+        base.set_number_of_physical_cores_minus_1(fields.number_of_physical_cores_minus_1);
+        base.set_log2_of_maximum_apic_id(fields.log2_of_maximum_apic_id)
+            .map_err(serde::de::Error::custom)?;
+        base.set_performance_timestamp_counter_size(fields.performance_timestamp_counter_size)
+            .map_err(serde::de::Error::custom)?;
+        base.set_reserved(fields.reserved)
+            .map_err(serde::de::Error::custom)?;
+        Ok(base)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Leaf0x1_SubLeaf0_Ecx;
+
+    #[test]
+    fn empty_string() {
+        assert_eq!(from_binary_string(""), Ok(0));
+        assert!(from_binary_string("2").is_err());
+
+        let flags = a::deserialize(&mut serde_json::Deserializer::from_str("\"\"")).unwrap();
+        assert_eq!(flags, Leaf0x1_SubLeaf0_Ecx::empty());
+        // Empty flags still serialize to the canonical binary string.
+        let mut serialized = Vec::new();
+        a::serialize(&flags, &mut serde_json::Serializer::new(&mut serialized)).unwrap();
+        assert_eq!(
+            String::from_utf8(serialized).unwrap(),
+            "\"0000_0000_0000_0000_0000_0000_0000_0000\""
+        );
+    }
+    #[test]
+    fn most_significant_bit_first() {
+        let bits = 0b0010_0000_0000_0000_0000_0010_0000_0111;
+        assert_eq!(
+            to_binary_string(bits),
+            "0010_0000_0000_0000_0000_0010_0000_0111"
+        );
+        assert_eq!(to_binary_string(1 << 31).find('1'), Some(0));
+        assert_eq!(to_binary_string(1).rfind('1'), Some(38));
+        assert_eq!(
+            from_binary_string("0010_0000_0000_0000_0000_0010_0000_0111"),
+            Ok(bits)
+        );
+
+        let flags = Leaf0x1_SubLeaf0_Ecx::sse3 | Leaf0x1_SubLeaf0_Ecx::hypervisor;
+        let mut serialized = Vec::new();
+        a::serialize(&flags, &mut serde_json::Serializer::new(&mut serialized)).unwrap();
+        assert_eq!(
+            String::from_utf8(serialized).unwrap(),
+            "\"1000_0000_0000_0000_0000_0000_0000_0001\""
+        );
     }
 }
@@ -0,0 +1,270 @@
+//! The processor brand string.
+
+use crate::{AdditionalInformation, BrandString, Cpuid, FixedString};
+
+/// The leaves holding the brand string, 16 bytes each.
+pub(crate) const BRAND_STRING_LEAVES: [u32; 3] = [0x8000_0002, 0x8000_0003, 0x8000_0004];
+
+/// The names of leaf 1 `ebx` brand indices, for processors without the brand string leaves.
+///
+/// <https://www.intel.com/content/www/us/en/developer/articles/technical/intel-sdm.html> Vol. 2A
+/// Table 3-13. Indices which are not listed are reserved.
+const BRAND_INDEX_NAMES: &[(u8, &str)] = &[
+    (0x01, "Intel(R) Celeron(R) processor"),
+    (0x02, "Intel(R) Pentium(R) III processor"),
+    (0x03, "Intel(R) Pentium(R) III Xeon(R) processor"),
+    (0x04, "Intel(R) Pentium(R) III processor"),
+    (0x06, "Mobile Intel(R) Pentium(R) III processor-M"),
+    (0x07, "Mobile Intel(R) Celeron(R) processor"),
+    (0x08, "Intel(R) Pentium(R) 4 processor"),
+    (0x09, "Intel(R) Pentium(R) 4 processor"),
+    (0x0A, "Intel(R) Celeron(R) processor"),
+    (0x0B, "Intel(R) Xeon(R) processor"),
+    (0x0C, "Intel(R) Xeon(R) processor MP"),
+    (0x0E, "Mobile Intel(R) Pentium(R) 4 processor-M"),
+    (0x0F, "Mobile Intel(R) Celeron(R) processor"),
+    (0x11, "Mobile Genuine Intel(R) processor"),
+    (0x12, "Intel(R) Celeron(R) M processor"),
+    (0x13, "Mobile Intel(R) Celeron(R) processor"),
+    (0x14, "Intel(R) Celeron(R) processor"),
+    (0x15, "Mobile Genuine Intel(R) processor"),
+    (0x16, "Intel(R) Pentium(R) M processor"),
+    (0x17, "Mobile Intel(R) Celeron(R) processor"),
+];
+
+impl AdditionalInformation {
+    /// Returns the name of the brand index, `None` when the index is `0` (unsupported) or
+    /// reserved.
+    ///
+    /// This identifies processors from before the brand string leaves, see
+    /// [`Cpuid::brand_string`].
+    #[must_use]
+    pub fn brand_name(&self) -> Option<&'static str> {
+        BRAND_INDEX_NAMES
+            .iter()
+            .find(|(index, _)| *index == self.brand_index)
+            .map(|(_, name)| *name)
+    }
+}
+
+impl Cpuid {
+    /// Returns the brand string of leaves `0x8000_0002` to `0x8000_0004` (see [`BrandString`])
+    /// with trailing NULs trimmed, e.g. `"      Intel(R) Xeon(R) CPU E5-2680 0 @ 2.70GHz"`.
+    ///
+    /// Unlike [`Cpuid::brand_string`] this is as reported, without collapsing whitespace or
+    /// falling back to the brand index.
+    #[must_use]
+    pub fn brand(&self) -> String {
+        String::from_utf8_lossy(&self.leaf0x8000_0002_brand_string.brand_string.0)
+            .trim_end_matches('\0')
+            .to_owned()
+    }
+
+    /// Returns the processor brand string e.g. `"Intel(R) Xeon(R) Platinum 8259CL CPU @ 2.50GHz"`.
+    ///
+    /// This is [`Cpuid::brand`] with whitespace collapsed, unless it is blank or leaf
+    /// `0x8000_0000` in [`Cpuid::misc`] reports the brand string leaves unsupported. Otherwise
+    /// this falls back to the name of the leaf 1 brand index, returning `None` when neither is
+    /// available.
+    #[must_use]
+    pub fn brand_string(&self) -> Option<String> {
+        let supported = self
+            .misc
+            .get(&(0x8000_0000, 0))
+            .is_none_or(|(eax, ..)| *eax >= 0x8000_0004);
+        let brand = self
+            .brand()
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+        if supported && !brand.is_empty() {
+            Some(brand)
+        } else {
+            self.leaf0x01_process_info_and_feature_bits
+                .additional_information
+                .brand_name()
+                .map(String::from)
+        }
+    }
+
+    /// Sets the processor brand string, NUL padded to 48 bytes (see [`BrandString`]), e.g. for
+    /// templates of named CPU models.
+    ///
+    /// When leaf `0x8000_0000` in [`Cpuid::misc`] does not report the brand string leaves
+    /// supported the highest extended leaf is raised to `0x8000_0004`, such that
+    /// [`Cpuid::brand_string`] reads them.
+    ///
+    /// # Errors
+    ///
+    /// Errors when the given string `s` is longer than `48` bytes
+    /// (`if s.len() <= 48 { ... Ok(()) } else { Err(...) }`).
+    pub fn set_brand_string(&mut self, s: &str) -> Result<(), &'static str> {
+        if s.len() > 48 {
+            return Err("s.len() > 48");
+        }
+        let mut brand_string = FixedString([0; 48]);
+        brand_string.0[..s.len()].copy_from_slice(s.as_bytes());
+        self.leaf0x8000_0002_brand_string = BrandString { brand_string };
+        let (highest, ..) = self.misc.entry((0x8000_0000, 0)).or_default();
+        *highest = (*highest).max(0x8000_0004);
+        Ok(())
+    }
+}
+
+impl Cpuid {
+    /// Removes the values identifying the processor model and vendor, such that a snapshot can be
+    /// shared without identifying the host.
+    ///
+    /// This clears the leaf 1 brand index and blanks the brand string and the manufacturer id
+    /// (see [`FixedString::is_blank`]), which are then not serialized. It also clears the leaf
+    /// `0x17` SOC vendor id, project id and vendor brand string. Features are left as they are.
+    pub fn anonymize(&mut self) {
+        self.leaf0x8000_0002_brand_string.brand_string = FixedString::default();
+        let soc = &mut self.leaf0x17_soc_vendor_attributes;
+        soc.vendor = 0;
+        soc.project_id = 0;
+        soc.brand_string = FixedString::default();
+        self.leaf0x01_process_info_and_feature_bits
+            .additional_information
+            .brand_index = 0;
+        self.leaf0x00_highest_function_parameter_an_manufacturer_id
+            .manufacturer_id = FixedString::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the registers holding 16 bytes of a brand string.
+    fn registers(s: &[u8; 16]) -> (u32, u32, u32, u32) {
+        let register = |i: usize| u32::from_le_bytes([s[i], s[i + 1], s[i + 2], s[i + 3]]);
+        (register(0), register(4), register(8), register(12))
+    }
+
+    #[test]
+    fn brand_name() {
+        let brand_name = |brand_index| {
+            AdditionalInformation {
+                brand_index,
+                clflush_line_size: 0,
+                maximum_addressable_logical_processor_ids: 0,
+                local_apic_id: 0,
+            }
+            .brand_name()
+        };
+        assert_eq!(brand_name(0x00), None);
+        assert_eq!(brand_name(0x01), Some("Intel(R) Celeron(R) processor"));
+        assert_eq!(brand_name(0x08), Some("Intel(R) Pentium(R) 4 processor"));
+        assert_eq!(brand_name(0x16), Some("Intel(R) Pentium(R) M processor"));
+        // Reserved
+        assert_eq!(brand_name(0x05), None);
+        assert_eq!(brand_name(0x18), None);
+    }
+    #[test]
+    fn brand_string() {
+        let mut cpuid = Cpuid::default();
+        assert_eq!(cpuid.brand_string(), None);
+
+        // Falls back to the brand index.
+        cpuid
+            .leaf0x01_process_info_and_feature_bits
+            .additional_information
+            .brand_index = 0x08;
+        assert_eq!(
+            cpuid.brand_string().as_deref(),
+            Some("Intel(R) Pentium(R) 4 processor")
+        );
+
+        cpuid.leaf0x8000_0002_brand_string = BrandString::from([
+            registers(b"      Intel(R) X"),
+            registers(b"eon(R) CPU  E5-2"),
+            registers(b"680 0 @ 2.70GHz\0"),
+        ]);
+        assert_eq!(
+            cpuid.brand(),
+            "      Intel(R) Xeon(R) CPU  E5-2680 0 @ 2.70GHz"
+        );
+        assert_eq!(
+            cpuid.brand_string().as_deref(),
+            Some("Intel(R) Xeon(R) CPU E5-2680 0 @ 2.70GHz")
+        );
+        cpuid.misc.insert((0x8000_0000, 0), (0x8000_0008, 0, 0, 0));
+        assert_eq!(
+            cpuid.brand_string().as_deref(),
+            Some("Intel(R) Xeon(R) CPU E5-2680 0 @ 2.70GHz")
+        );
+
+        // The brand string leaves are ignored when not reported supported.
+        cpuid.misc.insert((0x8000_0000, 0), (0x8000_0001, 0, 0, 0));
+        assert_eq!(
+            cpuid.brand_string().as_deref(),
+            Some("Intel(R) Pentium(R) 4 processor")
+        );
+    }
+    #[test]
+    fn set_brand_string() {
+        let mut cpuid = Cpuid::default();
+        let exact = "Intel(R) Xeon(R) Platinum 8259CL CPU @ 2.50GHz  ";
+        assert_eq!(exact.len(), 48);
+        assert_eq!(cpuid.set_brand_string(exact), Ok(()));
+        assert_eq!(cpuid.misc[&(0x8000_0000, 0)].0, 0x8000_0004);
+        assert_eq!(
+            cpuid.brand_string().as_deref(),
+            Some("Intel(R) Xeon(R) Platinum 8259CL CPU @ 2.50GHz")
+        );
+
+        // Shorter strings are NUL padded.
+        cpuid.misc.insert((0x8000_0000, 0), (0x8000_0008, 0, 0, 0));
+        assert_eq!(cpuid.set_brand_string("QEMU Virtual CPU"), Ok(()));
+        assert_eq!(cpuid.misc[&(0x8000_0000, 0)].0, 0x8000_0008);
+        assert_eq!(
+            cpuid.leaf0x8000_0002_brand_string.registers(),
+            [registers(b"QEMU Virtual CPU"), (0, 0, 0, 0), (0, 0, 0, 0)]
+        );
+        assert_eq!(cpuid.leaf::<0x8000_0002>(), b"QEMU Virtual CPU");
+        assert_eq!(cpuid.leaf::<0x8000_0003>(), &[0; 16]);
+        assert_eq!(cpuid.brand_string().as_deref(), Some("QEMU Virtual CPU"));
+
+        assert_eq!(cpuid.set_brand_string(&"A".repeat(49)), Err("s.len() > 48"));
+        assert_eq!(cpuid.brand_string().as_deref(), Some("QEMU Virtual CPU"));
+    }
+    #[test]
+    fn anonymize() {
+        let mut cpuid = Cpuid::default().with_manufacturer("GenuineIntel").unwrap();
+        cpuid
+            .set_brand_string("Intel(R) Xeon(R) CPU E5-2680 0 @ 2.70GHz")
+            .unwrap();
+        let soc = &mut cpuid.leaf0x17_soc_vendor_attributes;
+        soc.max_sub_leaf = 3;
+        soc.vendor = 0x1_0123;
+        soc.project_id = 0x4567;
+        soc.stepping_id = 2;
+        soc.brand_string.0[..11].copy_from_slice(b"Example SOC");
+        // Serialized as a plain string, like the manufacturer id.
+        assert!(serde_json::to_string(&cpuid)
+            .unwrap()
+            .contains(r#""brand_string":"Intel(R) Xeon(R) CPU E5-2680 0 @ 2.70GHz\u0000\u0000"#));
+
+        cpuid.anonymize();
+        assert_eq!(cpuid.brand_string(), None);
+        let serialized = serde_json::to_string(&cpuid).unwrap();
+        assert!(!serialized.contains("GenuineIntel"));
+        assert!(!serialized.contains("\"manufacturer_id\""));
+        assert!(!serialized.contains("Xeon"));
+        assert!(!serialized.contains("Example SOC"));
+        let soc = &cpuid.leaf0x17_soc_vendor_attributes;
+        assert_eq!(
+            (
+                soc.max_sub_leaf,
+                soc.vendor,
+                soc.project_id,
+                soc.stepping_id
+            ),
+            (3, 0, 0, 2)
+        );
+        assert!(soc.brand_string.is_blank());
+        assert!(serialized.contains(r#""leaf0x8000_0002_brand_string":{}"#));
+        assert_eq!(serde_json::from_str::<Cpuid>(&serialized).unwrap(), cpuid);
+    }
+}
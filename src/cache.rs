@@ -0,0 +1,229 @@
+//! The legacy cache and TLB descriptors of leaf 2, see [`crate::Leaf2CacheAndTlb`].
+
+/// The size, associativity and line size of a cache.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct CacheParameters {
+    /// The size in KiB.
+    pub size_kib: u16,
+    /// The number of ways.
+    pub ways: u8,
+    /// The line size in bytes.
+    pub line_size: u8,
+}
+
+/// The cached translations of a TLB.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum TlbKind {
+    Instruction,
+    Data,
+    /// A second level TLB shared by instructions and data.
+    Shared,
+}
+
+/// A leaf 2 descriptor byte, decoded.
+///
+/// <https://www.intel.com/content/www/us/en/developer/articles/technical/intel-sdm.html> Vol. 2A
+/// table 3-12 "Encoding of CPUID Leaf 2 Descriptors".
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum CacheDescriptor {
+    L1Instruction(CacheParameters),
+    L1Data(CacheParameters),
+    L2(CacheParameters),
+    L3(CacheParameters),
+    /// A TLB of `entries` entries, fully associative (or of unspecified associativity) when `ways`
+    /// is 0.
+    Tlb {
+        kind: TlbKind,
+        entries: u16,
+        ways: u8,
+    },
+    /// A trace cache of `kuops` K-μops.
+    TraceCache {
+        kuops: u8,
+        ways: u8,
+    },
+    /// Prefetching of `line_size` bytes.
+    Prefetch {
+        line_size: u8,
+    },
+    /// `0x40`, no L2 cache or, when there is a valid L2 cache, no L3 cache.
+    NoL2OrL3,
+    /// `0xFF`, leaf 2 reports no cache information, which is reported by leaf 4 instead.
+    UseLeaf4,
+    /// A descriptor byte not known.
+    Unknown(u8),
+}
+
+/// Returns the cache parameters of `size_kib` KiB, `ways` ways and `line_size` byte lines.
+const fn cache(size_kib: u16, ways: u8, line_size: u8) -> CacheParameters {
+    CacheParameters {
+        size_kib,
+        ways,
+        line_size,
+    }
+}
+
+/// Returns the TLB descriptor of `entries` entries and `ways` ways.
+const fn tlb(kind: TlbKind, entries: u16, ways: u8) -> CacheDescriptor {
+    CacheDescriptor::Tlb {
+        kind,
+        entries,
+        ways,
+    }
+}
+
+/// The known descriptor bytes, in byte order.
+const CACHE_DESCRIPTORS: &[(u8, CacheDescriptor)] = {
+    use CacheDescriptor::{L1Data, L1Instruction, NoL2OrL3, Prefetch, TraceCache, L2, L3};
+    use TlbKind::{Data, Instruction, Shared};
+    &[
+        (0x01, tlb(Instruction, 32, 4)),
+        (0x02, tlb(Instruction, 2, 0)),
+        (0x03, tlb(Data, 64, 4)),
+        (0x04, tlb(Data, 8, 4)),
+        (0x05, tlb(Data, 32, 4)),
+        (0x06, L1Instruction(cache(8, 4, 32))),
+        (0x08, L1Instruction(cache(16, 4, 32))),
+        (0x09, L1Instruction(cache(32, 4, 64))),
+        (0x0A, L1Data(cache(8, 2, 32))),
+        (0x0B, tlb(Instruction, 4, 4)),
+        (0x0C, L1Data(cache(16, 4, 32))),
+        (0x0D, L1Data(cache(16, 4, 64))),
+        (0x0E, L1Data(cache(24, 6, 64))),
+        (0x1D, L2(cache(128, 2, 64))),
+        (0x21, L2(cache(256, 8, 64))),
+        (0x22, L3(cache(512, 4, 64))),
+        (0x23, L3(cache(1024, 8, 64))),
+        (0x24, L2(cache(1024, 16, 64))),
+        (0x25, L3(cache(2048, 8, 64))),
+        (0x29, L3(cache(4096, 8, 64))),
+        (0x2C, L1Data(cache(32, 8, 64))),
+        (0x30, L1Instruction(cache(32, 8, 64))),
+        (0x40, NoL2OrL3),
+        (0x41, L2(cache(128, 4, 32))),
+        (0x42, L2(cache(256, 4, 32))),
+        (0x43, L2(cache(512, 4, 32))),
+        (0x44, L2(cache(1024, 4, 32))),
+        (0x45, L2(cache(2048, 4, 32))),
+        (0x46, L3(cache(4096, 4, 64))),
+        (0x47, L3(cache(8192, 8, 64))),
+        (0x48, L2(cache(3072, 12, 64))),
+        // An L3 cache on the Xeon MP family 0xF model 6.
+        (0x49, L2(cache(4096, 16, 64))),
+        (0x4A, L3(cache(6144, 12, 64))),
+        (0x4B, L3(cache(8192, 16, 64))),
+        (0x4C, L3(cache(12288, 12, 64))),
+        (0x4D, L3(cache(16384, 16, 64))),
+        (0x4E, L2(cache(6144, 24, 64))),
+        (0x4F, tlb(Instruction, 32, 0)),
+        (0x50, tlb(Instruction, 64, 0)),
+        (0x51, tlb(Instruction, 128, 0)),
+        (0x52, tlb(Instruction, 256, 0)),
+        (0x55, tlb(Instruction, 7, 0)),
+        (0x56, tlb(Data, 16, 4)),
+        (0x57, tlb(Data, 16, 4)),
+        (0x59, tlb(Data, 16, 0)),
+        (0x5A, tlb(Data, 32, 4)),
+        (0x5B, tlb(Data, 64, 0)),
+        (0x5C, tlb(Data, 128, 0)),
+        (0x5D, tlb(Data, 256, 0)),
+        (0x60, L1Data(cache(16, 8, 64))),
+        (0x61, tlb(Instruction, 48, 0)),
+        (0x63, tlb(Data, 32, 4)),
+        (0x66, L1Data(cache(8, 4, 64))),
+        (0x67, L1Data(cache(16, 4, 64))),
+        (0x68, L1Data(cache(32, 4, 64))),
+        (0x6A, tlb(Data, 64, 8)),
+        (0x6B, tlb(Data, 256, 8)),
+        (0x6C, tlb(Data, 128, 8)),
+        (0x6D, tlb(Data, 16, 0)),
+        (0x70, TraceCache { kuops: 12, ways: 8 }),
+        (0x71, TraceCache { kuops: 16, ways: 8 }),
+        (0x72, TraceCache { kuops: 32, ways: 8 }),
+        (0x76, tlb(Instruction, 8, 0)),
+        (0x78, L2(cache(1024, 4, 64))),
+        (0x79, L2(cache(128, 8, 64))),
+        (0x7A, L2(cache(256, 8, 64))),
+        (0x7B, L2(cache(512, 8, 64))),
+        (0x7C, L2(cache(1024, 8, 64))),
+        (0x7D, L2(cache(2048, 8, 64))),
+        (0x7F, L2(cache(512, 2, 64))),
+        (0x80, L2(cache(512, 8, 64))),
+        (0x82, L2(cache(256, 8, 32))),
+        (0x83, L2(cache(512, 8, 32))),
+        (0x84, L2(cache(1024, 8, 32))),
+        (0x85, L2(cache(2048, 8, 32))),
+        (0x86, L2(cache(512, 4, 64))),
+        (0x87, L2(cache(1024, 8, 64))),
+        (0xA0, tlb(Data, 32, 0)),
+        (0xB0, tlb(Instruction, 128, 4)),
+        (0xB1, tlb(Instruction, 8, 4)),
+        (0xB2, tlb(Instruction, 64, 4)),
+        (0xB3, tlb(Data, 128, 4)),
+        (0xB4, tlb(Data, 256, 4)),
+        (0xB5, tlb(Instruction, 64, 8)),
+        (0xB6, tlb(Instruction, 128, 8)),
+        (0xBA, tlb(Data, 64, 4)),
+        (0xC0, tlb(Data, 8, 4)),
+        (0xC1, tlb(Shared, 1024, 8)),
+        (0xC2, tlb(Data, 16, 4)),
+        (0xC3, tlb(Shared, 1536, 6)),
+        (0xC4, tlb(Data, 32, 4)),
+        (0xCA, tlb(Shared, 512, 4)),
+        (0xD0, L3(cache(512, 4, 64))),
+        (0xD1, L3(cache(1024, 4, 64))),
+        (0xD2, L3(cache(2048, 4, 64))),
+        (0xD6, L3(cache(1024, 8, 64))),
+        (0xD7, L3(cache(2048, 8, 64))),
+        (0xD8, L3(cache(4096, 8, 64))),
+        (0xDC, L3(cache(1536, 12, 64))),
+        (0xDD, L3(cache(3072, 12, 64))),
+        (0xDE, L3(cache(6144, 12, 64))),
+        (0xE2, L3(cache(2048, 16, 64))),
+        (0xE3, L3(cache(4096, 16, 64))),
+        (0xE4, L3(cache(8192, 16, 64))),
+        (0xEA, L3(cache(12288, 24, 64))),
+        (0xEB, L3(cache(18432, 24, 64))),
+        (0xEC, L3(cache(24576, 24, 64))),
+        (0xF0, Prefetch { line_size: 64 }),
+        (0xF1, Prefetch { line_size: 128 }),
+        (0xFF, CacheDescriptor::UseLeaf4),
+    ]
+};
+
+impl From<u8> for CacheDescriptor {
+    fn from(byte: u8) -> Self {
+        CACHE_DESCRIPTORS
+            .binary_search_by_key(&byte, |(descriptor, _)| *descriptor)
+            .map_or(Self::Unknown(byte), |i| CACHE_DESCRIPTORS[i].1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cache_descriptors() {
+        assert!(CACHE_DESCRIPTORS
+            .windows(2)
+            .all(|pair| pair[0].0 < pair[1].0));
+        assert_eq!(
+            CacheDescriptor::from(0x2C),
+            CacheDescriptor::L1Data(cache(32, 8, 64))
+        );
+        assert_eq!(CacheDescriptor::from(0xC1), tlb(TlbKind::Shared, 1024, 8));
+        assert_eq!(
+            CacheDescriptor::from(0xF0),
+            CacheDescriptor::Prefetch { line_size: 64 }
+        );
+        assert_eq!(CacheDescriptor::from(0xFF), CacheDescriptor::UseLeaf4);
+        assert_eq!(CacheDescriptor::from(0x40), CacheDescriptor::NoL2OrL3);
+        assert_eq!(CacheDescriptor::from(0x5C), tlb(TlbKind::Data, 128, 0));
+        assert_eq!(
+            CacheDescriptor::from(0x71),
+            CacheDescriptor::TraceCache { kuops: 16, ways: 8 }
+        );
+        assert_eq!(CacheDescriptor::from(0x07), CacheDescriptor::Unknown(0x07));
+    }
+}
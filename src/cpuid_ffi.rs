@@ -1,5 +1,11 @@
 use std::alloc::Layout;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::mem::ManuallyDrop;
 use std::ops::Index;
+use std::ptr::NonNull;
+
+use kvm_bindings::KVM_MAX_CPUID_ENTRIES;
 
 // Stuff to use for interaction with ffi.
 
@@ -7,12 +13,16 @@ use std::ops::Index;
 /// [`kvm_cpuid`](https://elixir.bootlin.com/linux/v5.10.129/source/arch/x86/include/uapi/asm/kvm.h#L226)
 /// .
 ///
-/// [`RawCpuid`] has an identical memory layout to
-/// [`kvm_cpuid`](https://elixir.bootlin.com/linux/v5.10.129/source/arch/x86/include/uapi/asm/kvm.h#L226)
-/// thus we can [`std::mem::transmute`] between them.
+/// [`RawCpuidEntry`] has an identical memory layout to `kvm_cpuid_entry2` thus we can
+/// [`std::mem::transmute`] between slices of them. [`RawCpuid`] itself is not layout compatible
+/// with `kvm_cpuid`, it points to its entries and records their capacity, so convert with
+/// [`From`] rather than transmuting.
 ///
 /// This allows [`RawCpuid`] to function as a simpler replacement for [`kvm_bindings::CpuId`]. In
 /// the future it may replace [`kvm_bindings::CpuId`] fully.
+///
+/// The allocation may hold more than `nent` entries so [`RawCpuid::push`] grows it
+/// geometrically.
 #[derive(Debug)]
 #[repr(C)]
 pub struct RawCpuid {
@@ -21,8 +31,38 @@ pub struct RawCpuid {
     padding: u32,
     // Pointer to entries
     pub entries: *mut RawCpuidEntry,
+    /// Number of entries allocated.
+    cap: u32,
 }
 impl RawCpuid {
+    /// Returns the number of entries allocated, such that `nent` entries may grow to this without
+    /// reallocating.
+    #[must_use]
+    pub fn capacity(&self) -> u32 {
+        self.cap
+    }
+
+    /// Appends an entry, growing the allocation geometrically when full.
+    pub fn push(&mut self, entry: RawCpuidEntry) {
+        self.with_vec(|entries| entries.push(entry));
+    }
+
+    /// Calls `f` with the entries as a [`Vec`], updating `self` with the result.
+    fn with_vec<F: FnOnce(&mut Vec<RawCpuidEntry>)>(&mut self, f: F) {
+        // `replace` leaves `self` empty (so dropping it does nothing) while `f` runs.
+        let (entries, nent, cap) = (
+            std::mem::replace(&mut self.entries, NonNull::dangling().as_ptr()),
+            std::mem::take(&mut self.nent),
+            std::mem::take(&mut self.cap),
+        );
+        let mut entries =
+            ManuallyDrop::new(unsafe { Vec::from_raw_parts(entries, nent as usize, cap as usize) });
+        f(&mut entries);
+        self.nent = u32::try_from(entries.len()).unwrap();
+        self.cap = u32::try_from(entries.capacity()).unwrap();
+        self.entries = entries.as_mut_ptr();
+    }
+
     /// Yields an iterator across the entries.
     #[must_use]
     pub fn iter(&'_ self) -> RawCpuidIter<'_> {
@@ -32,6 +72,11 @@ impl RawCpuid {
         }
     }
 
+    /// Yields an iterator across the entries, allowing modification.
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, RawCpuidEntry> {
+        unsafe { std::slice::from_raw_parts_mut(self.entries, self.nent as usize) }.iter_mut()
+    }
+
     /// Returns an entry for a given lead (function) and sub-leaf (index).
     ///
     /// Returning `None` if it is not present.
@@ -40,6 +85,71 @@ impl RawCpuid {
         self.iter()
             .find(|entry| entry.function == leaf && entry.index == sub_leaf)
     }
+
+    /// Converts to [`kvm_bindings::CpuId`].
+    ///
+    /// # Errors
+    ///
+    /// When there are more than [`KVM_MAX_CPUID_ENTRIES`] entries.
+    pub fn try_into_kvm(&self) -> Result<kvm_bindings::CpuId, IntoKvmError> {
+        let cpuid_slice = unsafe { std::slice::from_raw_parts(self.entries, self.nent as usize) };
+        #[allow(clippy::transmute_ptr_to_ptr)]
+        let kvm_bindings_slice = unsafe {
+            std::mem::transmute::<&[RawCpuidEntry], &[kvm_bindings::kvm_cpuid_entry2]>(cpuid_slice)
+        };
+        kvm_bindings::CpuId::from_entries(kvm_bindings_slice)
+            .map_err(|_| IntoKvmError::TooManyEntries { nent: self.nent })
+    }
+
+    /// Identical to `RawCpuid::from(value)` but only copies the entries when there are at most
+    /// `max`, guarding against the unbounded allocation of a buggy or malicious source
+    /// reporting a huge number of entries.
+    ///
+    /// # Errors
+    ///
+    /// When `value` has more than `max` entries.
+    pub fn from_kvm_limited(
+        value: kvm_bindings::CpuId,
+        max: usize,
+    ) -> Result<Self, TooManyEntries> {
+        let nent = value.as_slice().len();
+        if nent > max {
+            return Err(TooManyEntries { nent, max });
+        }
+        Ok(Self::from(value))
+    }
+
+    /// Retains only the entries for which `f` returns `true`.
+    pub fn retain<F: FnMut(&RawCpuidEntry) -> bool>(&mut self, f: F) {
+        self.with_vec(|entries| entries.retain(f));
+    }
+}
+impl From<Vec<RawCpuidEntry>> for RawCpuid {
+    fn from(entries: Vec<RawCpuidEntry>) -> Self {
+        let mut entries = ManuallyDrop::new(entries);
+        Self {
+            nent: u32::try_from(entries.len()).unwrap(),
+            padding: Default::default(),
+            entries: entries.as_mut_ptr(),
+            cap: u32::try_from(entries.capacity()).unwrap(),
+        }
+    }
+}
+impl<'a> IntoIterator for &'a RawCpuid {
+    type IntoIter = RawCpuidIter<'a>;
+    type Item = &'a RawCpuidEntry;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+impl<'a> IntoIterator for &'a mut RawCpuid {
+    type IntoIter = std::slice::IterMut<'a, RawCpuidEntry>;
+    type Item = &'a mut RawCpuidEntry;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
 }
 impl Index<usize> for RawCpuid {
     type Output = RawCpuidEntry;
@@ -50,13 +160,22 @@ impl Index<usize> for RawCpuid {
         unsafe { &*self.entries.add(index) }
     }
 }
-// We implement custom drop which drops all entries using `self.nent`
+impl Extend<RawCpuidEntry> for RawCpuid {
+    fn extend<T: IntoIterator<Item = RawCpuidEntry>>(&mut self, iter: T) {
+        self.with_vec(|entries| entries.extend(iter));
+    }
+}
+// We implement custom drop which drops all entries using `self.cap`
 impl Drop for RawCpuid {
     fn drop(&mut self) {
+        // A table without capacity has no allocation.
+        if self.cap == 0 {
+            return;
+        }
         unsafe {
             std::alloc::dealloc(
                 self.entries.cast::<u8>(),
-                Layout::array::<RawCpuidEntry>(self.nent as usize).unwrap(),
+                Layout::array::<RawCpuidEntry>(self.cap as usize).unwrap(),
             );
         }
     }
@@ -81,7 +200,12 @@ impl<'a> Iterator for RawCpuidIter<'a> {
         rtn
     }
 }
-#[derive(Debug, Clone, Eq, PartialEq)]
+/// A rusty mimic of
+/// [`kvm_cpuid_entry2`](https://elixir.bootlin.com/linux/v5.10.129/source/arch/x86/include/uapi/asm/kvm.h#L232).
+///
+/// Equality and hashing ignore the padding, which is not necessarily zeroed when converted from
+/// [`kvm_bindings::kvm_cpuid_entry2`].
+#[derive(Debug, Clone)]
 #[repr(C)]
 pub struct RawCpuidEntry {
     pub function: u32,
@@ -116,30 +240,92 @@ impl RawCpuidEntry {
         }
     }
 }
+impl RawCpuidEntry {
+    /// The fields compared by [`PartialEq`] and [`Hash`], every field except the padding.
+    fn key(&self) -> (u32, u32, u32, u32, u32, u32, u32) {
+        (
+            self.function,
+            self.index,
+            self.flags,
+            self.eax,
+            self.ebx,
+            self.ecx,
+            self.edx,
+        )
+    }
+}
+impl PartialEq for RawCpuidEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+impl Eq for RawCpuidEntry {}
+impl Hash for RawCpuidEntry {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key().hash(state);
+    }
+}
+/// This copies every entry however many `value` reports, see [`RawCpuid::from_kvm_limited`] to
+/// bound the allocation.
 impl From<kvm_bindings::CpuId> for RawCpuid {
     fn from(value: kvm_bindings::CpuId) -> Self {
         // As cannot acquire ownership of the underlying slice, we clone it.
-        let cloned = value.as_slice().to_vec();
-        let (ptr, len, _cap) = cloned.into_raw_parts();
+        let cloned = value.as_slice().to_vec().into_boxed_slice();
+        let nent = u32::try_from(cloned.len()).unwrap();
         Self {
-            nent: u32::try_from(len).unwrap(),
+            nent,
             padding: Default::default(),
-            entries: ptr.cast::<RawCpuidEntry>(),
+            entries: Box::into_raw(cloned).cast::<RawCpuidEntry>(),
+            cap: nent,
         }
     }
 }
 // We can't implement a foreign trait on a foreign type, thus we can't implement `From<RawCpuid> for
 // kvm_bindings::CpuId` thus we must implement `Into`.
+/// # Panics
+///
+/// When there are more than [`KVM_MAX_CPUID_ENTRIES`] entries, see [`RawCpuid::try_into_kvm`].
 #[allow(clippy::from_over_into)]
 impl Into<kvm_bindings::CpuId> for RawCpuid {
     fn into(self) -> kvm_bindings::CpuId {
-        let cpuid_slice = unsafe { std::slice::from_raw_parts(self.entries, self.nent as usize) };
-        // println!("cpuid_slice: {:?}",cpuid_slice);
-        #[allow(clippy::transmute_ptr_to_ptr)]
-        let kvm_bindings_slice = unsafe { std::mem::transmute(cpuid_slice) };
-        kvm_bindings::CpuId::from_entries(kvm_bindings_slice).unwrap()
+        self.try_into_kvm().unwrap()
+    }
+}
+/// Error type for [`RawCpuid::try_into_kvm`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum IntoKvmError {
+    /// There are more entries than [`KVM_MAX_CPUID_ENTRIES`].
+    TooManyEntries { nent: u32 },
+}
+impl fmt::Display for IntoKvmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooManyEntries { nent } => write!(
+                f,
+                "Too many entries {nent}, expected at most {KVM_MAX_CPUID_ENTRIES}"
+            ),
+        }
     }
 }
+impl std::error::Error for IntoKvmError {}
+/// Error type for [`RawCpuid::from_kvm_limited`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct TooManyEntries {
+    /// The number of entries.
+    pub nent: usize,
+    /// The maximum number of entries.
+    pub max: usize,
+}
+impl fmt::Display for TooManyEntries {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Too many entries {}, expected at most {}",
+            self.nent, self.max
+        )
+    }
+}
+impl std::error::Error for TooManyEntries {}
 // // We can't implement a foreign trait on a foreign type.
 #[allow(clippy::from_over_into)]
 impl Into<(u32, u32, u32, u32)> for RawCpuidEntry {
@@ -148,51 +334,179 @@ impl Into<(u32, u32, u32, u32)> for RawCpuidEntry {
     }
 }
 
-#[cfg(tests)]
+#[cfg(test)]
 mod tests {
-    #[cfg(target_os = "linux")]
+    use core::arch::x86_64::CpuidResult;
+
+    use super::*;
+    use crate::{Cpuid, Leaf0x7_SubLeaf0_Ebx};
+
     #[test]
-    fn testing() {
-        use kvm_bindings::{kvm_cpuid_entry2, KVM_MAX_CPUID_ENTRIES};
+    fn eq_ignores_padding() {
+        let entry = RawCpuidEntry::new(7, 0, 1, 2, 3, 4, 5);
+        let padded = RawCpuidEntry {
+            padding: [6, 7, 8],
+            ..entry.clone()
+        };
+        assert_eq!(entry, padded);
+        let hash = |entry: &RawCpuidEntry| {
+            let mut hasher = std::collections::hash_map::DefaultHasher::new();
+            entry.hash(&mut hasher);
+            hasher.finish()
+        };
+        assert_eq!(hash(&entry), hash(&padded));
+        assert_eq!(
+            [entry.clone(), padded]
+                .into_iter()
+                .collect::<std::collections::HashSet<_>>()
+                .len(),
+            1
+        );
+        assert_ne!(entry, RawCpuidEntry::new(7, 0, 1, 2, 3, 4, 6));
+    }
+    #[test]
+    fn retain() {
+        let mut cpuid = RawCpuid::from(vec![
+            RawCpuidEntry::new(0, 0, 0, 1, 2, 3, 4),
+            RawCpuidEntry::new(0x4000_0000, 0, 0, 5, 6, 7, 8),
+            RawCpuidEntry::new(1, 0, 0, 9, 10, 11, 12),
+            RawCpuidEntry::new(0x4000_0001, 0, 0, 13, 14, 15, 16),
+            RawCpuidEntry::new(0x8000_0000, 0, 0, 17, 18, 19, 20),
+        ]);
+        cpuid.retain(|entry| entry.function < 0x4000_0000);
+        assert_eq!(cpuid.nent, 2);
+        assert_eq!(
+            cpuid.iter().cloned().collect::<Vec<_>>(),
+            [
+                RawCpuidEntry::new(0, 0, 0, 1, 2, 3, 4),
+                RawCpuidEntry::new(1, 0, 0, 9, 10, 11, 12),
+            ]
+        );
 
-        let kvm = kvm_ioctls::Kvm::new().unwrap();
-        let vm = kvm.create_vm().unwrap();
-        let vcpu = vm.create_vcpu(0).unwrap();
-        let kvm_cpuid = kvm.get_supported_cpuid(KVM_MAX_CPUID_ENTRIES).unwrap();
-        check_err();
+        cpuid.retain(|_| false);
+        assert_eq!(cpuid.nent, 0);
+        assert_eq!(cpuid.iter().next(), None);
+    }
 
-        println!("kvm_cpuid:");
-        for x in kvm_cpuid.clone().as_slice() {
-            println!("\t{:?}", x);
-        }
+    #[test]
+    fn try_into_kvm() {
+        let entries = |n: u32| {
+            RawCpuid::from(
+                (0..n)
+                    .map(|leaf| RawCpuidEntry::new(leaf, 0, 0, 1, 2, 3, 4))
+                    .collect::<Vec<_>>(),
+            )
+        };
+        let max = u32::try_from(KVM_MAX_CPUID_ENTRIES).unwrap();
+        let kvm = entries(max).try_into_kvm().unwrap();
+        assert_eq!(kvm.as_slice().len(), KVM_MAX_CPUID_ENTRIES);
+        assert_eq!(
+            entries(max + 1).try_into_kvm().unwrap_err(),
+            IntoKvmError::TooManyEntries { nent: max + 1 }
+        );
+    }
 
-        let cpuid = CpuId::from(kvm_cpuid);
-        println!("cpuid:");
-        for x in cpuid.iter() {
-            println!("\t{:?}", x);
-        }
+    #[test]
+    fn from_kvm_limited() {
+        let kvm = |n: u32| {
+            RawCpuid::from(
+                (0..n)
+                    .map(|leaf| RawCpuidEntry::new(leaf, 0, 0, 1, 2, 3, 4))
+                    .collect::<Vec<_>>(),
+            )
+            .try_into_kvm()
+            .unwrap()
+        };
+        let cpuid = RawCpuid::from_kvm_limited(kvm(4), 4).unwrap();
+        assert_eq!(cpuid.nent, 4);
+        assert_eq!(
+            cpuid.get(3, 0),
+            Some(&RawCpuidEntry::new(3, 0, 0, 1, 2, 3, 4))
+        );
+        assert_eq!(
+            RawCpuid::from_kvm_limited(kvm(5), 4).unwrap_err(),
+            TooManyEntries { nent: 5, max: 4 }
+        );
+    }
 
-        let kvm_cpuid2: kvm_bindings::CpuId = cpuid.into();
-        println!("kvm_cpuid2:");
-        for x in kvm_cpuid2.clone().as_slice() {
-            println!("\t{:?}", x);
+    #[test]
+    fn push() {
+        let mut cpuid = RawCpuid::from(Vec::new());
+        assert_eq!(cpuid.capacity(), 0);
+        let mut capacities = vec![cpuid.capacity()];
+        for leaf in 0..1000 {
+            cpuid.push(RawCpuidEntry::new(leaf, 0, 0, 1, 2, 3, 4));
+            if capacities.last() != Some(&cpuid.capacity()) {
+                capacities.push(cpuid.capacity());
+            }
         }
+        assert_eq!(cpuid.nent, 1000);
+        assert!(cpuid.capacity() >= 1000);
+        // Geometric growth reallocates a logarithmic number of times.
+        assert!(capacities.len() <= 12, "{capacities:?}");
+        assert!(cpuid
+            .iter()
+            .zip(0..)
+            .all(|(entry, leaf)| *entry == RawCpuidEntry::new(leaf, 0, 0, 1, 2, 3, 4)));
 
-        vcpu.set_cpuid2(&kvm_cpuid2).unwrap();
-        check_err();
+        cpuid.extend([RawCpuidEntry::new(0x4000_0000, 0, 0, 5, 6, 7, 8)]);
+        assert_eq!(cpuid.nent, 1001);
+        assert_eq!(
+            cpuid.get(0x4000_0000, 0),
+            Some(&RawCpuidEntry::new(0x4000_0000, 0, 0, 5, 6, 7, 8))
+        );
 
-        let kvm_cpuid3 = vcpu.get_cpuid2(KVM_MAX_CPUID_ENTRIES).unwrap();
-        check_err();
-        println!("kvm_cpuid 3:");
-        for x in kvm_cpuid3.as_slice() {
-            println!("\t{:?}", x);
-        }
+        let entries = RawCpuid::from(vec![RawCpuidEntry::new(0, 0, 0, 1, 2, 3, 4)]);
+        assert_eq!(entries.capacity(), entries.nent);
+    }
 
-        fn check_err() {
-            let errno = unsafe { libc::__errno_location() };
-            println!("errno: {}", unsafe { *errno });
-            let string = std::ffi::CString::new("get_supported_cpuid").unwrap();
-            unsafe { libc::perror(string.as_ptr()) };
-        }
+    /// Reads `leaf` and `sub_leaf` from `cpuid`, zeroed when not present.
+    fn read(cpuid: &RawCpuid, leaf: u32, sub_leaf: u32) -> CpuidResult {
+        cpuid.get(leaf, sub_leaf).map_or(
+            CpuidResult {
+                eax: 0,
+                ebx: 0,
+                ecx: 0,
+                edx: 0,
+            },
+            |entry| CpuidResult {
+                eax: entry.eax,
+                ebx: entry.ebx,
+                ecx: entry.ecx,
+                edx: entry.edx,
+            },
+        )
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn kvm_set_get_covers() {
+        // `/dev/kvm` may be unavailable, e.g. within a container.
+        let Ok(kvm) = kvm_ioctls::Kvm::new() else {
+            eprintln!("skipping, KVM is unavailable");
+            return;
+        };
+        let vm = kvm.create_vm().unwrap();
+        let vcpu = vm.create_vcpu(0).unwrap();
+
+        // KVM adjusts the cpuid it is given (e.g. clearing `la57` when unsupported by the host's
+        // paging), so we use the adjusted cpuid as the baseline.
+        let supported = kvm.get_supported_cpuid(KVM_MAX_CPUID_ENTRIES).unwrap();
+        vcpu.set_cpuid2(&supported).unwrap();
+        let mut cpuid = RawCpuid::from(vcpu.get_cpuid2(KVM_MAX_CPUID_ENTRIES).unwrap());
+
+        // A template providing less than the baseline.
+        let mut template = Cpuid::from_fn(|leaf, sub_leaf| read(&cpuid, leaf, sub_leaf));
+        template
+            .leaf0x07_extended_features
+            .sub_leaf0
+            .ebx
+            .remove(Leaf0x7_SubLeaf0_Ebx::avx2);
+        template.mask_raw(&mut cpuid);
+
+        vcpu.set_cpuid2(&cpuid.into()).unwrap();
+        let readback = RawCpuid::from(vcpu.get_cpuid2(KVM_MAX_CPUID_ENTRIES).unwrap());
+        let readback = Cpuid::from_fn(|leaf, sub_leaf| read(&readback, leaf, sub_leaf));
+        assert!(readback.covers(&template));
     }
 }
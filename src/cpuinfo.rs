@@ -0,0 +1,341 @@
+//! Feature flags as named by Linux in `/proc/cpuinfo`.
+//!
+//! The kernel names most flags after the specification, but renames some (e.g. `sse3` is `pni`
+//! and `lm` is long mode) and hides others entirely. Hidden flags are omitted from these tables.
+
+use crate::{
+    Cpuid, Leaf0x1_SubLeaf0_Ecx, Leaf0x1_SubLeaf0_Edx, Leaf0x7_SubLeaf0_Ebx, Leaf0x7_SubLeaf0_Ecx,
+    Leaf0x7_SubLeaf0_Edx, Leaf0x7_SubLeaf1_Eax, Leaf0x8000_0001_SubLeaf0_Ecx,
+    Leaf0x8000_0001_SubLeaf0_Edx, Leaf0x8000_0008_SubLeaf0_Ebx, Leaf0xD_SubLeaf1_Eax,
+};
+
+/// Leaf 1 `edx`, the kernel's word 0.
+const LEAF0X1_EDX: &[(u32, &str)] = &[
+    (Leaf0x1_SubLeaf0_Edx::fpu.bits(), "fpu"),
+    (Leaf0x1_SubLeaf0_Edx::vme.bits(), "vme"),
+    (Leaf0x1_SubLeaf0_Edx::de.bits(), "de"),
+    (Leaf0x1_SubLeaf0_Edx::pse.bits(), "pse"),
+    (Leaf0x1_SubLeaf0_Edx::tsc.bits(), "tsc"),
+    (Leaf0x1_SubLeaf0_Edx::msr.bits(), "msr"),
+    (Leaf0x1_SubLeaf0_Edx::pae.bits(), "pae"),
+    (Leaf0x1_SubLeaf0_Edx::mce.bits(), "mce"),
+    (Leaf0x1_SubLeaf0_Edx::cx8.bits(), "cx8"),
+    (Leaf0x1_SubLeaf0_Edx::apic.bits(), "apic"),
+    (Leaf0x1_SubLeaf0_Edx::sep.bits(), "sep"),
+    (Leaf0x1_SubLeaf0_Edx::mtrr.bits(), "mtrr"),
+    (Leaf0x1_SubLeaf0_Edx::pge.bits(), "pge"),
+    (Leaf0x1_SubLeaf0_Edx::mca.bits(), "mca"),
+    (Leaf0x1_SubLeaf0_Edx::cmov.bits(), "cmov"),
+    (Leaf0x1_SubLeaf0_Edx::pat.bits(), "pat"),
+    (Leaf0x1_SubLeaf0_Edx::pse_36.bits(), "pse36"),
+    (Leaf0x1_SubLeaf0_Edx::psn.bits(), "pn"),
+    (Leaf0x1_SubLeaf0_Edx::clfsh.bits(), "clflush"),
+    (Leaf0x1_SubLeaf0_Edx::ds.bits(), "dts"),
+    (Leaf0x1_SubLeaf0_Edx::acpi.bits(), "acpi"),
+    (Leaf0x1_SubLeaf0_Edx::mmx.bits(), "mmx"),
+    (Leaf0x1_SubLeaf0_Edx::fxsr.bits(), "fxsr"),
+    (Leaf0x1_SubLeaf0_Edx::sse.bits(), "sse"),
+    (Leaf0x1_SubLeaf0_Edx::sse2.bits(), "sse2"),
+    (Leaf0x1_SubLeaf0_Edx::ss.bits(), "ss"),
+    (Leaf0x1_SubLeaf0_Edx::htt.bits(), "ht"),
+    (Leaf0x1_SubLeaf0_Edx::tm.bits(), "tm"),
+    (Leaf0x1_SubLeaf0_Edx::ia64.bits(), "ia64"),
+    (Leaf0x1_SubLeaf0_Edx::pbe.bits(), "pbe"),
+];
+/// Leaf `0x8000_0001` `edx`, the kernel's word 1.
+const LEAF0X8000_0001_EDX: &[(u32, &str)] = &[
+    (Leaf0x8000_0001_SubLeaf0_Edx::syscall.bits(), "syscall"),
+    (Leaf0x8000_0001_SubLeaf0_Edx::mp.bits(), "mp"),
+    (Leaf0x8000_0001_SubLeaf0_Edx::nx.bits(), "nx"),
+    (Leaf0x8000_0001_SubLeaf0_Edx::mmxext.bits(), "mmxext"),
+    (Leaf0x8000_0001_SubLeaf0_Edx::fxsr_opt.bits(), "fxsr_opt"),
+    (Leaf0x8000_0001_SubLeaf0_Edx::pdpe1gb.bits(), "pdpe1gb"),
+    (Leaf0x8000_0001_SubLeaf0_Edx::rdtscp.bits(), "rdtscp"),
+    (Leaf0x8000_0001_SubLeaf0_Edx::lm.bits(), "lm"),
+    (Leaf0x8000_0001_SubLeaf0_Edx::_3dnowext.bits(), "3dnowext"),
+    (Leaf0x8000_0001_SubLeaf0_Edx::_3dnow.bits(), "3dnow"),
+];
+/// Leaf 1 `ecx`, the kernel's word 4.
+const LEAF0X1_ECX: &[(u32, &str)] = &[
+    (Leaf0x1_SubLeaf0_Ecx::sse3.bits(), "pni"),
+    (Leaf0x1_SubLeaf0_Ecx::pclmulqdq.bits(), "pclmulqdq"),
+    (Leaf0x1_SubLeaf0_Ecx::dtes64.bits(), "dtes64"),
+    (Leaf0x1_SubLeaf0_Ecx::monitor.bits(), "monitor"),
+    (Leaf0x1_SubLeaf0_Ecx::ds_cpl.bits(), "ds_cpl"),
+    (Leaf0x1_SubLeaf0_Ecx::vmx.bits(), "vmx"),
+    (Leaf0x1_SubLeaf0_Ecx::smx.bits(), "smx"),
+    (Leaf0x1_SubLeaf0_Ecx::est.bits(), "est"),
+    (Leaf0x1_SubLeaf0_Ecx::tm2.bits(), "tm2"),
+    (Leaf0x1_SubLeaf0_Ecx::ssse3.bits(), "ssse3"),
+    (Leaf0x1_SubLeaf0_Ecx::cnxt_id.bits(), "cid"),
+    (Leaf0x1_SubLeaf0_Ecx::sdbg.bits(), "sdbg"),
+    (Leaf0x1_SubLeaf0_Ecx::fma.bits(), "fma"),
+    (Leaf0x1_SubLeaf0_Ecx::cx16.bits(), "cx16"),
+    (Leaf0x1_SubLeaf0_Ecx::xtpr.bits(), "xtpr"),
+    (Leaf0x1_SubLeaf0_Ecx::pdcm.bits(), "pdcm"),
+    (Leaf0x1_SubLeaf0_Ecx::pcid.bits(), "pcid"),
+    (Leaf0x1_SubLeaf0_Ecx::dca.bits(), "dca"),
+    (Leaf0x1_SubLeaf0_Ecx::sse4_1.bits(), "sse4_1"),
+    (Leaf0x1_SubLeaf0_Ecx::sse4_2.bits(), "sse4_2"),
+    (Leaf0x1_SubLeaf0_Ecx::x2apic.bits(), "x2apic"),
+    (Leaf0x1_SubLeaf0_Ecx::movbe.bits(), "movbe"),
+    (Leaf0x1_SubLeaf0_Ecx::popcnt.bits(), "popcnt"),
+    (
+        Leaf0x1_SubLeaf0_Ecx::tsc_deadline.bits(),
+        "tsc_deadline_timer",
+    ),
+    (Leaf0x1_SubLeaf0_Ecx::aes.bits(), "aes"),
+    (Leaf0x1_SubLeaf0_Ecx::xsave.bits(), "xsave"),
+    (Leaf0x1_SubLeaf0_Ecx::avx.bits(), "avx"),
+    (Leaf0x1_SubLeaf0_Ecx::f16c.bits(), "f16c"),
+    (Leaf0x1_SubLeaf0_Ecx::rdrnd.bits(), "rdrand"),
+    (Leaf0x1_SubLeaf0_Ecx::hypervisor.bits(), "hypervisor"),
+];
+/// Leaf `0x8000_0001` `ecx`, the kernel's word 6.
+const LEAF0X8000_0001_ECX: &[(u32, &str)] = &[
+    (Leaf0x8000_0001_SubLeaf0_Ecx::lahf.bits(), "lahf_lm"),
+    (
+        Leaf0x8000_0001_SubLeaf0_Ecx::cmp_legacy.bits(),
+        "cmp_legacy",
+    ),
+    (Leaf0x8000_0001_SubLeaf0_Ecx::svm.bits(), "svm"),
+    (Leaf0x8000_0001_SubLeaf0_Ecx::extapic.bits(), "extapic"),
+    (
+        Leaf0x8000_0001_SubLeaf0_Ecx::cr8_legacy.bits(),
+        "cr8_legacy",
+    ),
+    (Leaf0x8000_0001_SubLeaf0_Ecx::abm.bits(), "abm"),
+    (Leaf0x8000_0001_SubLeaf0_Ecx::sse4a.bits(), "sse4a"),
+    (
+        Leaf0x8000_0001_SubLeaf0_Ecx::missalignsse.bits(),
+        "misalignsse",
+    ),
+    (
+        Leaf0x8000_0001_SubLeaf0_Ecx::_3dnowprefetch.bits(),
+        "3dnowprefetch",
+    ),
+    (Leaf0x8000_0001_SubLeaf0_Ecx::osvw.bits(), "osvw"),
+    (Leaf0x8000_0001_SubLeaf0_Ecx::ibs.bits(), "ibs"),
+    (Leaf0x8000_0001_SubLeaf0_Ecx::xop.bits(), "xop"),
+    (Leaf0x8000_0001_SubLeaf0_Ecx::skinit.bits(), "skinit"),
+    (Leaf0x8000_0001_SubLeaf0_Ecx::wdt.bits(), "wdt"),
+    (Leaf0x8000_0001_SubLeaf0_Ecx::lwp.bits(), "lwp"),
+    (Leaf0x8000_0001_SubLeaf0_Ecx::fma4.bits(), "fma4"),
+    (Leaf0x8000_0001_SubLeaf0_Ecx::tce.bits(), "tce"),
+    (
+        Leaf0x8000_0001_SubLeaf0_Ecx::nodeid_msr.bits(),
+        "nodeid_msr",
+    ),
+    (Leaf0x8000_0001_SubLeaf0_Ecx::tbm.bits(), "tbm"),
+    (Leaf0x8000_0001_SubLeaf0_Ecx::topoext.bits(), "topoext"),
+    (
+        Leaf0x8000_0001_SubLeaf0_Ecx::perfctr_core.bits(),
+        "perfctr_core",
+    ),
+    (
+        Leaf0x8000_0001_SubLeaf0_Ecx::perfctr_nb.bits(),
+        "perfctr_nb",
+    ),
+    (Leaf0x8000_0001_SubLeaf0_Ecx::dbx.bits(), "bpext"),
+    (Leaf0x8000_0001_SubLeaf0_Ecx::perftsc.bits(), "ptsc"),
+    (Leaf0x8000_0001_SubLeaf0_Ecx::pcx_l2i.bits(), "perfctr_llc"),
+    (Leaf0x8000_0001_SubLeaf0_Ecx::monitorx.bits(), "mwaitx"),
+];
+/// Leaf 7 sub-leaf 0 `ebx`, the kernel's word 9.
+const LEAF0X7_EBX: &[(u32, &str)] = &[
+    (Leaf0x7_SubLeaf0_Ebx::fsgsbase.bits(), "fsgsbase"),
+    (Leaf0x7_SubLeaf0_Ebx::IA32_TSC_ADJUST.bits(), "tsc_adjust"),
+    (Leaf0x7_SubLeaf0_Ebx::sgx.bits(), "sgx"),
+    (Leaf0x7_SubLeaf0_Ebx::bmi1.bits(), "bmi1"),
+    (Leaf0x7_SubLeaf0_Ebx::hle.bits(), "hle"),
+    (Leaf0x7_SubLeaf0_Ebx::avx2.bits(), "avx2"),
+    (Leaf0x7_SubLeaf0_Ebx::smep.bits(), "smep"),
+    (Leaf0x7_SubLeaf0_Ebx::bmi2.bits(), "bmi2"),
+    (Leaf0x7_SubLeaf0_Ebx::erms.bits(), "erms"),
+    (Leaf0x7_SubLeaf0_Ebx::invpcid.bits(), "invpcid"),
+    (Leaf0x7_SubLeaf0_Ebx::rtm.bits(), "rtm"),
+    (Leaf0x7_SubLeaf0_Ebx::pqdm.bits(), "cqm"),
+    (Leaf0x7_SubLeaf0_Ebx::mpx.bits(), "mpx"),
+    (Leaf0x7_SubLeaf0_Ebx::pqe.bits(), "rdt_a"),
+    (Leaf0x7_SubLeaf0_Ebx::avx512_f.bits(), "avx512f"),
+    (Leaf0x7_SubLeaf0_Ebx::avx512_dq.bits(), "avx512dq"),
+    (Leaf0x7_SubLeaf0_Ebx::rdseed.bits(), "rdseed"),
+    (Leaf0x7_SubLeaf0_Ebx::adx.bits(), "adx"),
+    (Leaf0x7_SubLeaf0_Ebx::smap.bits(), "smap"),
+    (Leaf0x7_SubLeaf0_Ebx::avx512_ifma.bits(), "avx512ifma"),
+    (Leaf0x7_SubLeaf0_Ebx::clflushopt.bits(), "clflushopt"),
+    (Leaf0x7_SubLeaf0_Ebx::clwb.bits(), "clwb"),
+    (Leaf0x7_SubLeaf0_Ebx::intel_pt.bits(), "intel_pt"),
+    (Leaf0x7_SubLeaf0_Ebx::avx512_pf.bits(), "avx512pf"),
+    (Leaf0x7_SubLeaf0_Ebx::avx512_er.bits(), "avx512er"),
+    (Leaf0x7_SubLeaf0_Ebx::avx512_cd.bits(), "avx512cd"),
+    (Leaf0x7_SubLeaf0_Ebx::sha.bits(), "sha_ni"),
+    (Leaf0x7_SubLeaf0_Ebx::avx512_bw.bits(), "avx512bw"),
+    (Leaf0x7_SubLeaf0_Ebx::avx512_vl.bits(), "avx512vl"),
+];
+/// Leaf `0xD` sub-leaf 1 `eax`, the kernel's word 10.
+const LEAF0XD_SUB_LEAF1_EAX: &[(u32, &str)] = &[
+    (Leaf0xD_SubLeaf1_Eax::xsaveopt.bits(), "xsaveopt"),
+    (Leaf0xD_SubLeaf1_Eax::xsavec.bits(), "xsavec"),
+    (Leaf0xD_SubLeaf1_Eax::xgetbv_ecx1.bits(), "xgetbv1"),
+    (Leaf0xD_SubLeaf1_Eax::xss.bits(), "xsaves"),
+];
+/// Leaf 7 sub-leaf 1 `eax`, the kernel's word 12.
+const LEAF0X7_SUB_LEAF1_EAX: &[(u32, &str)] = &[
+    (Leaf0x7_SubLeaf1_Eax::avx_vnni.bits(), "avx_vnni"),
+    (Leaf0x7_SubLeaf1_Eax::avx512_bf16.bits(), "avx512_bf16"),
+];
+/// Leaf `0x8000_0008` `ebx`, the kernel's word 13.
+const LEAF0X8000_0008_EBX: &[(u32, &str)] = &[
+    (Leaf0x8000_0008_SubLeaf0_Ebx::clzero.bits(), "clzero"),
+    (Leaf0x8000_0008_SubLeaf0_Ebx::retired_instr.bits(), "irperf"),
+    (
+        Leaf0x8000_0008_SubLeaf0_Ebx::xrstor_fp_err.bits(),
+        "xsaveerptr",
+    ),
+    (Leaf0x8000_0008_SubLeaf0_Ebx::rdpru.bits(), "rdpru"),
+    (Leaf0x8000_0008_SubLeaf0_Ebx::wbnoinvd.bits(), "wbnoinvd"),
+    (Leaf0x8000_0008_SubLeaf0_Ebx::ppin.bits(), "amd_ppin"),
+    (Leaf0x8000_0008_SubLeaf0_Ebx::virt_ssbd.bits(), "virt_ssbd"),
+];
+/// Leaf 7 sub-leaf 0 `ecx`, the kernel's word 16.
+const LEAF0X7_ECX: &[(u32, &str)] = &[
+    (Leaf0x7_SubLeaf0_Ecx::avx512_vbmi.bits(), "avx512vbmi"),
+    (Leaf0x7_SubLeaf0_Ecx::umip.bits(), "umip"),
+    (Leaf0x7_SubLeaf0_Ecx::pku.bits(), "pku"),
+    (Leaf0x7_SubLeaf0_Ecx::ospke.bits(), "ospke"),
+    (Leaf0x7_SubLeaf0_Ecx::waitpkg.bits(), "waitpkg"),
+    (Leaf0x7_SubLeaf0_Ecx::avx512_vbmi2.bits(), "avx512_vbmi2"),
+    (Leaf0x7_SubLeaf0_Ecx::gfni.bits(), "gfni"),
+    (Leaf0x7_SubLeaf0_Ecx::vaes.bits(), "vaes"),
+    (Leaf0x7_SubLeaf0_Ecx::vpclmulqdq.bits(), "vpclmulqdq"),
+    (Leaf0x7_SubLeaf0_Ecx::avx512_vnni.bits(), "avx512_vnni"),
+    (Leaf0x7_SubLeaf0_Ecx::avx512_bitalg.bits(), "avx512_bitalg"),
+    (Leaf0x7_SubLeaf0_Ecx::TIME_END.bits(), "tme"),
+    (
+        Leaf0x7_SubLeaf0_Ecx::avx512_vpopcntdq.bits(),
+        "avx512_vpopcntdq",
+    ),
+    (Leaf0x7_SubLeaf0_Ecx::_5_level_paging.bits(), "la57"),
+    (Leaf0x7_SubLeaf0_Ecx::rdpid.bits(), "rdpid"),
+    (Leaf0x7_SubLeaf0_Ecx::cldemote.bits(), "cldemote"),
+    (Leaf0x7_SubLeaf0_Ecx::MOVDIRI.bits(), "movdiri"),
+    (Leaf0x7_SubLeaf0_Ecx::MOVDIR64B.bits(), "movdir64b"),
+    (Leaf0x7_SubLeaf0_Ecx::ENQCMD.bits(), "enqcmd"),
+    (Leaf0x7_SubLeaf0_Ecx::sgx_lc.bits(), "sgx_lc"),
+];
+/// Leaf 7 sub-leaf 0 `edx`, the kernel's word 18.
+const LEAF0X7_EDX: &[(u32, &str)] = &[
+    (Leaf0x7_SubLeaf0_Edx::avx512_4vnniw.bits(), "avx512_4vnniw"),
+    (Leaf0x7_SubLeaf0_Edx::avx512_4fmaps.bits(), "avx512_4fmaps"),
+    (Leaf0x7_SubLeaf0_Edx::fsrm.bits(), "fsrm"),
+    (
+        Leaf0x7_SubLeaf0_Edx::avx512_vp2intersect.bits(),
+        "avx512_vp2intersect",
+    ),
+    (Leaf0x7_SubLeaf0_Edx::md_clear.bits(), "md_clear"),
+    (Leaf0x7_SubLeaf0_Edx::SERIALIZE.bits(), "serialize"),
+    (Leaf0x7_SubLeaf0_Edx::TSXLDTRK.bits(), "tsxldtrk"),
+    (Leaf0x7_SubLeaf0_Edx::pcconfig.bits(), "pconfig"),
+    (Leaf0x7_SubLeaf0_Edx::lbr.bits(), "arch_lbr"),
+    (Leaf0x7_SubLeaf0_Edx::cet_ibt.bits(), "ibt"),
+    (Leaf0x7_SubLeaf0_Edx::amx_bf16.bits(), "amx_bf16"),
+    (Leaf0x7_SubLeaf0_Edx::AVX512_FP16.bits(), "avx512_fp16"),
+    (Leaf0x7_SubLeaf0_Edx::amx_tile.bits(), "amx_tile"),
+    (Leaf0x7_SubLeaf0_Edx::amx_int8.bits(), "amx_int8"),
+    (Leaf0x7_SubLeaf0_Edx::L1D_FLUSH.bits(), "flush_l1d"),
+    (
+        Leaf0x7_SubLeaf0_Edx::IA32_ARCH_CAPABILITIES.bits(),
+        "arch_capabilities",
+    ),
+];
+
+impl Cpuid {
+    /// Returns the feature flags set, named and ordered as Linux lists them in `/proc/cpuinfo`.
+    ///
+    /// This only includes flags the kernel reports directly from these registers, not those it
+    /// synthesizes (e.g. `constant_tsc`) or reports from leaves we do not describe.
+    #[must_use]
+    pub fn cpuinfo_flags(&self) -> Vec<&'static str> {
+        let leaf1 = &self
+            .leaf0x01_process_info_and_feature_bits
+            .feature_information;
+        let leaf7 = &self.leaf0x07_extended_features;
+        let leaf0x8000_0001 = &self.leaf0x8000_0001_highest_function_parameter_an_manufacturer_id;
+        let registers = [
+            (leaf1.edx.bits(), LEAF0X1_EDX),
+            (leaf0x8000_0001.edx.bits(), LEAF0X8000_0001_EDX),
+            (leaf1.ecx.bits(), LEAF0X1_ECX),
+            (leaf0x8000_0001.ecx.bits(), LEAF0X8000_0001_ECX),
+            (leaf7.sub_leaf0.ebx.bits(), LEAF0X7_EBX),
+            (
+                self.leaf0x0d_cpuid_feature_bits.bits(),
+                LEAF0XD_SUB_LEAF1_EAX,
+            ),
+            (leaf7.sub_leaf1.bits(), LEAF0X7_SUB_LEAF1_EAX),
+            (
+                self.leaf0x8000_0008_virtual_and_physical_address_sizes
+                    .ebx
+                    .bits(),
+                LEAF0X8000_0008_EBX,
+            ),
+            (leaf7.sub_leaf0.ecx.bits(), LEAF0X7_ECX),
+            (leaf7.sub_leaf0.edx.bits(), LEAF0X7_EDX),
+        ];
+        registers
+            .into_iter()
+            .flat_map(|(bits, names)| {
+                names
+                    .iter()
+                    .filter(move |(flag, _)| bits & flag != 0)
+                    .map(|(_, name)| *name)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::arch::x86_64::CpuidResult;
+
+    use super::*;
+
+    #[test]
+    fn sse3_is_pni() {
+        let cpuid = Cpuid::from_fn(|leaf, sub_leaf| CpuidResult {
+            eax: 0,
+            ebx: 0,
+            ecx: if (leaf, sub_leaf) == (1, 0) {
+                Leaf0x1_SubLeaf0_Ecx::sse3.bits()
+            } else {
+                0
+            },
+            edx: 0,
+        });
+        assert_eq!(cpuid.cpuinfo_flags(), ["pni"]);
+    }
+    #[test]
+    fn kernel_order() {
+        let cpuid = Cpuid::from_fn(|leaf, sub_leaf| match (leaf, sub_leaf) {
+            (1, 0) => CpuidResult {
+                eax: 0,
+                ebx: 0,
+                ecx: Leaf0x1_SubLeaf0_Ecx::rdrnd.bits(),
+                edx: Leaf0x1_SubLeaf0_Edx::fpu.bits(),
+            },
+            (0x8000_0001, 0) => CpuidResult {
+                eax: 0,
+                ebx: 0,
+                ecx: 0,
+                edx: Leaf0x8000_0001_SubLeaf0_Edx::lm.bits(),
+            },
+            _ => CpuidResult {
+                eax: 0,
+                ebx: 0,
+                ecx: 0,
+                edx: 0,
+            },
+        });
+        assert_eq!(cpuid.cpuinfo_flags(), ["fpu", "lm", "rdrand"]);
+    }
+}
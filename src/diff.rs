@@ -0,0 +1,465 @@
+//! Explanations of why one [`Cpuid`] does not cover another.
+
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::features::FLAGS_REGISTERS;
+use crate::Cpuid;
+
+/// A feature flag and the leaf defining it.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize)]
+pub struct FeatureLoc {
+    pub leaf: u32,
+    pub sub_leaf: u32,
+    pub name: &'static str,
+}
+impl fmt::Display for FeatureLoc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} (leaf {:#x} sub-leaf {:#x})",
+            self.name, self.leaf, self.sub_leaf
+        )
+    }
+}
+
+/// A scalar value where a host provides less than a template requires.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize)]
+pub struct ScalarLack {
+    pub name: &'static str,
+    pub required: u32,
+    pub available: u32,
+}
+impl fmt::Display for ScalarLack {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} (required {}, available {})",
+            self.name, self.required, self.available
+        )
+    }
+}
+
+/// What a host lacks to run a guest template, see [`Cpuid::diff`].
+///
+/// A host covers the template if and only if this is empty, excluding bits set in the template
+/// which have no name.
+///
+/// A missing feature is a hard block, while an insufficient scalar (e.g. address bits) may be
+/// resolved by configuring the guest differently, so these are kept separate.
+///
+/// This serializes for machine-readable reports, e.g.
+/// `{"missing_features":[{"leaf":7,"sub_leaf":0,"name":"avx2"}],"insufficient_scalars":[],
+/// "mismatched":[]}`.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Serialize)]
+pub struct CpuidDiff {
+    /// The features the template sets which the host does not.
+    pub missing_features: Vec<FeatureLoc>,
+    /// The scalar values where the host provides less than the template.
+    pub insufficient_scalars: Vec<ScalarLack>,
+    /// The values the host and template must share but do not, and `cache_and_tlb_descriptors`
+    /// when the host lacks leaf 2 descriptors of the template.
+    pub mismatched: Vec<&'static str>,
+}
+/// The number of each kind of difference in a [`CpuidDiff`], e.g. to rank hosts by how close
+/// they are to running a template.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Serialize)]
+pub struct DiffCounts {
+    /// The number of [`CpuidDiff::missing_features`].
+    pub missing_features: usize,
+    /// The number of [`CpuidDiff::insufficient_scalars`].
+    pub insufficient_scalars: usize,
+    /// The number of [`CpuidDiff::mismatched`].
+    pub mismatched: usize,
+}
+
+impl CpuidDiff {
+    /// If the host lacks nothing.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.missing_features.is_empty()
+            && self.insufficient_scalars.is_empty()
+            && self.mismatched.is_empty()
+    }
+
+    /// Returns the number of each kind of difference.
+    #[must_use]
+    pub fn counts(&self) -> DiffCounts {
+        DiffCounts {
+            missing_features: self.missing_features.len(),
+            insufficient_scalars: self.insufficient_scalars.len(),
+            mismatched: self.mismatched.len(),
+        }
+    }
+}
+impl fmt::Display for CpuidDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "(empty)");
+        }
+        let mut separator = "";
+        if !self.missing_features.is_empty() {
+            write!(f, "missing features:")?;
+            for feature in &self.missing_features {
+                write!(f, "\n  {feature}")?;
+            }
+            separator = "\n";
+        }
+        if !self.insufficient_scalars.is_empty() {
+            write!(f, "{separator}insufficient scalars:")?;
+            for scalar in &self.insufficient_scalars {
+                write!(f, "\n  {scalar}")?;
+            }
+            separator = "\n";
+        }
+        if !self.mismatched.is_empty() {
+            write!(f, "{separator}mismatched:")?;
+            for name in &self.mismatched {
+                write!(f, "\n  {name}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Asserts `host` covers `guest` (see [`Cpuid::covers`]), panicking with what `host` lacks (see
+/// [`Cpuid::diff`]) rather than a bare `assertion failed`.
+///
+/// Like [`assert!`], a custom message may follow the arguments.
+///
+/// ```
+/// use test_cpuid::{assert_covers, Cpuid};
+/// let host = Cpuid::new();
+/// assert_covers!(host, host.clone());
+/// ```
+#[macro_export]
+macro_rules! assert_covers {
+    ($host:expr, $guest:expr $(,)?) => {
+        match (&$host, &$guest) {
+            (host, guest) => {
+                if !$crate::Cpuid::covers(host, guest) {
+                    ::core::panic!(
+                        "assertion failed: `{}` covers `{}`\n{}",
+                        ::core::stringify!($host),
+                        ::core::stringify!($guest),
+                        $crate::Cpuid::diff(guest, host)
+                    );
+                }
+            }
+        }
+    };
+    ($host:expr, $guest:expr, $($arg:tt)+) => {
+        match (&$host, &$guest) {
+            (host, guest) => {
+                if !$crate::Cpuid::covers(host, guest) {
+                    ::core::panic!(
+                        "assertion failed: `{}` covers `{}`: {}\n{}",
+                        ::core::stringify!($host),
+                        ::core::stringify!($guest),
+                        ::core::format_args!($($arg)+),
+                        $crate::Cpuid::diff(guest, host)
+                    );
+                }
+            }
+        }
+    };
+}
+
+impl Cpuid {
+    /// Returns what `host` lacks to run `self` as a guest, such that `host.covers(self)` when this
+    /// is empty.
+    #[must_use]
+    pub fn diff(&self, host: &Cpuid) -> CpuidDiff {
+        let missing_features = self.missing_features(host).collect();
+        let insufficient_scalars = self
+            .scalar_minimums()
+            .into_iter()
+            .zip(host.scalar_minimums())
+            .filter(|((_, required), (_, available))| available < required)
+            .map(|((name, required), (_, available))| ScalarLack {
+                name,
+                required,
+                available,
+            })
+            .collect();
+
+        let (leaf0, host_leaf0) = (
+            &self.leaf0x00_highest_function_parameter_an_manufacturer_id,
+            &host.leaf0x00_highest_function_parameter_an_manufacturer_id,
+        );
+        let (leaf1, host_leaf1) = (
+            &self.leaf0x01_process_info_and_feature_bits,
+            &host.leaf0x01_process_info_and_feature_bits,
+        );
+        let mismatched = [
+            (
+                "manufacturer_id",
+                leaf0.manufacturer_id == host_leaf0.manufacturer_id,
+            ),
+            (
+                "processor_version_information",
+                leaf1.processor_version_information == host_leaf1.processor_version_information,
+            ),
+            (
+                "brand_index",
+                leaf1.additional_information.brand_index
+                    == host_leaf1.additional_information.brand_index,
+            ),
+            (
+                "brand_string",
+                self.leaf0x8000_0002_brand_string == host.leaf0x8000_0002_brand_string,
+            ),
+            (
+                "clflush_line_size",
+                leaf1.additional_information.clflush_line_size
+                    == host_leaf1.additional_information.clflush_line_size,
+            ),
+            (
+                "mawau",
+                self.leaf0x07_extended_features.sub_leaf0.ecx.mawau()
+                    == host.leaf0x07_extended_features.sub_leaf0.ecx.mawau(),
+            ),
+            (
+                "cache_and_tlb_descriptors",
+                host.leaf0x02_cache_and_tlb
+                    .covers(&self.leaf0x02_cache_and_tlb),
+            ),
+        ]
+        .into_iter()
+        .filter(|(_, equal)| !equal)
+        .map(|(name, _)| name)
+        .collect();
+
+        CpuidDiff {
+            missing_features,
+            insufficient_scalars,
+            mismatched,
+        }
+    }
+
+    /// Returns the number of features `self` sets which `host` does not, the length of
+    /// [`CpuidDiff::missing_features`] without collecting them.
+    #[must_use]
+    pub fn missing_count(&self, host: &Cpuid) -> usize {
+        self.missing_features(host).count()
+    }
+
+    /// Returns the features `self` sets which `host` does not, see
+    /// [`CpuidDiff::missing_features`].
+    fn missing_features<'a>(&'a self, host: &'a Cpuid) -> impl Iterator<Item = FeatureLoc> + 'a {
+        FLAGS_REGISTERS.iter().flat_map(move |register| {
+            let missing = (register.get)(self) & !(register.get)(host);
+            register
+                .flags
+                .iter()
+                .filter(move |(_, flag)| missing & flag != 0)
+                .map(|(name, _)| FeatureLoc {
+                    leaf: register.leaf,
+                    sub_leaf: register.sub_leaf,
+                    name,
+                })
+        })
+    }
+
+    /// Returns the hosts of `hosts` which can run `self` as a guest, those which cover `self`.
+    #[must_use]
+    pub fn placement_candidates<'a>(&self, hosts: &'a [Cpuid]) -> Vec<&'a Cpuid> {
+        hosts.iter().filter(|host| host.covers(self)).collect()
+    }
+
+    /// Returns the hosts of `hosts` which cannot run `self` as a guest, each with what it lacks.
+    ///
+    /// This is the complement of [`Cpuid::placement_candidates`].
+    #[must_use]
+    pub fn placement_rejections<'a>(&self, hosts: &'a [Cpuid]) -> Vec<(&'a Cpuid, CpuidDiff)> {
+        hosts
+            .iter()
+            .filter(|host| !host.covers(self))
+            .map(|host| (host, self.diff(host)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Leaf0x7_SubLeaf0_Ebx, Leaf0x7_SubLeaf0_Ecx};
+
+    #[test]
+    fn placement() {
+        let mut template = Cpuid::default();
+        template
+            .leaf0x8000_0008_virtual_and_physical_address_sizes
+            .eax
+            .set_number_of_physical_address_bits(40);
+        template.leaf0x07_extended_features.sub_leaf0.ebx |= Leaf0x7_SubLeaf0_Ebx::avx2;
+        template.leaf0x07_extended_features.sub_leaf0.ecx |= Leaf0x7_SubLeaf0_Ecx::umip;
+        let capable = template.clone();
+
+        let mut lacking_avx2 = template.clone();
+        lacking_avx2.leaf0x07_extended_features.sub_leaf0.ebx &= !Leaf0x7_SubLeaf0_Ebx::avx2;
+        lacking_avx2.leaf0x07_extended_features.sub_leaf0.ecx &= !Leaf0x7_SubLeaf0_Ecx::umip;
+
+        let mut small = template.clone();
+        small
+            .leaf0x8000_0008_virtual_and_physical_address_sizes
+            .eax
+            .set_number_of_physical_address_bits(39);
+
+        let hosts = [capable, lacking_avx2, small];
+        let candidates = template.placement_candidates(&hosts);
+        assert_eq!(candidates, [&hosts[0]]);
+        assert!(template.diff(&hosts[0]).is_empty());
+
+        let rejections = template.placement_rejections(&hosts);
+        assert_eq!(rejections.len(), 2);
+        assert_eq!(rejections[0].0, &hosts[1]);
+        assert_eq!(
+            rejections[0].1.missing_features,
+            [
+                FeatureLoc {
+                    leaf: 7,
+                    sub_leaf: 0,
+                    name: "avx2"
+                },
+                FeatureLoc {
+                    leaf: 7,
+                    sub_leaf: 0,
+                    name: "umip"
+                }
+            ]
+        );
+        assert_eq!(rejections[1].0, &hosts[2]);
+        assert_eq!(
+            rejections[1].1,
+            CpuidDiff {
+                insufficient_scalars: vec![ScalarLack {
+                    name: "phys_addr_bits",
+                    required: 40,
+                    available: 39
+                }],
+                ..CpuidDiff::default()
+            }
+        );
+    }
+    #[test]
+    fn assert_covers() {
+        let host = Cpuid::new();
+        let mut guest = host.clone();
+        crate::assert_covers!(host, guest);
+
+        guest.leaf0x07_extended_features.sub_leaf0.ebx |= Leaf0x7_SubLeaf0_Ebx::avx2;
+        let mut lacking = host.clone();
+        lacking
+            .leaf0x07_extended_features
+            .sub_leaf0
+            .ebx
+            .remove(Leaf0x7_SubLeaf0_Ebx::avx2);
+        let panic =
+            std::panic::catch_unwind(|| crate::assert_covers!(lacking, guest, "host {}", 1))
+                .unwrap_err();
+        let message = panic.downcast_ref::<String>().unwrap();
+        assert!(message.starts_with("assertion failed: `lacking` covers `guest`: host 1\n"));
+        assert!(
+            message.contains("avx2 (leaf 0x7 sub-leaf 0x0)"),
+            "{message}"
+        );
+    }
+    #[test]
+    fn counts() {
+        let mut template = Cpuid::default().with_manufacturer("GenuineIntel").unwrap();
+        template.leaf0x07_extended_features.sub_leaf0.ebx |=
+            Leaf0x7_SubLeaf0_Ebx::avx2 | Leaf0x7_SubLeaf0_Ebx::bmi2;
+        template.leaf0x07_extended_features.sub_leaf0.ecx |= Leaf0x7_SubLeaf0_Ecx::umip;
+        template
+            .leaf0x8000_0008_virtual_and_physical_address_sizes
+            .eax
+            .set_number_of_physical_address_bits(40);
+        assert_eq!(template.diff(&template).counts(), DiffCounts::default());
+        assert_eq!(template.missing_count(&template), 0);
+
+        let mut host = template.clone().with_manufacturer("Bogus Vendor").unwrap();
+        host.leaf0x07_extended_features.sub_leaf0.ebx &=
+            !(Leaf0x7_SubLeaf0_Ebx::avx2 | Leaf0x7_SubLeaf0_Ebx::bmi2);
+        host.leaf0x07_extended_features.sub_leaf0.ecx &= !Leaf0x7_SubLeaf0_Ecx::umip;
+        host.leaf0x8000_0008_virtual_and_physical_address_sizes
+            .eax
+            .set_number_of_physical_address_bits(0);
+        let diff = template.diff(&host);
+        assert_eq!(
+            diff.counts(),
+            DiffCounts {
+                missing_features: diff.missing_features.len(),
+                insufficient_scalars: diff.insufficient_scalars.len(),
+                mismatched: diff.mismatched.len(),
+            }
+        );
+        assert_eq!(
+            diff.counts(),
+            DiffCounts {
+                missing_features: 3,
+                insufficient_scalars: 1,
+                mismatched: 1,
+            }
+        );
+        assert_eq!(template.missing_count(&host), 3);
+    }
+    #[test]
+    fn display() {
+        assert_eq!(CpuidDiff::default().to_string(), "(empty)");
+
+        let mut template = Cpuid::default();
+        template.leaf0x07_extended_features.sub_leaf0.ebx |= Leaf0x7_SubLeaf0_Ebx::avx2;
+        template
+            .leaf0x8000_0008_virtual_and_physical_address_sizes
+            .eax
+            .set_number_of_physical_address_bits(40);
+        let mut host = template.clone();
+        host.leaf0x07_extended_features.sub_leaf0.ebx &= !Leaf0x7_SubLeaf0_Ebx::avx2;
+        host.leaf0x8000_0008_virtual_and_physical_address_sizes
+            .eax
+            .set_number_of_physical_address_bits(39);
+
+        let diff = template.diff(&host);
+        assert_eq!(
+            diff.to_string(),
+            "missing features:\n  avx2 (leaf 0x7 sub-leaf 0x0)\ninsufficient scalars:\n  \
+             phys_addr_bits (required 40, available 39)"
+        );
+
+        let insufficient = CpuidDiff {
+            insufficient_scalars: diff.insufficient_scalars,
+            ..CpuidDiff::default()
+        };
+        assert!(insufficient
+            .to_string()
+            .starts_with("insufficient scalars:"));
+    }
+    #[test]
+    fn serialize() {
+        let mut template = Cpuid::default();
+        template.leaf0x07_extended_features.sub_leaf0.ebx |= Leaf0x7_SubLeaf0_Ebx::avx2;
+        template
+            .leaf0x8000_0008_virtual_and_physical_address_sizes
+            .eax
+            .set_number_of_physical_address_bits(40);
+        let mut host = template.clone();
+        host.leaf0x07_extended_features.sub_leaf0.ebx &= !Leaf0x7_SubLeaf0_Ebx::avx2;
+        host.leaf0x8000_0008_virtual_and_physical_address_sizes
+            .eax
+            .set_number_of_physical_address_bits(0);
+
+        let json = serde_json::to_value(template.diff(&host)).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "missing_features": [{ "leaf": 7, "sub_leaf": 0, "name": "avx2" }],
+                "insufficient_scalars": [
+                    { "name": "phys_addr_bits", "required": 40, "available": 0 }
+                ],
+                "mismatched": [],
+            })
+        );
+    }
+}
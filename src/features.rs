@@ -0,0 +1,944 @@
+//! Named access to the features described by bit flags.
+
+use std::fmt;
+
+use crate::{
+    Cpuid, Leaf0x12_SubLeaf0_Eax, Leaf0x14_SubLeaf0_Ebx, Leaf0x19_SubLeaf0_Ebx,
+    Leaf0x1_SubLeaf0_Ecx, Leaf0x1_SubLeaf0_Edx, Leaf0x6_SubLeaf0_Eax, Leaf0x6_SubLeaf0_Ecx,
+    Leaf0x7_SubLeaf0_Ebx, Leaf0x7_SubLeaf0_Ecx, Leaf0x7_SubLeaf0_Edx, Leaf0x7_SubLeaf1_Eax,
+    Leaf0x8000_0001_SubLeaf0_Ecx, Leaf0x8000_0001_SubLeaf0_Edx, Leaf0x8000_0008_SubLeaf0_Ebx,
+    Leaf0x8000_001F_SubLeaf0_Eax, Leaf0xD_SubLeaf1_Eax, RawCpuid, RawCpuidEntry,
+};
+
+/// Implements named access to the given flags.
+macro_rules! flag_names {
+    ( $x:ident: $($flag:ident),* $(,)? ) => {
+        impl $x {
+            /// The name and bit of each flag, in bit order.
+            pub(crate) const NAMES: &'static [(&'static str, u32)] =
+                &[$((stringify!($flag), $x::$flag.bits())),*];
+
+            /// Returns if the flag `name` is set, `None` when there is no flag `name`.
+            #[must_use]
+            pub fn contains_name(&self, name: &str) -> Option<bool> {
+                Self::NAMES
+                    .iter()
+                    .find(|(flag, _)| *flag == name)
+                    .map(|(_, bit)| self.bits & bit != 0)
+            }
+        }
+    };
+}
+
+flag_names!(
+    Leaf0x1_SubLeaf0_Ecx: sse3, pclmulqdq, dtes64, monitor, ds_cpl, vmx, smx, est, tm2,
+    ssse3, cnxt_id, sdbg, fma, cx16, xtpr, pdcm, pcid, dca, sse4_1, sse4_2, x2apic, movbe,
+    popcnt, tsc_deadline, aes, xsave, osxsave, avx, f16c, rdrnd, hypervisor
+);
+flag_names!(
+    Leaf0x1_SubLeaf0_Edx: fpu, vme, de, pse, tsc, msr, pae, mce, cx8, apic, sep, mtrr, pge,
+    mca, cmov, pat, pse_36, psn, clfsh, ds, acpi, mmx, fxsr, sse, sse2, ss, htt, tm, ia64,
+    pbe
+);
+flag_names!(
+    Leaf0x6_SubLeaf0_Eax: digital_thermal_sensor_capability,
+    intel_turbo_boost_technology_capability, always_running_apic_timer_capability,
+    power_limit_notification_capability, extended_clock_modulation_duty_capability,
+    package_thermal_management_capability
+);
+flag_names!(
+    Leaf0x6_SubLeaf0_Ecx: hardware_coordination_feedback_capability, acnt2_capability,
+    performance_energy_bias_capability
+);
+flag_names!(
+    Leaf0x7_SubLeaf0_Ebx: fsgsbase, IA32_TSC_ADJUST, sgx, bmi1, hle, avx2, FDP_EXCPTN_ONLY,
+    smep, bmi2, erms, invpcid, rtm, pqdm, FPU_CS_and_FPU_DS_deprecated, mpx, pqe, avx512_f,
+    avx512_dq, rdseed, adx, smap, avx512_ifma, pccommit, clflushopt, clwb, intel_pt,
+    avx512_pf, avx512_er, avx512_cd, sha, avx512_bw, avx512_vl
+);
+flag_names!(
+    Leaf0x7_SubLeaf0_Ecx: prefetchwt1, avx512_vbmi, umip, pku, ospke, waitpkg, avx512_vbmi2,
+    cet_ss, gfni, vaes, vpclmulqdq, avx512_vnni, avx512_bitalg, TIME_END, avx512_vpopcntdq,
+    _5_level_paging, rdpid, KL, cldemote, MOVDIRI, MOVDIR64B, ENQCMD, sgx_lc, pks
+);
+flag_names!(
+    Leaf0x7_SubLeaf0_Edx: avx512_4vnniw, avx512_4fmaps, fsrm, uintr, avx512_vp2intersect,
+    SRBDS_CTRL, md_clear, RMT_ALWAYS_ABORT, TSX_FORCE_ABORT, SERIALIZE, Hybrid, TSXLDTRK,
+    pcconfig, lbr, cet_ibt, amx_bf16, AVX512_FP16, amx_tile, amx_int8, IBRS_IBPB_spec_ctrl,
+    stibp, L1D_FLUSH, IA32_ARCH_CAPABILITIES, IA32_CORE_CAPABILITIES, ssbd
+);
+flag_names!(
+    Leaf0x7_SubLeaf1_Eax: avx_vnni, avx512_bf16, fast_zero_rep_movsb, fast_short_rep_stosb,
+    fast_short_rep_cmpsb_scasb, fred, lkgs, hreset
+);
+flag_names!(Leaf0xD_SubLeaf1_Eax: xsaveopt, xsavec, xgetbv_ecx1, xss);
+flag_names!(Leaf0x12_SubLeaf0_Eax: sgx1, sgx2, oss, encls);
+flag_names!(Leaf0x14_SubLeaf0_Ebx: ptwrite);
+flag_names!(Leaf0x19_SubLeaf0_Ebx: aes_kle, aes_wide_kl, kl_msrs);
+flag_names!(
+    Leaf0x8000_0001_SubLeaf0_Edx: syscall, mp, nx, mmxext, fxsr_opt, pdpe1gb, rdtscp, lm,
+    _3dnowext, _3dnow
+);
+flag_names!(
+    Leaf0x8000_0001_SubLeaf0_Ecx: lahf, cmp_legacy, svm, extapic, cr8_legacy, abm, sse4a,
+    missalignsse, _3dnowprefetch, osvw, ibs, xop, skinit, wdt, lwp, fma4, tce, nodeid_msr,
+    tbm, topoext, perfctr_core, perfctr_nb, dbx, perftsc, pcx_l2i, monitorx, addr_mask_ext
+);
+flag_names!(
+    Leaf0x8000_0008_SubLeaf0_Ebx: clzero, retired_instr, xrstor_fp_err, invlpgb, rdpru,
+    mcommit, wbnoinvd, ibpb, wbinvd_int, ibrs, single_thread_ibp, single_thread_ibp_ao,
+    no_efer_lmsle, invlpgb_nested, ppin, ssbd, virt_ssbd, ssb_no
+);
+flag_names!(
+    Leaf0x8000_001F_SubLeaf0_Eax: sme, sev, page_flush, sev_es, sev_snp, vmpl,
+    hw_cache_coherency, _64_host, restricted_injection, alternative_injection, debug_swap,
+    prevent_host_ibs, vte
+);
+
+/// A cpuid output register.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub(crate) enum Register {
+    Eax,
+    Ebx,
+    Ecx,
+    Edx,
+}
+impl Register {
+    fn of(self, entry: &mut RawCpuidEntry) -> &mut u32 {
+        match self {
+            Self::Eax => &mut entry.eax,
+            Self::Ebx => &mut entry.ebx,
+            Self::Ecx => &mut entry.ecx,
+            Self::Edx => &mut entry.edx,
+        }
+    }
+}
+impl fmt::Display for Register {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Eax => write!(f, "EAX"),
+            Self::Ebx => write!(f, "EBX"),
+            Self::Ecx => write!(f, "ECX"),
+            Self::Edx => write!(f, "EDX"),
+        }
+    }
+}
+
+/// A register described by bit flags.
+pub(crate) struct FlagsRegister {
+    pub leaf: u32,
+    pub sub_leaf: u32,
+    pub register: Register,
+    /// The name and bit of each flag, in bit order.
+    pub flags: &'static [(&'static str, u32)],
+    pub get: fn(&Cpuid) -> u32,
+    pub get_mut: fn(&mut Cpuid) -> &mut u32,
+}
+
+impl FlagsRegister {
+    /// Returns the bits a flag is defined for.
+    pub fn named_bits(&self) -> u32 {
+        self.flags.iter().fold(0, |named, (_, bit)| named | bit)
+    }
+
+    /// Returns the bits a flag is defined for, and the leaf 7 `ecx` MAWAU bits which hold a value.
+    pub fn defined_bits(&self) -> u32 {
+        let named = self.named_bits();
+        if (self.leaf, self.sub_leaf, self.register) == (7, 0, Register::Ecx) {
+            named | Leaf0x7_SubLeaf0_Ecx::MAWAU
+        } else {
+            named
+        }
+    }
+}
+
+/// The number of flags of [`FLAGS_REGISTERS`], each a bit of [`Cpuid::feature_bitmap`].
+const FLAG_COUNT: usize = {
+    let mut count = 0;
+    let mut i = 0;
+    while i < FLAGS_REGISTERS.len() {
+        count += FLAGS_REGISTERS[i].flags.len();
+        i += 1;
+    }
+    count
+};
+const _: () = assert!(FLAG_COUNT <= 256, "The flags exceed the feature bitmap");
+
+/// Every register described by bit flags, in leaf order.
+pub(crate) static FLAGS_REGISTERS: [FlagsRegister; 16] = [
+    FlagsRegister {
+        leaf: 1,
+        sub_leaf: 0,
+        register: Register::Ecx,
+        flags: Leaf0x1_SubLeaf0_Ecx::NAMES,
+        get: |cpuid| {
+            cpuid
+                .leaf0x01_process_info_and_feature_bits
+                .feature_information
+                .ecx
+                .bits
+        },
+        get_mut: |cpuid| {
+            &mut cpuid
+                .leaf0x01_process_info_and_feature_bits
+                .feature_information
+                .ecx
+                .bits
+        },
+    },
+    FlagsRegister {
+        leaf: 1,
+        sub_leaf: 0,
+        register: Register::Edx,
+        flags: Leaf0x1_SubLeaf0_Edx::NAMES,
+        get: |cpuid| {
+            cpuid
+                .leaf0x01_process_info_and_feature_bits
+                .feature_information
+                .edx
+                .bits
+        },
+        get_mut: |cpuid| {
+            &mut cpuid
+                .leaf0x01_process_info_and_feature_bits
+                .feature_information
+                .edx
+                .bits
+        },
+    },
+    FlagsRegister {
+        leaf: 6,
+        sub_leaf: 0,
+        register: Register::Eax,
+        flags: Leaf0x6_SubLeaf0_Eax::NAMES,
+        get: |cpuid| {
+            cpuid
+                .leaf0x06_thermal_and_power_management
+                .features
+                .eax
+                .bits
+        },
+        get_mut: |cpuid| {
+            &mut cpuid
+                .leaf0x06_thermal_and_power_management
+                .features
+                .eax
+                .bits
+        },
+    },
+    FlagsRegister {
+        leaf: 6,
+        sub_leaf: 0,
+        register: Register::Ecx,
+        flags: Leaf0x6_SubLeaf0_Ecx::NAMES,
+        get: |cpuid| {
+            cpuid
+                .leaf0x06_thermal_and_power_management
+                .features
+                .ecx
+                .bits
+        },
+        get_mut: |cpuid| {
+            &mut cpuid
+                .leaf0x06_thermal_and_power_management
+                .features
+                .ecx
+                .bits
+        },
+    },
+    FlagsRegister {
+        leaf: 7,
+        sub_leaf: 0,
+        register: Register::Ebx,
+        flags: Leaf0x7_SubLeaf0_Ebx::NAMES,
+        get: |cpuid| cpuid.leaf0x07_extended_features.sub_leaf0.ebx.bits,
+        get_mut: |cpuid| &mut cpuid.leaf0x07_extended_features.sub_leaf0.ebx.bits,
+    },
+    FlagsRegister {
+        leaf: 7,
+        sub_leaf: 0,
+        register: Register::Ecx,
+        flags: Leaf0x7_SubLeaf0_Ecx::NAMES,
+        get: |cpuid| cpuid.leaf0x07_extended_features.sub_leaf0.ecx.bits,
+        get_mut: |cpuid| &mut cpuid.leaf0x07_extended_features.sub_leaf0.ecx.bits,
+    },
+    FlagsRegister {
+        leaf: 7,
+        sub_leaf: 0,
+        register: Register::Edx,
+        flags: Leaf0x7_SubLeaf0_Edx::NAMES,
+        get: |cpuid| cpuid.leaf0x07_extended_features.sub_leaf0.edx.bits,
+        get_mut: |cpuid| &mut cpuid.leaf0x07_extended_features.sub_leaf0.edx.bits,
+    },
+    FlagsRegister {
+        leaf: 7,
+        sub_leaf: 1,
+        register: Register::Eax,
+        flags: Leaf0x7_SubLeaf1_Eax::NAMES,
+        get: |cpuid| cpuid.leaf0x07_extended_features.sub_leaf1.bits,
+        get_mut: |cpuid| &mut cpuid.leaf0x07_extended_features.sub_leaf1.bits,
+    },
+    FlagsRegister {
+        leaf: 0xD,
+        sub_leaf: 1,
+        register: Register::Eax,
+        flags: Leaf0xD_SubLeaf1_Eax::NAMES,
+        get: |cpuid| cpuid.leaf0x0d_cpuid_feature_bits.bits,
+        get_mut: |cpuid| &mut cpuid.leaf0x0d_cpuid_feature_bits.bits,
+    },
+    FlagsRegister {
+        leaf: 0x12,
+        sub_leaf: 0,
+        register: Register::Eax,
+        flags: Leaf0x12_SubLeaf0_Eax::NAMES,
+        get: |cpuid| cpuid.leaf0x12_cpuid_feature_bits.bits,
+        get_mut: |cpuid| &mut cpuid.leaf0x12_cpuid_feature_bits.bits,
+    },
+    FlagsRegister {
+        leaf: 0x14,
+        sub_leaf: 0,
+        register: Register::Ebx,
+        flags: Leaf0x14_SubLeaf0_Ebx::NAMES,
+        get: |cpuid| cpuid.leaf0x14_cpuid_feature_bits.bits,
+        get_mut: |cpuid| &mut cpuid.leaf0x14_cpuid_feature_bits.bits,
+    },
+    FlagsRegister {
+        leaf: 0x19,
+        sub_leaf: 0,
+        register: Register::Ebx,
+        flags: Leaf0x19_SubLeaf0_Ebx::NAMES,
+        get: |cpuid| cpuid.leaf0x19_cpuid_feature_bits.bits,
+        get_mut: |cpuid| &mut cpuid.leaf0x19_cpuid_feature_bits.bits,
+    },
+    FlagsRegister {
+        leaf: 0x8000_0001,
+        sub_leaf: 0,
+        register: Register::Edx,
+        flags: Leaf0x8000_0001_SubLeaf0_Edx::NAMES,
+        get: |cpuid| {
+            cpuid
+                .leaf0x8000_0001_highest_function_parameter_an_manufacturer_id
+                .edx
+                .bits
+        },
+        get_mut: |cpuid| {
+            &mut cpuid
+                .leaf0x8000_0001_highest_function_parameter_an_manufacturer_id
+                .edx
+                .bits
+        },
+    },
+    FlagsRegister {
+        leaf: 0x8000_0001,
+        sub_leaf: 0,
+        register: Register::Ecx,
+        flags: Leaf0x8000_0001_SubLeaf0_Ecx::NAMES,
+        get: |cpuid| {
+            cpuid
+                .leaf0x8000_0001_highest_function_parameter_an_manufacturer_id
+                .ecx
+                .bits
+        },
+        get_mut: |cpuid| {
+            &mut cpuid
+                .leaf0x8000_0001_highest_function_parameter_an_manufacturer_id
+                .ecx
+                .bits
+        },
+    },
+    FlagsRegister {
+        leaf: 0x8000_0008,
+        sub_leaf: 0,
+        register: Register::Ebx,
+        flags: Leaf0x8000_0008_SubLeaf0_Ebx::NAMES,
+        get: |cpuid| {
+            cpuid
+                .leaf0x8000_0008_virtual_and_physical_address_sizes
+                .ebx
+                .bits
+        },
+        get_mut: |cpuid| {
+            &mut cpuid
+                .leaf0x8000_0008_virtual_and_physical_address_sizes
+                .ebx
+                .bits
+        },
+    },
+    FlagsRegister {
+        leaf: 0x8000_001F,
+        sub_leaf: 0,
+        register: Register::Eax,
+        flags: Leaf0x8000_001F_SubLeaf0_Eax::NAMES,
+        get: |cpuid| cpuid.leaf0x8000_001F_cpuid_feature_bits.bits,
+        get_mut: |cpuid| &mut cpuid.leaf0x8000_001F_cpuid_feature_bits.bits,
+    },
+];
+
+/// Error type for [`Cpuid::set_feature`] and [`Cpuid::disable_feature`], no feature has the
+/// given name.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct UnknownFeature(pub String);
+impl fmt::Display for UnknownFeature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Unknown feature {}", self.0)
+    }
+}
+impl std::error::Error for UnknownFeature {}
+
+impl Cpuid {
+    /// The version of the layout of [`Cpuid::feature_bitmap`].
+    pub const FEATURE_BITMAP_VERSION: u32 = 1;
+
+    /// Returns the names of the features set, in bit order for each register in leaf order.
+    ///
+    /// Some names are defined in multiple registers (e.g. `ssbd`) so may appear more than once.
+    pub fn active_features(&self) -> impl Iterator<Item = &'static str> + '_ {
+        FLAGS_REGISTERS.iter().flat_map(move |register| {
+            let bits = (register.get)(self);
+            register
+                .flags
+                .iter()
+                .filter(move |(_, flag)| bits & flag != 0)
+                .map(|(name, _)| *name)
+        })
+    }
+
+    /// Returns the names of the features set, sorted and deduplicated.
+    ///
+    /// Unlike [`Cpuid::active_features`] names defined in multiple registers appear once, so the
+    /// lists of 2 hosts can be compared line by line.
+    #[must_use]
+    pub fn active_features_sorted(&self) -> Vec<&'static str> {
+        let mut features = self.active_features().collect::<Vec<_>>();
+        features.sort_unstable();
+        features.dedup();
+        features
+    }
+
+    /// Returns the number of feature bits set across every register described by bit flags.
+    ///
+    /// Reserved (unnamed) bits are not counted. A name defined in multiple registers (e.g.
+    /// `ssbd`) is counted once for each register it is set in, see
+    /// [`Cpuid::enabled_feature_names_count`] to count names.
+    #[must_use]
+    pub fn feature_count(&self) -> u32 {
+        FLAGS_REGISTERS
+            .iter()
+            .map(|register| ((register.get)(self) & register.named_bits()).count_ones())
+            .sum()
+    }
+
+    /// Returns the number of distinct names of the features set, the length of
+    /// [`Cpuid::active_features_sorted`].
+    #[must_use]
+    pub fn enabled_feature_names_count(&self) -> usize {
+        self.active_features_sorted().len()
+    }
+
+    /// If the feature `name` is set.
+    ///
+    /// A name defined in multiple registers is set if it is set in any of them, the only such
+    /// name is `ssbd` (leaf `0x7` EDX and leaf `0x8000_0008` EBX), use
+    /// [`Cpuid::has_feature_in_leaf`] to check a specific register.
+    #[must_use]
+    pub fn has_feature(&self, name: &str) -> bool {
+        self.active_features().any(|feature| feature == name)
+    }
+
+    /// If the feature `name` is set in leaf `leaf`.
+    #[must_use]
+    pub fn has_feature_in_leaf(&self, name: &str, leaf: u32) -> bool {
+        FLAGS_REGISTERS
+            .iter()
+            .filter(|register| register.leaf == leaf)
+            .any(|register| {
+                let bits = (register.get)(self);
+                register
+                    .flags
+                    .iter()
+                    .any(|(flag, bit)| *flag == name && bits & bit != 0)
+            })
+    }
+
+    /// Sets the feature `name` in every register defining it.
+    ///
+    /// # Errors
+    ///
+    /// When no feature has the name `name`.
+    pub fn set_feature(&mut self, name: &str) -> Result<(), UnknownFeature> {
+        self.update_feature(name, |bits, bit| *bits |= bit)
+    }
+
+    /// Clears the feature `name` in every register defining it.
+    ///
+    /// # Errors
+    ///
+    /// When no feature has the name `name`.
+    pub fn disable_feature(&mut self, name: &str) -> Result<(), UnknownFeature> {
+        self.update_feature(name, |bits, bit| *bits &= !bit)
+    }
+
+    /// Returns `self` with the feature `name` set, see [`Cpuid::set_feature`].
+    ///
+    /// # Errors
+    ///
+    /// When no feature has the name `name`.
+    pub fn with_feature(mut self, name: &str) -> Result<Self, UnknownFeature> {
+        self.set_feature(name)?;
+        Ok(self)
+    }
+
+    /// Returns `self` with the feature `name` clear, see [`Cpuid::disable_feature`].
+    ///
+    /// # Errors
+    ///
+    /// When no feature has the name `name`.
+    pub fn without_feature(mut self, name: &str) -> Result<Self, UnknownFeature> {
+        self.disable_feature(name)?;
+        Ok(self)
+    }
+
+    /// Applies `update` to the bit of the feature `name` in every register defining it.
+    fn update_feature(
+        &mut self,
+        name: &str,
+        update: impl Fn(&mut u32, u32),
+    ) -> Result<(), UnknownFeature> {
+        let mut found = false;
+        for register in &FLAGS_REGISTERS {
+            for (_, bit) in register.flags.iter().filter(|(flag, _)| *flag == name) {
+                update((register.get_mut)(self), *bit);
+                found = true;
+            }
+        }
+        if found {
+            Ok(())
+        } else {
+            Err(UnknownFeature(String::from(name)))
+        }
+    }
+
+    /// Returns a hash of the features set, equal for snapshots with the same features.
+    ///
+    /// This is the 64 bit FNV-1a hash of the registers described by bit flags in leaf order, so is
+    /// stable across runs and builds.
+    #[must_use]
+    pub fn feature_hash(&self) -> u64 {
+        FLAGS_REGISTERS
+            .iter()
+            .flat_map(|register| (register.get)(self).to_le_bytes())
+            .fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+                (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+            })
+    }
+
+    /// Returns a short printable fingerprint of the features set, the first 12 hex characters of
+    /// [`Cpuid::feature_hash`].
+    #[must_use]
+    pub fn fingerprint(&self) -> String {
+        format!("{:016x}", self.feature_hash())[..12].to_string()
+    }
+
+    /// Returns the features set packed as a fixed width bit vector, for clustering hosts.
+    ///
+    /// Each named flag of the registers described by bit flags is assigned the next bit, taking
+    /// the flags of each register in bit order and the registers in leaf order (the order of
+    /// [`Cpuid::active_features`]). Bit `i` is bit `i % 8` of byte `i / 8`, the unused high bits
+    /// are zero. This layout is [`Cpuid::FEATURE_BITMAP_VERSION`], newly named flags change it.
+    #[must_use]
+    pub fn feature_bitmap(&self) -> [u8; 32] {
+        let mut bitmap = [0; 32];
+        let flags = FLAGS_REGISTERS.iter().flat_map(|register| {
+            let bits = (register.get)(self);
+            register.flags.iter().map(move |(_, flag)| bits & flag != 0)
+        });
+        for (i, set) in flags.enumerate() {
+            bitmap[i / 8] |= u8::from(set) << (i % 8);
+        }
+        bitmap
+    }
+
+    /// Constructs [`Cpuid`] with the features set in `bitmap`, the inverse of
+    /// [`Cpuid::feature_bitmap`]. Every other value is zero.
+    #[must_use]
+    pub fn from_feature_bitmap(bitmap: &[u8; 32]) -> Self {
+        let mut cpuid = Self::default();
+        let mut i = 0;
+        for register in &FLAGS_REGISTERS {
+            let bits = (register.get_mut)(&mut cpuid);
+            for (_, flag) in register.flags {
+                if bitmap[i / 8] & (1 << (i % 8)) != 0 {
+                    *bits |= flag;
+                }
+                i += 1;
+            }
+        }
+        cpuid
+    }
+
+    /// Returns what a host must provide to run `self` as a guest, for storing in a migration
+    /// manifest and later checking with [`Cpuid::satisfies`].
+    ///
+    /// This is every feature of [`Cpuid::active_features`] followed by the minimum of each scalar
+    /// value [`Cpuid::covers`] compares, formatted as `"<name>>=<value>"` e.g.
+    /// `"phys_addr_bits>=39"`.
+    #[must_use]
+    pub fn required_features(&self) -> Vec<String> {
+        self.active_features()
+            .map(String::from)
+            .chain(
+                self.scalar_minimums()
+                    .into_iter()
+                    .map(|(name, value)| format!("{name}>={value}")),
+            )
+            .collect()
+    }
+
+    /// If `self` provides every requirement in `required`, see [`Cpuid::required_features`].
+    ///
+    /// Unknown requirements are not satisfied.
+    pub fn satisfies<S: AsRef<str>>(&self, required: &[S]) -> bool {
+        let minimums = self.scalar_minimums();
+        required
+            .iter()
+            .map(AsRef::as_ref)
+            .all(|requirement| match requirement.split_once(">=") {
+                Some((name, value)) => {
+                    let provided = minimums.iter().find(|(minimum, _)| *minimum == name);
+                    matches!(
+                        (provided, value.parse::<u32>()),
+                        (Some((_, provided)), Ok(value)) if *provided >= value
+                    )
+                }
+                None => self.has_feature(requirement),
+            })
+    }
+
+    /// Masks the feature bits of `raw` with those of `self`, such that `raw` provides no feature
+    /// beyond `self`.
+    ///
+    /// Only the registers described by bit flags are masked, every other register (and any leaf
+    /// `self` does not describe) is left as is.
+    pub fn mask_raw(&self, raw: &mut RawCpuid) {
+        for entry in raw.iter_mut() {
+            let key = (entry.function, entry.index);
+            for register in FLAGS_REGISTERS
+                .iter()
+                .filter(|register| (register.leaf, register.sub_leaf) == key)
+            {
+                *register.register.of(entry) &= (register.get)(self);
+            }
+        }
+    }
+
+    /// The scalar values [`Cpuid::covers`] requires to be greater than or equal.
+    pub(crate) fn scalar_minimums(&self) -> [(&'static str, u32); 8] {
+        let leaf0x8000_0008 = &self.leaf0x8000_0008_virtual_and_physical_address_sizes;
+        [
+            (
+                "max_leaf",
+                self.leaf0x00_highest_function_parameter_an_manufacturer_id
+                    .highest_calling_parameter,
+            ),
+            (
+                "max_logical_processor_ids",
+                u32::from(
+                    self.leaf0x01_process_info_and_feature_bits
+                        .additional_information
+                        .maximum_addressable_logical_processor_ids,
+                ),
+            ),
+            (
+                "interrupt_thresholds",
+                u32::from(
+                    self.leaf0x06_thermal_and_power_management
+                        .number_of_interrupt_thresholds
+                        .number_of_interrupt_thresholds(),
+                ),
+            ),
+            (
+                "phys_addr_bits",
+                u32::from(leaf0x8000_0008.eax.number_of_physical_address_bits()),
+            ),
+            (
+                "linear_addr_bits",
+                u32::from(leaf0x8000_0008.eax.number_of_linear_address_bits()),
+            ),
+            (
+                "physical_cores_minus_1",
+                u32::from(leaf0x8000_0008.ecx.number_of_physical_cores_minus_1()),
+            ),
+            (
+                "log2_max_apic_id",
+                u32::from(leaf0x8000_0008.ecx.log2_of_maximum_apic_id()),
+            ),
+            (
+                "tsc_size",
+                u32::from(leaf0x8000_0008.ecx.performance_timestamp_counter_size()),
+            ),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::arch::x86_64::CpuidResult;
+
+    use super::*;
+
+    #[test]
+    fn with_feature() {
+        let host = Cpuid::new();
+        let template = host
+            .clone()
+            .with_feature("avx2")
+            .unwrap()
+            .with_feature("ssbd")
+            .unwrap()
+            .without_feature("avx512_f")
+            .unwrap()
+            .without_feature("sse3")
+            .unwrap();
+        assert!(template.has_feature("avx2"));
+        assert!(template.has_feature_in_leaf("ssbd", 7));
+        assert!(template.has_feature_in_leaf("ssbd", 0x8000_0008));
+        assert!(!template.has_feature("avx512_f"));
+        assert!(!template.has_feature("sse3"));
+        assert_eq!(
+            template.clone().without_feature("avx3"),
+            Err(UnknownFeature(String::from("avx3")))
+        );
+
+        let mut cpuid = template.clone();
+        cpuid.set_feature("sse3").unwrap();
+        cpuid.disable_feature("avx2").unwrap();
+        assert!(cpuid.has_feature("sse3") && !cpuid.has_feature("avx2"));
+        assert_eq!(
+            cpuid.set_feature("avx3"),
+            Err(UnknownFeature(String::from("avx3")))
+        );
+    }
+    #[test]
+    fn required_features() {
+        let mut host = Cpuid::default().with_manufacturer("GenuineIntel").unwrap();
+        host.leaf0x01_process_info_and_feature_bits
+            .feature_information
+            .edx = Leaf0x1_SubLeaf0_Edx::fpu | Leaf0x1_SubLeaf0_Edx::sse;
+        host.leaf0x07_extended_features.sub_leaf0.ebx = Leaf0x7_SubLeaf0_Ebx::avx2;
+        host.leaf0x8000_0008_virtual_and_physical_address_sizes
+            .eax
+            .set_number_of_physical_address_bits(40);
+        let mut template = host.clone();
+        template.leaf0x07_extended_features.sub_leaf0.ebx = Leaf0x7_SubLeaf0_Ebx::empty();
+        template
+            .leaf0x8000_0008_virtual_and_physical_address_sizes
+            .eax
+            .set_number_of_physical_address_bits(39);
+        assert!(host.covers(&template));
+
+        let required = template.required_features();
+        assert!(required
+            .iter()
+            .any(|feature| feature == "phys_addr_bits>=39"));
+        let active = host.active_features().collect::<Vec<_>>();
+        assert!(required
+            .iter()
+            .filter(|feature| !feature.contains(">="))
+            .all(|feature| active.contains(&feature.as_str())));
+        assert!(required.iter().any(|feature| feature == "fpu"));
+        assert!(!required.iter().any(|feature| feature == "avx2"));
+        assert!(host.satisfies(&required));
+
+        // A host lacking a required feature does not satisfy it.
+        let mut lacking = host.clone();
+        lacking
+            .leaf0x01_process_info_and_feature_bits
+            .feature_information
+            .edx = Leaf0x1_SubLeaf0_Edx::empty();
+        assert!(!lacking.satisfies(&["fpu"]));
+        assert!(!host.satisfies(&["phys_addr_bits>=1000"]));
+        assert!(!host.satisfies(&["not_a_feature"]));
+    }
+    #[test]
+    fn active_features_sorted() {
+        let mut cpuid = Cpuid::default();
+        cpuid
+            .leaf0x01_process_info_and_feature_bits
+            .feature_information
+            .edx = Leaf0x1_SubLeaf0_Edx::sse | Leaf0x1_SubLeaf0_Edx::fpu;
+        cpuid.leaf0x07_extended_features.sub_leaf0.edx = Leaf0x7_SubLeaf0_Edx::ssbd;
+        cpuid.leaf0x8000_0008_virtual_and_physical_address_sizes.ebx =
+            Leaf0x8000_0008_SubLeaf0_Ebx::ssbd;
+        assert_eq!(
+            cpuid.active_features().collect::<Vec<_>>(),
+            ["fpu", "sse", "ssbd", "ssbd"]
+        );
+        assert_eq!(cpuid.active_features_sorted(), ["fpu", "ssbd", "sse"]);
+    }
+    #[test]
+    fn feature_count() {
+        let zeroed = Cpuid::default();
+        assert_eq!(zeroed.feature_count(), 0);
+        assert_eq!(zeroed.enabled_feature_names_count(), 0);
+
+        let full = Cpuid::from_fn(|_, _| CpuidResult {
+            eax: u32::MAX,
+            ebx: u32::MAX,
+            ecx: u32::MAX,
+            edx: u32::MAX,
+        });
+        let flags = FLAGS_REGISTERS
+            .iter()
+            .map(|register| register.flags.len())
+            .sum::<usize>();
+        assert_eq!(full.feature_count() as usize, flags);
+        // `ssbd` is named in 2 registers.
+        assert_eq!(full.enabled_feature_names_count(), flags - 1);
+    }
+    #[test]
+    fn feature_bitmap() {
+        let cpuid = Cpuid::new();
+        let bitmap = cpuid.feature_bitmap();
+        let from_bitmap = Cpuid::from_feature_bitmap(&bitmap);
+        assert_eq!(from_bitmap.feature_bitmap(), bitmap);
+        assert_eq!(
+            from_bitmap.active_features_sorted(),
+            cpuid.active_features_sorted()
+        );
+
+        // Feature-equal snapshots share a bitmap.
+        let mut equal = cpuid.clone();
+        equal.normalize();
+        equal
+            .leaf0x8000_0008_virtual_and_physical_address_sizes
+            .eax
+            .set_number_of_physical_address_bits(39);
+        assert_eq!(equal.feature_bitmap(), bitmap);
+
+        // `sse3` is the first flag and `ssbd` of leaf 7 `edx` the 151st.
+        let mut cpuid = Cpuid::from_feature_bitmap(&[0; 32]);
+        cpuid
+            .leaf0x01_process_info_and_feature_bits
+            .feature_information
+            .ecx = Leaf0x1_SubLeaf0_Ecx::sse3;
+        cpuid.leaf0x07_extended_features.sub_leaf0.edx = Leaf0x7_SubLeaf0_Edx::ssbd;
+        let mut expected = [0; 32];
+        expected[0] = 1;
+        expected[150 / 8] = 1 << (150 % 8);
+        assert_eq!(cpuid.feature_bitmap(), expected);
+    }
+    #[test]
+    fn fingerprint() {
+        let cpuid = Cpuid::new();
+        let fingerprint = cpuid.fingerprint();
+        assert_eq!(fingerprint.len(), 12);
+
+        // Snapshots differing only outside the feature bits share a fingerprint.
+        let mut equal = cpuid.clone();
+        equal.normalize();
+        equal
+            .leaf0x8000_0008_virtual_and_physical_address_sizes
+            .eax
+            .set_number_of_physical_address_bits(39);
+        assert_eq!(equal.fingerprint(), fingerprint);
+
+        let mut different = cpuid;
+        different.leaf0x07_extended_features.sub_leaf0.ebx ^= Leaf0x7_SubLeaf0_Ebx::avx2;
+        assert_ne!(different.fingerprint(), fingerprint);
+    }
+    #[test]
+    fn has_feature() {
+        // `ssbd` is the only name defined in multiple registers.
+        let mut names = FLAGS_REGISTERS
+            .iter()
+            .flat_map(|register| register.flags.iter().map(|(name, _)| *name))
+            .collect::<Vec<_>>();
+        names.sort_unstable();
+        let duplicates = names
+            .windows(2)
+            .filter(|pair| pair[0] == pair[1])
+            .map(|pair| pair[0])
+            .collect::<Vec<_>>();
+        assert_eq!(duplicates, ["ssbd"]);
+
+        let mut cpuid = Cpuid::default();
+        assert!(!cpuid.has_feature("ssbd"));
+        cpuid.leaf0x07_extended_features.sub_leaf0.edx = Leaf0x7_SubLeaf0_Edx::ssbd;
+        assert!(cpuid.has_feature("ssbd"));
+        assert!(cpuid.has_feature_in_leaf("ssbd", 0x7));
+        assert!(!cpuid.has_feature_in_leaf("ssbd", 0x8000_0008));
+
+        cpuid.leaf0x07_extended_features.sub_leaf0.edx = Leaf0x7_SubLeaf0_Edx::empty();
+        cpuid.leaf0x8000_0008_virtual_and_physical_address_sizes.ebx =
+            Leaf0x8000_0008_SubLeaf0_Ebx::ssbd;
+        assert!(cpuid.has_feature("ssbd"));
+        assert!(!cpuid.has_feature_in_leaf("ssbd", 0x7));
+        assert!(cpuid.has_feature_in_leaf("ssbd", 0x8000_0008));
+    }
+    #[test]
+    fn waitpkg() {
+        let host = Cpuid::new();
+        let mut template = host.clone();
+        template.leaf0x07_extended_features.sub_leaf0.ecx |= Leaf0x7_SubLeaf0_Ecx::waitpkg;
+        assert!(template.has_feature("waitpkg"));
+        assert!(template
+            .active_features()
+            .any(|feature| feature == "waitpkg"));
+
+        let mut lacking = host;
+        lacking.leaf0x07_extended_features.sub_leaf0.ecx &= !Leaf0x7_SubLeaf0_Ecx::waitpkg;
+        assert!(!lacking.has_feature("waitpkg"));
+        assert!(!lacking
+            .active_features()
+            .any(|feature| feature == "waitpkg"));
+        // A template requiring `waitpkg` is rejected by a host lacking it.
+        assert!(!lacking.covers(&template));
+        assert!(template.covers(&lacking));
+    }
+    #[test]
+    fn contains_name() {
+        let ecx = Leaf0x1_SubLeaf0_Ecx::avx | Leaf0x1_SubLeaf0_Ecx::sse3;
+        assert_eq!(ecx.contains_name("avx"), Some(true));
+        assert_eq!(ecx.contains_name("aes"), Some(false));
+        assert_eq!(ecx.contains_name("avx2"), None);
+        assert_eq!(
+            Leaf0x1_SubLeaf0_Ecx::empty().contains_name("avx"),
+            Some(false)
+        );
+    }
+    #[test]
+    fn mask_raw() {
+        let mut raw = RawCpuid::from(vec![
+            RawCpuidEntry::new(1, 0, 0, 0x1234, 0x5678, u32::MAX, u32::MAX),
+            RawCpuidEntry::new(7, 0, 0, 1, u32::MAX, 0, 0),
+            RawCpuidEntry::new(0x4000_0000, 0, 0, 1, 2, 3, 4),
+        ]);
+        let mut template = Cpuid::new();
+        let feature_information = &mut template
+            .leaf0x01_process_info_and_feature_bits
+            .feature_information;
+        feature_information.ecx = Leaf0x1_SubLeaf0_Ecx::sse3 | Leaf0x1_SubLeaf0_Ecx::avx;
+        feature_information.edx = Leaf0x1_SubLeaf0_Edx::fpu;
+        template.leaf0x07_extended_features.sub_leaf0.ebx = Leaf0x7_SubLeaf0_Ebx::avx2;
+        template.mask_raw(&mut raw);
+
+        assert_eq!(
+            raw.iter().cloned().collect::<Vec<_>>(),
+            [
+                // Only the flag registers are masked.
+                RawCpuidEntry::new(
+                    1,
+                    0,
+                    0,
+                    0x1234,
+                    0x5678,
+                    (Leaf0x1_SubLeaf0_Ecx::sse3 | Leaf0x1_SubLeaf0_Ecx::avx).bits(),
+                    Leaf0x1_SubLeaf0_Edx::fpu.bits()
+                ),
+                RawCpuidEntry::new(7, 0, 0, 1, Leaf0x7_SubLeaf0_Ebx::avx2.bits(), 0, 0),
+                // Leaves not described are left as is.
+                RawCpuidEntry::new(0x4000_0000, 0, 0, 1, 2, 3, 4),
+            ]
+        );
+    }
+}
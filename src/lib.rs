@@ -5,7 +5,6 @@
     clippy::similar_names,
     clippy::unsafe_derive_deserialize
 )]
-#![feature(vec_into_raw_parts)]
 //! Example
 //! ```ignore
 //! use test_cpuid::Cpuid;
@@ -18,9 +17,10 @@
 //! let _register0_0_ecx = cpuid.leaf::<0>().sub_leaf::<0>().ecx();
 //! let _register0_0_edx = cpuid.leaf::<0>().sub_leaf::<0>().edx();
 //! ```
-//! Bit flags are serialized in a little endian format e.g.
+//! Bit flags are serialized as a binary string, most significant bit first (the 31st bit is the
+//! leftmost character and the 0th bit the rightmost) with an `_` between each nibble, e.g.
 //! ```ignore
-//! biflags! {
+//! bitflags! {
 //!     pub struct MyBitFlags: u32 {
 //!         const one = 1 << 0;
 //!         const two = 1 << 1;
@@ -30,22 +30,56 @@
 //!     }
 //! }
 //! let my_bit_flags = MyBitFlags { bits: 0b0010_0000_0000_0000_0000_0010_0000_0111 };
-//! assert_eq!("00100000000000000000001000000111",serde_json::to_string(&my_bit_flags).unwrap());
+//! assert_eq!(
+//!     "\"0010_0000_0000_0000_0000_0010_0000_0111\"",
+//!     serde_json::to_string(&my_bit_flags).unwrap()
+//! );
 //! ```
 
-use core::arch::x86_64::{CpuidResult, __cpuid, __cpuid_count};
-use std::collections::HashMap;
-use std::mem::transmute;
+use core::arch::x86_64::{__cpuid_count, _mm_lfence, _mm_mfence, CpuidResult};
+use std::collections::{BTreeMap, HashMap};
+use std::convert::Infallible;
 use std::{fmt, str};
 mod bitflags_util;
 
+#[cfg(feature = "arbitrary")]
+mod arbitrary;
+mod baseline;
+mod brand;
+mod cache;
 mod cpuid_ffi;
+mod cpuinfo;
+mod diff;
+mod features;
+mod libvirt;
+mod merge;
+mod microarchitecture;
+mod msr;
+mod options;
+mod patch;
+mod preset;
+mod report;
+mod snapshot;
+mod summary;
+mod template;
+#[cfg(feature = "serde_yaml")]
+mod yaml;
+pub use baseline::*;
 use bitflags::bitflags;
 #[allow(clippy::wildcard_imports)]
 use bitflags_util::*;
+pub use cache::*;
 pub use cpuid_ffi::*;
+pub use diff::*;
+pub use features::UnknownFeature;
+#[cfg(not(feature = "tracing"))]
 use log_derive::{logfn, logfn_inputs};
+pub use merge::*;
+pub use options::*;
+pub use preset::*;
 use serde::{Deserialize, Serialize};
+pub use summary::*;
+pub use template::*;
 // -----------------------------------------------------------------------------
 // Bit flag definitions
 // -----------------------------------------------------------------------------
@@ -133,7 +167,7 @@ bitflags! {
         const digital_thermal_sensor_capability =           1 << 0;
         const intel_turbo_boost_technology_capability =     1 << 1;
         const always_running_apic_timer_capability =        1 << 2;
-        // 4th bit reserved
+        // 3rd bit reserved
         const power_limit_notification_capability =         1 << 4;
         const extended_clock_modulation_duty_capability =   1 << 5;
         const package_thermal_management_capability =       1 << 6;
@@ -198,6 +232,7 @@ bitflags! {
         const umip =                1 << 2;
         const pku =                 1 << 3;
         const ospke =               1 << 4;
+        /// `UMONITOR`, `UMWAIT` and `TPAUSE`, implying the `IA32_UMWAIT_CONTROL` MSR (`0xE1`).
         const waitpkg =             1 << 5;
         const avx512_vbmi2 =        1 << 6;
         const cet_ss =              1 << 7;
@@ -226,8 +261,8 @@ bitflags! {
     #[derive(Serialize, Deserialize)]
     #[repr(C)]
     pub struct Leaf0x7_SubLeaf0_Edx: u32 {
+        // 0th bit reserved
         // 1st bit reserved
-        // 2nd bit reserved
         const avx512_4vnniw = 1 << 2;
         const avx512_4fmaps = 1 << 3;
         const fsrm = 1 << 4;
@@ -361,6 +396,7 @@ bitflags! {
         const tce =             1 << 17;
         // 18th bit reserved
         const nodeid_msr =      1 << 19;
+        // 20th bit reserved
         const tbm =             1 << 21;
         const topoext =         1 << 22;
         const perfctr_core =    1 << 23;
@@ -399,7 +435,7 @@ bitflags! {
         const ssbd = 1 << 24;
         const virt_ssbd = 1 << 25;
         const ssb_no = 1 << 26;
-        //26th to 31st bits reserved.
+        // 27th to 31st bits reserved.
     }
     /// <https://en.wikipedia.org/wiki/CPUID#EAX=8000001Fh>
     #[derive(Serialize, Deserialize)]
@@ -477,6 +513,16 @@ impl Leaf0x8000_001F_SubLeaf0_Eax {
 // Bit flag registers impls
 // -----------------------------------------------------------------------------
 
+impl Leaf0x7_SubLeaf0_Ecx {
+    /// The MAWAU bits, 17th to 21st, which hold a value rather than flags.
+    const MAWAU: u32 = 0b0000_0000_0011_1110_0000_0000_0000_0000;
+
+    /// Returns the MPX address-width adjust (MAWAU) used by `BNDLDX` and `BNDSTX` in 64-bit mode.
+    #[must_use]
+    pub fn mawau(&self) -> u8 {
+        ((self.bits & Self::MAWAU) >> 17) as u8
+    }
+}
 impl Leaf0x7_SubLeaf1_Eax {
     #[must_use]
     pub fn eax(&self) -> u32 {
@@ -514,6 +560,32 @@ impl Leaf0x8000_001F_SubLeaf0_Eax {
     }
 }
 
+/// Reads a single leaf with the `cpuid` instruction, returning a
+/// [`CpuidResult`](core::arch::x86_64::CpuidResult), without constructing a whole [`Cpuid`].
+///
+/// The sub-leaf defaults to `0` when omitted.
+///
+/// ```
+/// use test_cpuid::cpuid;
+/// let ecx = cpuid!(leaf = 1, sub_leaf = 0).ecx;
+/// let eax = cpuid!(0x8000_0008).eax;
+/// ```
+#[macro_export]
+macro_rules! cpuid {
+    (leaf = $leaf:expr, sub_leaf = $sub_leaf:expr $(,)?) => {
+        ::core::arch::x86_64::__cpuid_count($leaf, $sub_leaf)
+    };
+    (leaf = $leaf:expr $(,)?) => {
+        $crate::cpuid!(leaf = $leaf, sub_leaf = 0)
+    };
+    ($leaf:expr, $sub_leaf:expr $(,)?) => {
+        $crate::cpuid!(leaf = $leaf, sub_leaf = $sub_leaf)
+    };
+    ($leaf:expr $(,)?) => {
+        $crate::cpuid!(leaf = $leaf, sub_leaf = 0)
+    };
+}
+
 // -----------------------------------------------------------------------------
 // Cpuid definition
 // -----------------------------------------------------------------------------
@@ -527,6 +599,8 @@ pub struct Cpuid {
         HighestFunctionParameterAndManufacturerID,
     /// leaf 1
     pub leaf0x01_process_info_and_feature_bits: ProcessorInfoAndFeatureBits,
+    /// leaf 2
+    pub leaf0x02_cache_and_tlb: Leaf2CacheAndTlb,
     /// leaf 6
     pub leaf0x06_thermal_and_power_management: ThermalAndPowerManagement,
     /// leaf 7
@@ -540,123 +614,495 @@ pub struct Cpuid {
     /// leaf 20 / 0x14h
     #[serde(with = "k")]
     pub leaf0x14_cpuid_feature_bits: Leaf0x14_SubLeaf0_Ebx,
+    /// leaf 23 / 0x17h
+    pub leaf0x17_soc_vendor_attributes: SocVendorAttributes,
     /// leaf 25 / 0x19h
     #[serde(with = "l")]
     pub leaf0x19_cpuid_feature_bits: Leaf0x19_SubLeaf0_Ebx,
-    /// leaf 0x8000_0001
+    /// leaf `0x8000_0001`
     pub leaf0x8000_0001_highest_function_parameter_an_manufacturer_id:
         ExtendedProcessorInfoAndFeatureBits,
-    /// leaf 0x8000_0008
+    /// leaves `0x8000_0002` to `0x8000_0004`
+    pub leaf0x8000_0002_brand_string: BrandString,
+    /// leaf `0x8000_0008`
     pub leaf0x8000_0008_virtual_and_physical_address_sizes: VirtualAndPhysicalAddressSizes,
-    /// leaf 0x8000_001F
+    /// leaf `0x8000_001F`
     #[serde(with = "p")]
     pub leaf0x8000_001F_cpuid_feature_bits: Leaf0x8000_001F_SubLeaf0_Eax,
-    /// To allow usability beyond what is immediately considered at the moment, for leafs we do not 
+    /// To allow usability beyond what is immediately considered at the moment, for leafs we do not
     /// explicitly describe we store them here in the format `<(leaf,subleaf),(eax,ebx,ecx,edx)>`.
+    #[serde(with = "misc_mod")]
     pub misc: HashMap<(u32, u32), (u32, u32, u32, u32)>,
+    /// The leaves of [`Cpuid::LEAVES`] which were zero-filled rather than queried, bit `i` set
+    /// for `Cpuid::LEAVES[i]`, see [`Cpuid::leaf_present`].
+    #[serde(default)]
+    pub(crate) absent_leaves: u16,
 }
+// Each leaf of `Cpuid::LEAVES` has a bit of `Cpuid::absent_leaves`.
+const _: () = assert!(Cpuid::LEAVES.len() <= u16::BITS as usize);
 impl Cpuid {
+    /// The size in bytes of each leaf in the byte form of [`Cpuid`], see [`Cpuid::to_bytes`].
+    pub const ENTRY_BYTES: usize = 6 * 4;
+    /// The AMD extended APIC id leaf, `eax` holding the extended APIC id.
+    const EXTENDED_APIC_ID_LEAF: u32 = 0x8000_001E;
+    /// The extended topology enumeration leaves, `edx` holding the x2APIC id.
+    const EXTENDED_TOPOLOGY_LEAVES: [u32; 2] = [0xB, 0x1F];
+    /// The leaves [`Cpuid`] describes, excluding those in [`Cpuid::misc`].
+    pub const LEAVES: [u32; 16] = [
+        0x0,
+        0x1,
+        0x2,
+        0x6,
+        0x7,
+        0xD,
+        0x12,
+        0x14,
+        0x17,
+        0x19,
+        0x8000_0001,
+        0x8000_0002,
+        0x8000_0003,
+        0x8000_0004,
+        0x8000_0008,
+        0x8000_001F,
+    ];
+    /// The topology leaves [`Cpuid::misc`] may hold.
+    const TOPOLOGY_LEAVES: [u32; 3] = [
+        Self::EXTENDED_TOPOLOGY_LEAVES[0],
+        Self::EXTENDED_TOPOLOGY_LEAVES[1],
+        Self::EXTENDED_APIC_ID_LEAF,
+    ];
+
+    /// Constructs [`Cpuid`] from the host, see [`HostCpuidReader`].
+    ///
+    /// Unlike [`Cpuid::default`], which is zeroed, this executes `cpuid`. Leaves above the highest
+    /// leaf reported are queried regardless, see [`Cpuid::try_new`].
+    ///
+    /// Some CPUs and hypervisors report `0xFFFF_FFFF` in every register for unsupported leaves,
+    /// which would otherwise decode as every feature set. As a heuristic, a leaf reporting this
+    /// which is above the highest leaf reported by leaf `0` (basic leaves) or `0x8000_0000`
+    /// (extended leaves) is zero-filled and not present (see [`Cpuid::leaf_present`]). Leaf
+    /// `0x8000_0000` is only queried for this when an extended leaf reports all ones. Constructing
+    /// from given results (e.g. [`Cpuid::from_fn`]) does not apply this.
     #[must_use]
     pub fn new() -> Self {
-        Self::default()
+        let Ok(cpuid) = Self::read_live(&mut HostCpuidReader, |reader| Self::from_reader(reader));
+        cpuid
+    }
+
+    /// Identical to [`Cpuid::new`] but fences each query (see [`SerializingHostCpuidReader`]).
+    #[must_use]
+    pub fn new_serializing() -> Self {
+        let Ok(cpuid) = Self::read_live(&mut SerializingHostCpuidReader, |reader| {
+            Self::from_reader(reader)
+        });
+        cpuid
+    }
+
+    /// Constructs [`Cpuid`] with `read` from the `cpuid` of a live CPU read by `reader`,
+    /// zero-filling the leaves reporting all ones above the highest leaf, see [`Cpuid::new`].
+    fn read_live<R: CpuidReader>(
+        reader: &mut R,
+        read: impl FnOnce(&mut SentinelCpuidReader<'_, R>) -> Result<Self, R::Error>,
+    ) -> Result<Self, R::Error> {
+        let mut sentinel = SentinelCpuidReader {
+            reader,
+            highest_basic_leaf: None,
+            highest_extended_leaf: None,
+            zeroed: Vec::new(),
+        };
+        let mut cpuid = read(&mut sentinel)?;
+        cpuid.mark_absent(&sentinel.zeroed);
+        Ok(cpuid)
+    }
+
+    /// Marks the leaves of [`Cpuid::LEAVES`] in `zeroed` as not present, see
+    /// [`Cpuid::leaf_present`].
+    fn mark_absent(&mut self, zeroed: &[u32]) {
+        for (i, leaf) in Self::LEAVES.iter().enumerate() {
+            if zeroed.contains(leaf) {
+                self.absent_leaves |= 1 << i;
+            }
+        }
+    }
+
+    /// Executes `cpuid` for leaf `LEAF` sub-leaf `SUB` on the host, without constructing a whole
+    /// [`Cpuid`].
+    ///
+    /// This is the counterpart of [`cpuid!`] with the leaf and sub-leaf fixed at compile time.
+    #[must_use]
+    pub fn query_single<const LEAF: u32, const SUB: u32>() -> CpuidResult {
+        __cpuid_count(LEAF, SUB)
+    }
+
+    /// Zeroes the fields which depend on which CPU ran the query, leaving the capabilities
+    /// intact, such that snapshots from different CPUs of the same host compare equal.
+    ///
+    /// This zeroes the local APIC id and, for any topology leaves in [`Cpuid::misc`] (`0xB`,
+    /// `0x1F` and `0x8000_001E`), the x2APIC id.
+    pub fn normalize(&mut self) {
+        self.leaf0x01_process_info_and_feature_bits
+            .additional_information
+            .local_apic_id = 0;
+        for (&(leaf, _), (eax, _, _, edx)) in &mut self.misc {
+            if Self::EXTENDED_TOPOLOGY_LEAVES.contains(&leaf) {
+                *edx = 0;
+            } else if leaf == Self::EXTENDED_APIC_ID_LEAF {
+                *eax = 0;
+            }
+        }
+    }
+
+    /// Zeroes the fields describing the host topology, leaving the capabilities intact, such
+    /// that a migratable guest template does not bake in the topology of the host it came from
+    /// and its [`Cpuid::covers`] does not compare topology.
+    ///
+    /// This zeroes:
+    /// - The local APIC id and the maximum addressable logical processor ids (leaf 1 `ebx` bits 16
+    ///   to 31).
+    /// - The number of physical cores and the log2 of the maximum APIC id (leaf `0x8000_0008` `ecx`
+    ///   bits 0 to 7 and 12 to 15).
+    ///
+    /// And removes the topology leaves in [`Cpuid::misc`] (`0xB`, `0x1F` and `0x8000_001E`).
+    pub fn strip_topology(&mut self) {
+        let additional = &mut self
+            .leaf0x01_process_info_and_feature_bits
+            .additional_information;
+        additional.local_apic_id = 0;
+        additional.maximum_addressable_logical_processor_ids = 0;
+        let ecx = &mut self.leaf0x8000_0008_virtual_and_physical_address_sizes.ecx;
+        ecx.0 &= !0b0000_0000_0000_0000_1111_0000_1111_1111;
+        self.misc
+            .retain(|(leaf, _), _| !Self::TOPOLOGY_LEAVES.contains(leaf));
+    }
+
+    /// If `self` and `other` are equal except in the fields in `ignore`, such that each consumer
+    /// can define which differences it accepts.
+    #[must_use]
+    pub fn eq_ignoring(&self, other: &Self, ignore: IgnoreFields) -> bool {
+        let strip = |cpuid: &Self| {
+            let mut cpuid = cpuid.clone();
+            let additional = &mut cpuid
+                .leaf0x01_process_info_and_feature_bits
+                .additional_information;
+            if ignore.contains(IgnoreFields::APIC_ID) {
+                additional.local_apic_id = 0;
+            }
+            if ignore.contains(IgnoreFields::BRAND_STRING) {
+                additional.brand_index = 0;
+                cpuid.leaf0x17_soc_vendor_attributes.brand_string = FixedString::default();
+                cpuid.leaf0x8000_0002_brand_string.brand_string = FixedString::default();
+            }
+            if ignore.contains(IgnoreFields::TOPOLOGY) {
+                additional.maximum_addressable_logical_processor_ids = 0;
+            }
+            cpuid.misc.retain(|&(leaf, _), _| {
+                !(ignore.contains(IgnoreFields::TOPOLOGY) && Self::TOPOLOGY_LEAVES.contains(&leaf)
+                    || ignore.contains(IgnoreFields::FREQUENCY) && matches!(leaf, 0x15 | 0x16))
+            });
+            cpuid
+        };
+        strip(self) == strip(other)
+    }
+
+    /// If `self` and `other` are equal except in the local APIC id, see [`Cpuid::eq_ignoring`].
+    #[must_use]
+    pub fn feature_eq(&self, other: &Self) -> bool {
+        self.eq_ignoring(other, IgnoreFields::APIC_ID)
     }
 
     // If the feature set of `self` covers the feature set of `other`.
-    #[logfn(Trace)]
-    #[logfn_inputs(Info)]
+    #[cfg_attr(not(feature = "tracing"), logfn(Trace), logfn_inputs(Info))]
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", ret))]
     pub fn covers(&self, other: &Self) -> bool {
-        // We first check they have the same manufacturer
+        // Leaf 0 is first, so we first check they have the same manufacturer
+        self.covers_leaves(other, &Self::LEAVES)
+    }
+
+    /// If guests can migrate in both directions between `self` and `other`, mutual
+    /// [`Cpuid::covers`].
+    ///
+    /// This uses the semantics of [`Cpuid::covers`] rather than equality, so fields `covers` does
+    /// not compare (e.g. the local APIC id) are ignored. As each scalar must be `>=` in both
+    /// directions the compared scalars end up equal.
+    #[must_use]
+    pub fn migration_compatible(&self, other: &Self) -> bool {
+        self.covers(other) && other.covers(self)
+    }
+
+    /// Identical to [`Cpuid::covers`] but only compares the leaves in `leaves`, such that
+    /// differences in other leaves (e.g. topology) do not prevent coverage.
+    ///
+    /// Leaves [`Cpuid`] does not describe (see [`Cpuid::LEAVES`]) are skipped. With the `tracing`
+    /// feature each leaf is compared in a `covers_leaf` span with the leaf as its `leaf` field.
+    #[must_use]
+    pub fn covers_leaves(&self, other: &Self, leaves: &[u32]) -> bool {
+        leaves.iter().all(|leaf| {
+            #[cfg(feature = "tracing")]
+            let _span = tracing::trace_span!("covers_leaf", leaf).entered();
+            self.covers_leaf(other, *leaf)
+        })
+    }
+
+    /// Returns how each leaf [`Cpuid`] describes (see [`Cpuid::LEAVES`]) of `self` compares to
+    /// that of `other`, comparing with [`Cpuid::covers_leaves`] in both directions.
+    ///
+    /// `self` covers `other` when no leaf is [`LeafOrdering::Covered`] or
+    /// [`LeafOrdering::Incomparable`], and guests can migrate in both directions when every leaf
+    /// is [`LeafOrdering::Equal`].
+    #[must_use]
+    pub fn compare_verbose(&self, other: &Self) -> Vec<(u32, LeafOrdering)> {
+        Self::LEAVES
+            .iter()
+            .map(|leaf| {
+                let ordering = match (
+                    self.covers_leaf(other, *leaf),
+                    other.covers_leaf(self, *leaf),
+                ) {
+                    (true, true) => LeafOrdering::Equal,
+                    (true, false) => LeafOrdering::Covers,
+                    (false, true) => LeafOrdering::Covered,
+                    (false, false) => LeafOrdering::Incomparable,
+                };
+                (*leaf, ordering)
+            })
+            .collect()
+    }
+
+    /// If leaf `leaf` of `self` covers leaf `leaf` of `other`, see [`Cpuid::covers_leaves`].
+    fn covers_leaf(&self, other: &Self, leaf: u32) -> bool {
+        // `self` is destructured exhaustively and the comparisons sized by `Cpuid::LEAVES`, such
+        // that a field added to `Cpuid` does not compile until it is compared here.
+        let Self {
+            leaf0x00_highest_function_parameter_an_manufacturer_id: leaf0x00,
+            leaf0x01_process_info_and_feature_bits: leaf0x01,
+            leaf0x02_cache_and_tlb: leaf0x02,
+            leaf0x06_thermal_and_power_management: leaf0x06,
+            leaf0x07_extended_features: leaf0x07,
+            leaf0x0d_cpuid_feature_bits: leaf0x0d,
+            leaf0x12_cpuid_feature_bits: leaf0x12,
+            leaf0x14_cpuid_feature_bits: leaf0x14,
+            leaf0x17_soc_vendor_attributes: _,
+            leaf0x19_cpuid_feature_bits: leaf0x19,
+            leaf0x8000_0001_highest_function_parameter_an_manufacturer_id: leaf0x8000_0001,
+            leaf0x8000_0002_brand_string: leaf0x8000_0002,
+            leaf0x8000_0008_virtual_and_physical_address_sizes: leaf0x8000_0008,
+            leaf0x8000_001F_cpuid_feature_bits: leaf0x8000_001f,
+            // Leaves not described are not compared.
+            misc: _,
+            absent_leaves: _,
+        } = self;
+        let comparisons: [(u32, &dyn Fn() -> bool); Self::LEAVES.len()] = [
+            (0x0, &|| {
+                leaf0x00.covers(&other.leaf0x00_highest_function_parameter_an_manufacturer_id)
+            }),
+            (0x1, &|| {
+                leaf0x01.covers(&other.leaf0x01_process_info_and_feature_bits)
+            }),
+            (0x2, &|| leaf0x02.covers(&other.leaf0x02_cache_and_tlb)),
+            (0x6, &|| {
+                leaf0x06.covers(&other.leaf0x06_thermal_and_power_management)
+            }),
+            (0x7, &|| leaf0x07.covers(&other.leaf0x07_extended_features)),
+            (0xD, &|| {
+                leaf0x0d.contains(other.leaf0x0d_cpuid_feature_bits)
+            }),
+            (0x12, &|| {
+                leaf0x12.contains(other.leaf0x12_cpuid_feature_bits)
+            }),
+            (0x14, &|| {
+                leaf0x14.contains(other.leaf0x14_cpuid_feature_bits)
+            }),
+            // Leaf 0x17 identifies the SOC rather than describing functionality.
+            (0x17, &|| true),
+            (0x19, &|| {
+                leaf0x19.contains(other.leaf0x19_cpuid_feature_bits)
+            }),
+            (0x8000_0001, &|| {
+                leaf0x8000_0001
+                    .covers(&other.leaf0x8000_0001_highest_function_parameter_an_manufacturer_id)
+            }),
+            // The brand string identifies the processor model, so must be equal.
+            (0x8000_0002, &|| {
+                leaf0x8000_0002.chunk(0) == other.leaf0x8000_0002_brand_string.chunk(0)
+            }),
+            (0x8000_0003, &|| {
+                leaf0x8000_0002.chunk(1) == other.leaf0x8000_0002_brand_string.chunk(1)
+            }),
+            (0x8000_0004, &|| {
+                leaf0x8000_0002.chunk(2) == other.leaf0x8000_0002_brand_string.chunk(2)
+            }),
+            (0x8000_0008, &|| {
+                leaf0x8000_0008.covers(&other.leaf0x8000_0008_virtual_and_physical_address_sizes)
+            }),
+            (0x8000_001F, &|| {
+                leaf0x8000_001f.contains(other.leaf0x8000_001F_cpuid_feature_bits)
+            }),
+        ];
+        debug_assert!(comparisons.iter().map(|(leaf, _)| *leaf).eq(Self::LEAVES));
+        comparisons
+            .iter()
+            .find(|(function, _)| *function == leaf)
+            .is_none_or(|(_, covers)| covers())
+    }
+
+    /// Returns `self` with the manufacturer id (leaf 0 `ebx`, `edx` and `ecx`) set to `s`.
+    ///
+    /// # Errors
+    ///
+    /// Errors when the given string `s` is not exactly `12` bytes long (see
+    /// [`HighestFunctionParameterAndManufacturerID::set_manufacturer`]).
+    pub fn with_manufacturer(mut self, s: &str) -> Result<Self, &'static str> {
         self.leaf0x00_highest_function_parameter_an_manufacturer_id
-            .covers(&other.leaf0x00_highest_function_parameter_an_manufacturer_id)
-            && self
-                .leaf0x01_process_info_and_feature_bits
-                .covers(&other.leaf0x01_process_info_and_feature_bits)
-            && self
-                .leaf0x06_thermal_and_power_management
-                .covers(&other.leaf0x06_thermal_and_power_management)
-            && self
-                .leaf0x07_extended_features
-                .covers(&other.leaf0x07_extended_features)
-            && self
-                .leaf0x0d_cpuid_feature_bits
-                .contains(other.leaf0x0d_cpuid_feature_bits)
-            && self
-                .leaf0x12_cpuid_feature_bits
-                .contains(other.leaf0x12_cpuid_feature_bits)
-            && self
-                .leaf0x14_cpuid_feature_bits
-                .contains(other.leaf0x14_cpuid_feature_bits)
-            && self
-                .leaf0x19_cpuid_feature_bits
-                .contains(other.leaf0x19_cpuid_feature_bits)
-            && self
-                .leaf0x8000_0001_highest_function_parameter_an_manufacturer_id
-                .covers(&other.leaf0x8000_0001_highest_function_parameter_an_manufacturer_id)
-            && self
-                .leaf0x8000_0008_virtual_and_physical_address_sizes
-                .covers(&other.leaf0x8000_0008_virtual_and_physical_address_sizes)
-            && self
-                .leaf0x8000_001F_cpuid_feature_bits
-                .contains(other.leaf0x8000_001F_cpuid_feature_bits)
+            .set_manufacturer(s)?;
+        Ok(self)
     }
 
+    /// Returns the physical address width (`MAXPHYADDR`).
+    ///
+    /// This is the number of physical address bits of leaf `0x8000_0008` when supported,
+    /// otherwise the architectural default of 36 with `pae` (leaf 1 `edx` bit 6) and 32 without.
+    ///
+    /// Leaf `0x8000_0008` is unsupported when leaf `0x8000_0000` in [`Cpuid::misc`] reports a lower
+    /// highest extended function, or when it reports 0 physical address bits (as the zeroed leaf
+    /// of a CPU not supporting it does).
     #[must_use]
-    pub fn leaf<const N: usize>(&self) -> &<Cpuid as Leaf<N>>::Output
-    where
-        Cpuid: Leaf<N>,
-    {
-        <Cpuid as Leaf<N>>::leaf(self)
+    pub fn max_phys_addr(&self) -> u8 {
+        let supported = self
+            .misc
+            .get(&(0x8000_0000, 0))
+            .is_none_or(|(eax, ..)| *eax >= 0x8000_0008);
+        let bits = self
+            .leaf0x8000_0008_virtual_and_physical_address_sizes
+            .eax
+            .number_of_physical_address_bits();
+        if supported && bits != 0 {
+            bits
+        } else if self
+            .leaf0x01_process_info_and_feature_bits
+            .feature_information
+            .edx
+            .contains(Leaf0x1_SubLeaf0_Edx::pae)
+        {
+            36
+        } else {
+            32
+        }
     }
-}
 
-impl Default for Cpuid {
-    fn default() -> Self {
-        Self {
-            leaf0x00_highest_function_parameter_an_manufacturer_id:
-                HighestFunctionParameterAndManufacturerID::new(),
-            leaf0x01_process_info_and_feature_bits: ProcessorInfoAndFeatureBits::new(),
-            leaf0x06_thermal_and_power_management: ThermalAndPowerManagement::new(),
-            leaf0x07_extended_features: ExtendedFeatures::new(),
+    /// Sets the physical address bits advertised to the guest (leaf `0x8000_0008` `eax` bits 0 to
+    /// 7) to `bits`, clamped to [`Cpuid::max_phys_addr`] of `host` such that `host` still covers
+    /// `self`.
+    ///
+    /// # Errors
+    ///
+    /// When `bits` is 0 or greater than 64.
+    pub fn set_guest_phys_addr_bits(&mut self, bits: u8, host: &Cpuid) -> Result<(), &'static str> {
+        if bits == 0 || bits > 64 {
+            return Err("Physical address bits must be within 1 to 64");
+        }
+        self.leaf0x8000_0008_virtual_and_physical_address_sizes
+            .eax
+            .set_number_of_physical_address_bits(bits.min(host.max_phys_addr()));
+        Ok(())
+    }
+
+    /// If running in a virtual machine, as reported by the `hypervisor` bit (leaf 1 `ecx` bit 31).
+    ///
+    /// This is a strong but not infallible signal, bare metal CPUs always clear the bit but a
+    /// hypervisor may choose not to set it (and nested setups may differ).
+    #[must_use]
+    pub fn is_virtual_machine(&self) -> bool {
+        self.leaf0x01_process_info_and_feature_bits
+            .feature_information
+            .ecx
+            .contains(Leaf0x1_SubLeaf0_Ecx::hypervisor)
+    }
+
+    /// Returns the hypervisor vendor id (leaf `0x4000_0000` `ebx`, `ecx` and `edx`) e.g.
+    /// `"KVMKVMKVM\0\0\0"`.
+    ///
+    /// This is `None` when not running in a virtual machine (see [`Cpuid::is_virtual_machine`])
+    /// or when leaf `0x4000_0000` is not in [`Cpuid::misc`].
+    #[must_use]
+    pub fn hypervisor_vendor(&self) -> Option<FixedString<12>> {
+        if !self.is_virtual_machine() {
+            return None;
+        }
+        let (_, ebx, ecx, edx) = self.misc.get(&(0x4000_0000, 0))?;
+        let vec = [ebx.to_ne_bytes(), ecx.to_ne_bytes(), edx.to_ne_bytes()].concat();
+        Some(FixedString(unsafe { vec.try_into().unwrap_unchecked() }))
+    }
+
+    /// Constructs [`Cpuid`] from the results given by `reader`.
+    ///
+    /// # Errors
+    ///
+    /// When `reader` errors reading any of the leaves [`Cpuid`] describes.
+    pub fn from_reader<R: CpuidReader>(reader: &mut R) -> Result<Self, R::Error> {
+        Ok(Self {
+            leaf0x00_highest_function_parameter_an_manufacturer_id: {
+                let CpuidResult { eax, ebx, ecx, edx } =
+                    read_leaf::<HighestFunctionParameterAndManufacturerID, _>(reader)?;
+                HighestFunctionParameterAndManufacturerID::from((eax, ebx, ecx, edx))
+            },
+            leaf0x01_process_info_and_feature_bits: {
+                let CpuidResult { eax, ebx, ecx, edx } =
+                    read_leaf::<ProcessorInfoAndFeatureBits, _>(reader)?;
+                ProcessorInfoAndFeatureBits::from((eax, ebx, ecx, edx))
+            },
+            leaf0x02_cache_and_tlb: Leaf2CacheAndTlb::from(read_leaf::<Leaf2CacheAndTlb, _>(
+                reader,
+            )?),
+            leaf0x06_thermal_and_power_management: {
+                let CpuidResult { eax, ebx, ecx, edx } =
+                    read_leaf::<ThermalAndPowerManagement, _>(reader)?;
+                ThermalAndPowerManagement::from((eax, ebx, ecx, edx))
+            },
+            leaf0x07_extended_features: {
+                let CpuidResult {
+                    eax: eax0,
+                    ebx: ebx0,
+                    ecx: ecx0,
+                    edx: edx0,
+                } = read_leaf::<ExtendedFeaturesSubLeaf0, _>(reader)?;
+                let CpuidResult {
+                    eax: eax1,
+                    ebx: ebx1,
+                    ecx: ecx1,
+                    edx: edx1,
+                } = read_leaf::<Leaf0x7_SubLeaf1_Eax, _>(reader)?;
+                ExtendedFeatures::from(((eax0, ebx0, ecx0, edx0), (eax1, ebx1, ecx1, edx1)))
+            },
             leaf0x0d_cpuid_feature_bits: {
-                let CpuidResult { eax, .. } = unsafe { __cpuid_count(13, 1) };
+                let CpuidResult { eax, .. } = read_leaf::<Leaf0xD_SubLeaf1_Eax, _>(reader)?;
                 Leaf0xD_SubLeaf1_Eax { bits: eax }
             },
             leaf0x12_cpuid_feature_bits: {
-                let CpuidResult {
-                    eax,
-                    ebx: _,
-                    ecx: _,
-                    edx: _,
-                } = unsafe { __cpuid_count(18, 0) };
+                let CpuidResult { eax, .. } = read_leaf::<Leaf0x12_SubLeaf0_Eax, _>(reader)?;
                 Leaf0x12_SubLeaf0_Eax { bits: eax }
             },
             leaf0x14_cpuid_feature_bits: {
-                let CpuidResult {
-                    eax: _,
-                    ebx,
-                    ecx: _,
-                    edx: _,
-                } = unsafe { __cpuid_count(20, 0) };
+                let CpuidResult { ebx, .. } = read_leaf::<Leaf0x14_SubLeaf0_Ebx, _>(reader)?;
                 Leaf0x14_SubLeaf0_Ebx { bits: ebx }
             },
+            leaf0x17_soc_vendor_attributes: SocVendorAttributes::read(reader)?,
             leaf0x19_cpuid_feature_bits: {
-                let CpuidResult {
-                    eax: _,
-                    ebx,
-                    ecx: _,
-                    edx: _,
-                } = unsafe { __cpuid_count(25, 0) };
+                let CpuidResult { ebx, .. } = read_leaf::<Leaf0x19_SubLeaf0_Ebx, _>(reader)?;
                 Leaf0x19_SubLeaf0_Ebx { bits: ebx }
             },
             leaf0x8000_0001_highest_function_parameter_an_manufacturer_id: {
-                let CpuidResult { ecx, edx, .. } = unsafe { __cpuid_count(0x8000_0001, 0) };
+                let CpuidResult { ecx, edx, .. } =
+                    read_leaf::<ExtendedProcessorInfoAndFeatureBits, _>(reader)?;
                 ExtendedProcessorInfoAndFeatureBits {
                     edx: Leaf0x8000_0001_SubLeaf0_Edx { bits: edx },
                     ecx: Leaf0x8000_0001_SubLeaf0_Ecx { bits: ecx },
                 }
             },
+            leaf0x8000_0002_brand_string: BrandString::read(reader)?,
             leaf0x8000_0008_virtual_and_physical_address_sizes: {
-                let CpuidResult { eax, ebx, ecx, .. } = unsafe { __cpuid_count(0x8000_0008, 0) };
+                let CpuidResult { eax, ebx, ecx, .. } =
+                    read_leaf::<VirtualAndPhysicalAddressSizes, _>(reader)?;
                 VirtualAndPhysicalAddressSizes {
                     eax: Leaf0x8000_0008_SubLeaf0_Eax(eax),
                     ebx: Leaf0x8000_0008_SubLeaf0_Ebx { bits: ebx },
@@ -664,11 +1110,458 @@ impl Default for Cpuid {
                 }
             },
             leaf0x8000_001F_cpuid_feature_bits: {
-                let CpuidResult { eax, .. } = unsafe { __cpuid_count(0x8000_001F, 0) };
+                let CpuidResult { eax, .. } = read_leaf::<Leaf0x8000_001F_SubLeaf0_Eax, _>(reader)?;
                 Leaf0x8000_001F_SubLeaf0_Eax { bits: eax }
             },
             misc: HashMap::new(),
+            absent_leaves: 0,
+        })
+    }
+
+    /// Identical to [`Cpuid::new`] but also returns the anomalies found in the results, see
+    /// [`Cpuid::try_from_reader`].
+    #[must_use]
+    pub fn new_with_warnings() -> (Self, Vec<DecodeWarning>) {
+        let Ok(cpuid) = Self::read_live(&mut HostCpuidReader, |reader| Self::from_reader(reader));
+        let warnings = cpuid.decode_warnings();
+        (cpuid, warnings)
+    }
+
+    /// Identical to [`Cpuid::from_reader`] but also returns the anomalies found in the results
+    /// (see [`Cpuid::decode_warnings`]), for exotic or emulated CPUs which may return garbage.
+    ///
+    /// # Errors
+    ///
+    /// When `reader` errors reading any of the leaves [`Cpuid`] describes.
+    pub fn try_from_reader<R: CpuidReader>(
+        reader: &mut R,
+    ) -> Result<(Self, Vec<DecodeWarning>), R::Error> {
+        let cpuid = Self::from_reader(reader)?;
+        let warnings = cpuid.decode_warnings();
+        Ok((cpuid, warnings))
+    }
+
+    /// Returns the anomalies in `self` which a real CPU should not report, a non UTF-8
+    /// manufacturer id, reserved bits set in the scalar registers, diverging duplicate bits
+    /// (see [`Cpuid::check_duplicate_bits`]), AVX-512 subfeatures without the foundation (see
+    /// [`Avx512Profile::is_consistent`]) or AMD only features (e.g. `monitorx`) with an Intel
+    /// manufacturer id.
+    #[must_use]
+    pub fn decode_warnings(&self) -> Vec<DecodeWarning> {
+        let manufacturer_id = &self
+            .leaf0x00_highest_function_parameter_an_manufacturer_id
+            .manufacturer_id
+            .0;
+        let invalid_manufacturer_id = str::from_utf8(manufacturer_id)
+            .is_err()
+            .then_some(DecodeWarning::InvalidManufacturerId(*manufacturer_id));
+        invalid_manufacturer_id
+            .into_iter()
+            .chain(reserved_bits_warnings(self.reserved_scalar_bits()))
+            .chain(self.check_duplicate_bits())
+            .chain(
+                (!self.avx512_profile().is_consistent())
+                    .then_some(DecodeWarning::Avx512WithoutFoundation),
+            )
+            .chain(
+                (manufacturer_id == b"GenuineIntel" && self.supports_mwaitx())
+                    .then_some(DecodeWarning::AmdFeatureOnIntel("monitorx")),
+            )
+            .collect()
+    }
+
+    /// Returns the reserved bits set in the modeled leaves, as [`DecodeWarning::ReservedBits`].
+    ///
+    /// These are the bits reserved in the scalar registers which [`Cpuid::decode_warnings`]
+    /// reports, the bits reserved in leaf 6 `ebx`, and the bits of the registers described by bit
+    /// flags which no flag is defined for (excluding the leaf 7 `ecx` MAWAU value). Deserializing
+    /// keeps these, see [`Cpuid::clear_reserved_bits`].
+    #[must_use]
+    pub fn reserved_bits_set(&self) -> Vec<DecodeWarning> {
+        let thresholds = (
+            leaf_key::<ThermalAndPowerManagement>(),
+            // Bits 4 to 31 are reserved.
+            self.leaf0x06_thermal_and_power_management
+                .number_of_interrupt_thresholds
+                .0
+                & !0b1111,
+        );
+        let flags = features::FLAGS_REGISTERS.iter().map(|register| {
+            (
+                (register.leaf, register.sub_leaf),
+                (register.get)(self) & !register.defined_bits(),
+            )
+        });
+        let mut reserved = self
+            .reserved_scalar_bits()
+            .into_iter()
+            .chain([thresholds])
+            .chain(flags)
+            .collect::<Vec<_>>();
+        reserved.sort_by_key(|(key, _)| *key);
+        reserved_bits_warnings(reserved).collect()
+    }
+
+    /// Clears the reserved bits set in the modeled leaves (see [`Cpuid::reserved_bits_set`]), to
+    /// sanitize a loaded snapshot for strict use.
+    ///
+    /// The AMD guest physical address size (leaf `0x8000_0008` `eax` bits 16 to 23), the leaf 7
+    /// `ecx` MAWAU value and the entries of [`Cpuid::misc`] are kept.
+    pub fn clear_reserved_bits(&mut self) {
+        for register in &features::FLAGS_REGISTERS {
+            *(register.get_mut)(self) &= register.defined_bits();
+        }
+        self.leaf0x01_process_info_and_feature_bits
+            .processor_version_information
+            .0 &= !ProcessorVersionInformation::RESERVED;
+        self.leaf0x06_thermal_and_power_management
+            .number_of_interrupt_thresholds
+            .0 &= 0b1111;
+        self.leaf0x17_soc_vendor_attributes.vendor &= !SocVendorAttributes::RESERVED;
+        let leaf0x8000_0008 = &mut self.leaf0x8000_0008_virtual_and_physical_address_sizes;
+        leaf0x8000_0008.eax.0 &= !(Leaf0x8000_0008_SubLeaf0_Eax::RESERVED & !0x00FF_0000);
+        leaf0x8000_0008.ecx.0 &= !Leaf0x8000_0008_SubLeaf0_Ecx::RESERVED;
+    }
+
+    /// Returns the reserved bits of the scalar registers, by the leaf and sub-leaf of the register.
+    fn reserved_scalar_bits(&self) -> [((u32, u32), u32); 4] {
+        let leaf0x8000_0008 = &self.leaf0x8000_0008_virtual_and_physical_address_sizes;
+        [
+            (
+                leaf_key::<ProcessorInfoAndFeatureBits>(),
+                self.leaf0x01_process_info_and_feature_bits
+                    .processor_version_information
+                    .reserved(),
+            ),
+            (
+                leaf_key::<SocVendorAttributes>(),
+                self.leaf0x17_soc_vendor_attributes.reserved(),
+            ),
+            (
+                leaf_key::<VirtualAndPhysicalAddressSizes>(),
+                // AMD reports the guest physical address size in bits 16 to 23.
+                leaf0x8000_0008.eax.reserved() & !0x00FF_0000,
+            ),
+            (
+                leaf_key::<VirtualAndPhysicalAddressSizes>(),
+                leaf0x8000_0008.ecx.reserved(),
+            ),
+        ]
+    }
+
+    /// Checks the bits of leaf `0x8000_0001` `edx` duplicating leaf 1 `edx` (bits 0 to 9, 12 to
+    /// 17, 23 and 24) agree with it, returning the differing bits.
+    ///
+    /// AMD duplicates these bits while Intel reports them clear, so they agree when either all
+    /// are clear or all match leaf 1 `edx`. This catches hand-edited templates which set a
+    /// feature in one leaf only.
+    #[must_use]
+    pub fn check_duplicate_bits(&self) -> Option<DecodeWarning> {
+        const DUPLICATES: u32 = 0b0000_0001_1000_0011_1111_0011_1111_1111;
+        let duplicates = self
+            .leaf0x8000_0001_highest_function_parameter_an_manufacturer_id
+            .edx
+            .bits
+            & DUPLICATES;
+        let leaf1 = self
+            .leaf0x01_process_info_and_feature_bits
+            .feature_information
+            .edx
+            .bits
+            & DUPLICATES;
+        let bits = duplicates ^ leaf1;
+        (duplicates != 0 && bits != 0).then_some(DecodeWarning::DuplicateBitsMismatch { bits })
+    }
+
+    /// Returns `(leaf, sub_leaf, registers)` for every leaf and sub-leaf modeled by the fields of
+    /// `self`, ordered by `(leaf, sub_leaf)`, excluding [`Cpuid::misc`].
+    ///
+    /// Registers which are not described are zeroed. This is the single source of the modeled
+    /// leaves for [`Cpuid::to_leaf_map`] (and so [`Cpuid::to_bytes`]).
+    pub fn iter_leaves(&self) -> impl Iterator<Item = (u32, u32, CpuidResult)> {
+        let leaf0 = &self.leaf0x00_highest_function_parameter_an_manufacturer_id;
+        let leaf1 = &self.leaf0x01_process_info_and_feature_bits;
+        let leaf2 = &self.leaf0x02_cache_and_tlb;
+        let leaf6 = &self.leaf0x06_thermal_and_power_management;
+        let leaf7 = &self.leaf0x07_extended_features;
+        let leaf0x17 = &self.leaf0x17_soc_vendor_attributes;
+        let leaf0x17_brand = leaf0x17.brand_string_registers();
+        let leaf0x8000_0001 = &self.leaf0x8000_0001_highest_function_parameter_an_manufacturer_id;
+        let leaf0x8000_0002_brand = self.leaf0x8000_0002_brand_string.registers();
+        let leaf0x8000_0008 = &self.leaf0x8000_0008_virtual_and_physical_address_sizes;
+        let modeled = [
+            (
+                leaf_key::<HighestFunctionParameterAndManufacturerID>(),
+                (leaf0.eax(), leaf0.ebx(), leaf0.ecx(), leaf0.edx()),
+            ),
+            (
+                leaf_key::<ProcessorInfoAndFeatureBits>(),
+                (leaf1.eax(), leaf1.ebx(), leaf1.ecx(), leaf1.edx()),
+            ),
+            (
+                leaf_key::<Leaf2CacheAndTlb>(),
+                (leaf2.eax, leaf2.ebx, leaf2.ecx, leaf2.edx),
+            ),
+            (
+                leaf_key::<ThermalAndPowerManagement>(),
+                (leaf6.eax(), leaf6.ebx(), leaf6.ecx(), 0),
+            ),
+            (
+                leaf_key::<ExtendedFeaturesSubLeaf0>(),
+                (
+                    0,
+                    leaf7.sub_leaf0.ebx(),
+                    leaf7.sub_leaf0.ecx(),
+                    leaf7.sub_leaf0.edx(),
+                ),
+            ),
+            (
+                leaf_key::<Leaf0x7_SubLeaf1_Eax>(),
+                (leaf7.sub_leaf1.eax(), 0, 0, 0),
+            ),
+            (
+                leaf_key::<Leaf0xD_SubLeaf1_Eax>(),
+                (self.leaf0x0d_cpuid_feature_bits.eax(), 0, 0, 0),
+            ),
+            (
+                leaf_key::<Leaf0x12_SubLeaf0_Eax>(),
+                (self.leaf0x12_cpuid_feature_bits.eax(), 0, 0, 0),
+            ),
+            (
+                leaf_key::<Leaf0x14_SubLeaf0_Ebx>(),
+                (0, self.leaf0x14_cpuid_feature_bits.ebx(), 0, 0),
+            ),
+            (
+                leaf_key::<SocVendorAttributes>(),
+                (
+                    leaf0x17.max_sub_leaf,
+                    leaf0x17.vendor,
+                    leaf0x17.project_id,
+                    leaf0x17.stepping_id,
+                ),
+            ),
+            ((SocVendorAttributes::LEAF, 1), leaf0x17_brand[0]),
+            ((SocVendorAttributes::LEAF, 2), leaf0x17_brand[1]),
+            ((SocVendorAttributes::LEAF, 3), leaf0x17_brand[2]),
+            (
+                leaf_key::<Leaf0x19_SubLeaf0_Ebx>(),
+                (0, self.leaf0x19_cpuid_feature_bits.ebx(), 0, 0),
+            ),
+            (
+                leaf_key::<ExtendedProcessorInfoAndFeatureBits>(),
+                (0, 0, leaf0x8000_0001.ecx(), leaf0x8000_0001.edx()),
+            ),
+            ((0x8000_0002, 0), leaf0x8000_0002_brand[0]),
+            ((0x8000_0003, 0), leaf0x8000_0002_brand[1]),
+            ((0x8000_0004, 0), leaf0x8000_0002_brand[2]),
+            (
+                leaf_key::<VirtualAndPhysicalAddressSizes>(),
+                (
+                    leaf0x8000_0008.eax(),
+                    leaf0x8000_0008.ebx(),
+                    leaf0x8000_0008.ecx(),
+                    0,
+                ),
+            ),
+            (
+                leaf_key::<Leaf0x8000_001F_SubLeaf0_Eax>(),
+                (self.leaf0x8000_001F_cpuid_feature_bits.eax(), 0, 0, 0),
+            ),
+        ];
+        modeled
+            .into_iter()
+            .map(|((leaf, sub_leaf), (eax, ebx, ecx, edx))| {
+                (leaf, sub_leaf, CpuidResult { eax, ebx, ecx, edx })
+            })
+    }
+
+    /// Returns the highest basic leaf the CPU reports supported, leaf 0 `eax`.
+    ///
+    /// Basic leaves above this are not implemented, the CPU answers them with arbitrary values
+    /// (Intel returns the highest basic leaf), see [`Cpuid::iter_reported_leaves`].
+    #[must_use]
+    pub fn effective_max_leaf(&self) -> u32 {
+        self.leaf0x00_highest_function_parameter_an_manufacturer_id
+            .highest_calling_parameter
+    }
+
+    /// If the CPU reports `leaf` supported, it is:
+    /// - A basic leaf no greater than [`Cpuid::effective_max_leaf`].
+    /// - A hypervisor leaf (`0x4000_0000` to `0x4FFF_FFFF`), which the hypervisor rather than the
+    ///   CPU reports, e.g. those [`Cpuid::with_options`] reads into [`Cpuid::misc`].
+    /// - An extended leaf no greater than the highest extended leaf, reported by leaf `0x8000_0000`
+    ///   in [`Cpuid::misc`]. Every extended leaf is when it is not in `misc`.
+    #[must_use]
+    pub fn leaf_reported(&self, leaf: u32) -> bool {
+        match leaf {
+            ..0x4000_0000 => leaf <= self.effective_max_leaf(),
+            0x4000_0000..=0x4FFF_FFFF | 0x8000_0000 => true,
+            0x8000_0001.. => self
+                .misc
+                .get(&(0x8000_0000, 0))
+                .is_none_or(|(eax, ..)| leaf <= *eax),
+            _ => false,
+        }
+    }
+
+    /// Identical to [`Cpuid::iter_leaves`] but skips the leaves the CPU does not report supported
+    /// (see [`Cpuid::leaf_reported`]), such that reports do not present their arbitrary values.
+    pub fn iter_reported_leaves(&self) -> impl Iterator<Item = (u32, u32, CpuidResult)> + '_ {
+        self.iter_leaves()
+            .filter(|(leaf, _, _)| self.leaf_reported(*leaf))
+    }
+
+    /// Returns the registers of every leaf and sub-leaf described by `self` (including `misc`)
+    /// ordered by `(leaf, sub_leaf)`.
+    ///
+    /// Registers which are not described are zeroed. Which leaves are present (see
+    /// [`Cpuid::leaf_present`]) is not carried, a zero-filled leaf is mapped like any other.
+    #[must_use]
+    pub fn to_leaf_map(&self) -> BTreeMap<(u32, u32), CpuidResult> {
+        self.misc
+            .iter()
+            .map(|(&key, &(eax, ebx, ecx, edx))| (key, CpuidResult { eax, ebx, ecx, edx }))
+            .chain(
+                self.iter_leaves()
+                    .map(|(leaf, sub_leaf, result)| ((leaf, sub_leaf), result)),
+            )
+            .collect()
+    }
+
+    /// Returns the registers of `leaf` sub-leaf `sub_leaf` (see [`Cpuid::to_leaf_map`]) as the
+    /// 16 bytes `eax`, `ebx`, `ecx`, `edx` each little-endian, the layout read from
+    /// `/dev/cpu/N/cpuid`, or `None` when the leaf is not described by `self`.
+    #[must_use]
+    pub fn leaf_bytes(&self, leaf: u32, sub_leaf: u32) -> Option<[u8; 16]> {
+        let CpuidResult { eax, ebx, ecx, edx } = *self.to_leaf_map().get(&(leaf, sub_leaf))?;
+        let mut bytes = [0; 16];
+        for (chunk, register) in bytes.chunks_exact_mut(4).zip([eax, ebx, ecx, edx]) {
+            chunk.copy_from_slice(&register.to_le_bytes());
+        }
+        Some(bytes)
+    }
+
+    /// Returns the highest sub-leaf of `leaf` stored by `self` (including `misc`), or `None` when
+    /// `leaf` is not stored.
+    ///
+    /// Leaf 7 `eax` (the highest sub-leaf reported by the CPU) is not stored, so this is the
+    /// highest sub-leaf read e.g. `1` for leaf 7 and `0` for leaf 1.
+    #[must_use]
+    pub fn highest_sub_leaf(&self, leaf: u32) -> Option<u32> {
+        self.to_leaf_map()
+            .range((leaf, 0)..=(leaf, u32::MAX))
+            .next_back()
+            .map(|((_, sub_leaf), _)| *sub_leaf)
+    }
+
+    /// Constructs [`Cpuid`] from a map of `(leaf, sub_leaf)` to registers, the inverse of
+    /// [`Cpuid::to_leaf_map`].
+    ///
+    /// Entries for leaves [`Cpuid`] does not explicitly describe are stored in `misc`. Every leaf
+    /// of [`Cpuid::LEAVES`] is present (see [`Cpuid::leaf_present`]), as the map does not carry
+    /// which were zero-filled.
+    ///
+    /// # Errors
+    ///
+    /// When `map` is missing a leaf [`Cpuid`] describes.
+    pub fn from_leaf_map(map: &BTreeMap<(u32, u32), CpuidResult>) -> Result<Self, DecodeError> {
+        let mut cpuid = Self::from_reader(&mut LeafMapReader(map))?;
+        let modeled = cpuid.to_leaf_map();
+        cpuid.misc = map
+            .iter()
+            .filter(|(key, _)| !modeled.contains_key(key))
+            .map(|(key, CpuidResult { eax, ebx, ecx, edx })| (*key, (*eax, *ebx, *ecx, *edx)))
+            .collect();
+        Ok(cpuid)
+    }
+
+    /// Returns a copy of `self` with the registers of every sub-leaf of `leaf` zeroed, including
+    /// those in [`Cpuid::misc`], e.g. to construct pairs differing in exactly one leaf.
+    #[must_use]
+    pub fn without_leaf(&self, leaf: u32) -> Self {
+        let map = self.to_leaf_map();
+        let mut cpuid = Self::from_fn(|function, index| {
+            map.get(&(function, index))
+                .filter(|_| function != leaf)
+                .copied()
+                .unwrap_or(CpuidResult {
+                    eax: 0,
+                    ebx: 0,
+                    ecx: 0,
+                    edx: 0,
+                })
+        });
+        cpuid.misc.clone_from(&self.misc);
+        for (_, registers) in cpuid
+            .misc
+            .iter_mut()
+            .filter(|((misc_leaf, _), _)| *misc_leaf == leaf)
+        {
+            *registers = (0, 0, 0, 0);
         }
+        cpuid.absent_leaves = self.absent_leaves;
+        cpuid
+    }
+
+    /// Constructs [`Cpuid`] from a table of `(leaf, sub_leaf, registers)` e.g. the responses of an
+    /// emulator.
+    ///
+    /// Identical to [`Cpuid::from_leaf_map`], when the table has duplicate entries the last is
+    /// used.
+    ///
+    /// # Errors
+    ///
+    /// When `table` is missing a leaf [`Cpuid`] describes.
+    pub fn from_results(table: &[(u32, u32, CpuidResult)]) -> Result<Self, DecodeError> {
+        let map = table
+            .iter()
+            .map(|(leaf, sub_leaf, result)| ((*leaf, *sub_leaf), *result))
+            .collect();
+        Self::from_leaf_map(&map)
+    }
+
+    /// Encodes [`Cpuid`] as bytes, the inverse of `Cpuid::try_from(&[u8])`.
+    ///
+    /// Each leaf of [`Cpuid::to_leaf_map`] is encoded in order as the little-endian `u32`s
+    /// `leaf, sub_leaf, eax, ebx, ecx, edx`, so like it which leaves are present is not carried.
+    #[must_use]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.to_leaf_map()
+            .into_iter()
+            .flat_map(|((leaf, sub_leaf), CpuidResult { eax, ebx, ecx, edx })| {
+                [leaf, sub_leaf, eax, ebx, ecx, edx]
+            })
+            .flat_map(u32::to_le_bytes)
+            .collect()
+    }
+
+    /// Constructs [`Cpuid`] answering each query with `f(leaf, sub_leaf)`, e.g. an emulator's own
+    /// cpuid handler.
+    #[must_use]
+    pub fn from_fn<F: FnMut(u32, u32) -> CpuidResult>(f: F) -> Self {
+        let Ok(cpuid) = Self::from_reader(&mut FnCpuidReader(f));
+        cpuid
+    }
+
+    #[must_use]
+    pub fn leaf<const N: usize>(&self) -> &<Cpuid as Leaf<N>>::Output
+    where
+        Cpuid: Leaf<N>,
+    {
+        <Cpuid as Leaf<N>>::leaf(self)
+    }
+}
+
+/// Every register zeroed, without reading the host (see [`Cpuid::new`]), such that types
+/// embedding [`Cpuid`] can derive `Default` without executing `cpuid`.
+impl Default for Cpuid {
+    fn default() -> Self {
+        Self::from_fn(|_, _| CpuidResult {
+            eax: 0,
+            ebx: 0,
+            ecx: 0,
+            edx: 0,
+        })
     }
 }
 impl fmt::Debug for Cpuid {
@@ -682,6 +1575,7 @@ impl fmt::Debug for Cpuid {
                 "leaf0x01_process_info_and_feature_bits",
                 &self.leaf0x01_process_info_and_feature_bits,
             )
+            .field("leaf0x02_cache_and_tlb", &self.leaf0x02_cache_and_tlb)
             .field(
                 "leaf0x06_thermal_and_power_management",
                 &self.leaf0x06_thermal_and_power_management,
@@ -702,6 +1596,10 @@ impl fmt::Debug for Cpuid {
                 "leaf0x14_cpuid_feature_bits",
                 &self.leaf0x14_cpuid_feature_bits,
             )
+            .field(
+                "leaf0x17_soc_vendor_attributes",
+                &self.leaf0x17_soc_vendor_attributes,
+            )
             .field(
                 "leaf0x19_cpuid_feature_bits",
                 &self.leaf0x19_cpuid_feature_bits,
@@ -710,6 +1608,10 @@ impl fmt::Debug for Cpuid {
                 "leaf0x8000_0001_highest_function_parameter_an_manufacturer_id",
                 &self.leaf0x8000_0001_highest_function_parameter_an_manufacturer_id,
             )
+            .field(
+                "leaf0x8000_0002_brand_string",
+                &self.leaf0x8000_0002_brand_string,
+            )
             .field(
                 "leaf0x8000_0008_virtual_and_physical_address_sizes",
                 &self.leaf0x8000_0008_virtual_and_physical_address_sizes,
@@ -718,53 +1620,112 @@ impl fmt::Debug for Cpuid {
                 "leaf0x8000_001F_cpuid_feature_bits",
                 &self.leaf0x8000_001F_cpuid_feature_bits,
             )
+            .field("misc", &self.misc)
+            .field("absent_leaves", &self.absent_leaves)
             .finish()
     }
 }
+/// Decodes the byte form of [`Cpuid`], see [`Cpuid::to_bytes`].
+///
+/// Entries of leaves [`Cpuid`] does not describe are stored in [`Cpuid::misc`] as by
+/// [`Cpuid::from_leaf_map`], while a leaf and sub-leaf given by more than one entry is an error.
+impl TryFrom<&[u8]> for Cpuid {
+    type Error = DecodeError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        if !bytes.len().is_multiple_of(Self::ENTRY_BYTES) {
+            return Err(DecodeError::InvalidLength { len: bytes.len() });
+        }
+        let mut map = BTreeMap::new();
+        for entry in bytes.chunks_exact(Self::ENTRY_BYTES) {
+            let mut words = entry
+                .chunks_exact(4)
+                .map(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]]));
+            let mut next = || words.next().unwrap();
+            let (leaf, sub_leaf) = (next(), next());
+            let result = CpuidResult {
+                eax: next(),
+                ebx: next(),
+                ecx: next(),
+                edx: next(),
+            };
+            if map.insert((leaf, sub_leaf), result).is_some() {
+                return Err(DecodeError::DuplicateLeaf { leaf, sub_leaf });
+            }
+        }
+        Self::from_leaf_map(&map)
+    }
+}
 /// This conversion is lossy, this means we cannot implement `From<Cpuid> for RawCpuid`
 impl TryFrom<RawCpuid> for Cpuid {
     // TODO Change this to at least `'static str` and use descriptions.
     type Error = ();
 
     fn try_from(cpuid: RawCpuid) -> Result<Self, Self::Error> {
-        Ok(Self {
+        let get = |(leaf, sub_leaf)| cpuid.get(leaf, sub_leaf).ok_or(());
+        let mut modeled = Self {
             leaf0x00_highest_function_parameter_an_manufacturer_id:
-                HighestFunctionParameterAndManufacturerID::from(cpuid.get(0, 0).ok_or(())?.clone()),
+                HighestFunctionParameterAndManufacturerID::from(
+                    get(leaf_key::<HighestFunctionParameterAndManufacturerID>())?.clone(),
+                ),
             leaf0x01_process_info_and_feature_bits: ProcessorInfoAndFeatureBits::from(
-                cpuid.get(1, 0).ok_or(())?.clone(),
+                get(leaf_key::<ProcessorInfoAndFeatureBits>())?.clone(),
+            ),
+            leaf0x02_cache_and_tlb: Leaf2CacheAndTlb::from(
+                get(leaf_key::<Leaf2CacheAndTlb>())?.clone(),
             ),
             leaf0x06_thermal_and_power_management: ThermalAndPowerManagement::from(
-                cpuid.get(6, 0).ok_or(())?.clone(),
+                get(leaf_key::<ThermalAndPowerManagement>())?.clone(),
             ),
             leaf0x07_extended_features: ExtendedFeatures::from((
-                cpuid.get(7, 0).ok_or(())?.clone(),
-                cpuid.get(7, 1).ok_or(())?.clone(),
+                get(leaf_key::<ExtendedFeaturesSubLeaf0>())?.clone(),
+                get(leaf_key::<Leaf0x7_SubLeaf1_Eax>())?.clone(),
             )),
             leaf0x0d_cpuid_feature_bits: {
-                let RawCpuidEntry { eax, .. } = cpuid.get(13, 1).ok_or(())?;
+                let RawCpuidEntry { eax, .. } = get(leaf_key::<Leaf0xD_SubLeaf1_Eax>())?;
                 Leaf0xD_SubLeaf1_Eax { bits: *eax }
             },
             leaf0x12_cpuid_feature_bits: {
-                let RawCpuidEntry { eax, .. } = cpuid.get(18, 0).ok_or(())?;
+                let RawCpuidEntry { eax, .. } = get(leaf_key::<Leaf0x12_SubLeaf0_Eax>())?;
                 Leaf0x12_SubLeaf0_Eax { bits: *eax }
             },
             leaf0x14_cpuid_feature_bits: {
-                let RawCpuidEntry { ebx, .. } = cpuid.get(20, 0).ok_or(())?;
+                let RawCpuidEntry { ebx, .. } = get(leaf_key::<Leaf0x14_SubLeaf0_Ebx>())?;
                 Leaf0x14_SubLeaf0_Ebx { bits: *ebx }
             },
+            leaf0x17_soc_vendor_attributes: {
+                let mut sub_leaves = [(0, 0, 0, 0); 4];
+                for (sub_leaf, registers) in (0..).zip(&mut sub_leaves) {
+                    let RawCpuidEntry {
+                        eax, ebx, ecx, edx, ..
+                    } = get((SocVendorAttributes::LEAF, sub_leaf))?;
+                    *registers = (*eax, *ebx, *ecx, *edx);
+                }
+                SocVendorAttributes::from(sub_leaves)
+            },
             leaf0x19_cpuid_feature_bits: {
-                let RawCpuidEntry { ebx, .. } = cpuid.get(25, 0).ok_or(())?;
+                let RawCpuidEntry { ebx, .. } = get(leaf_key::<Leaf0x19_SubLeaf0_Ebx>())?;
                 Leaf0x19_SubLeaf0_Ebx { bits: *ebx }
             },
             leaf0x8000_0001_highest_function_parameter_an_manufacturer_id: {
-                let RawCpuidEntry { ecx, edx, .. } = cpuid.get(0x8000_0001, 0).ok_or(())?;
+                let RawCpuidEntry { ecx, edx, .. } =
+                    get(leaf_key::<ExtendedProcessorInfoAndFeatureBits>())?;
                 ExtendedProcessorInfoAndFeatureBits {
                     edx: Leaf0x8000_0001_SubLeaf0_Edx { bits: *edx },
                     ecx: Leaf0x8000_0001_SubLeaf0_Ecx { bits: *ecx },
                 }
             },
+            leaf0x8000_0002_brand_string: {
+                let mut leaves = [(0, 0, 0, 0); 3];
+                for (leaf, registers) in brand::BRAND_STRING_LEAVES.into_iter().zip(&mut leaves) {
+                    let entry = get((leaf, BrandString::SUB_LEAF))?;
+                    *registers = (entry.eax, entry.ebx, entry.ecx, entry.edx);
+                }
+                BrandString::from(leaves)
+            },
             leaf0x8000_0008_virtual_and_physical_address_sizes: {
-                let RawCpuidEntry { eax, ebx, ecx, .. } = cpuid.get(0x8000_0008, 0).ok_or(())?;
+                let RawCpuidEntry { eax, ebx, ecx, .. } =
+                    get(leaf_key::<VirtualAndPhysicalAddressSizes>())?;
                 VirtualAndPhysicalAddressSizes {
                     eax: Leaf0x8000_0008_SubLeaf0_Eax(*eax),
                     ebx: Leaf0x8000_0008_SubLeaf0_Ebx { bits: *ebx },
@@ -772,42 +1733,332 @@ impl TryFrom<RawCpuid> for Cpuid {
                 }
             },
             leaf0x8000_001F_cpuid_feature_bits: {
-                let RawCpuidEntry { eax, .. } = cpuid.get(0x8000_001F, 0).ok_or(())?;
+                let RawCpuidEntry { eax, .. } = get(leaf_key::<Leaf0x8000_001F_SubLeaf0_Eax>())?;
                 Leaf0x8000_001F_SubLeaf0_Eax { bits: *eax }
             },
-            misc: {
-                // Filter out entries we already store explicitly
-                #[allow(clippy::unnested_or_patterns)]
-                cpuid
-                    .iter()
-                    .filter(|entry| {
-                        !matches!(
-                            (entry.function, entry.index),
-                            (0, 0)
-                                | (1, 0)
-                                | (6, 0)
-                                | (7, 0)
-                                | (13, 1)
-                                | (18, 0)
-                                | (20, 0)
-                                | (25, 0)
-                                | (0x8000_0001, 0)
-                                | (0x8000_0008, 0)
-                                | (0x8000_001F, 0)
-                        )
-                    })
-                    .map(|entry| {
-                        (
-                            (entry.function, entry.index),
-                            (entry.eax, entry.ebx, entry.ecx, entry.edx),
-                        )
-                    })
-                    .collect()
-            },
-        })
+            misc: HashMap::new(),
+            absent_leaves: 0,
+        };
+        // Filter out entries we already store explicitly
+        let stored = modeled.to_leaf_map();
+        modeled.misc = cpuid
+            .iter()
+            .filter(|entry| !stored.contains_key(&(entry.function, entry.index)))
+            .map(|entry| {
+                (
+                    (entry.function, entry.index),
+                    (entry.eax, entry.ebx, entry.ecx, entry.edx),
+                )
+            })
+            .collect();
+        Ok(modeled)
+    }
+}
+
+/// Error type for the `TryFrom<&RawCpuidEntry>` implementations of the leaf types, the entry is
+/// of a leaf or sub-leaf other than the one the type describes (see [`LeafNumber`]).
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct LeafMismatch {
+    /// The `(leaf, sub_leaf)` the type describes.
+    pub expected: (u32, u32),
+    /// The `(function, index)` of the entry.
+    pub found: (u32, u32),
+}
+impl fmt::Display for LeafMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Expected leaf {:#x} sub-leaf {:#x}, found leaf {:#x} sub-leaf {:#x}",
+            self.expected.0, self.expected.1, self.found.0, self.found.1
+        )
+    }
+}
+impl std::error::Error for LeafMismatch {}
+
+/// Implements `TryFrom<&RawCpuidEntry>` for the given leaf types, decoding `$entry` with
+/// `$decode` when it is of the leaf and sub-leaf the type describes.
+macro_rules! try_from_entry {
+    ( $($x:ty: |$entry:ident| $decode:expr),* $(,)? ) => {
+        $(
+            impl TryFrom<&RawCpuidEntry> for $x {
+                type Error = LeafMismatch;
+
+                fn try_from($entry: &RawCpuidEntry) -> Result<Self, Self::Error> {
+                    let found = ($entry.function, $entry.index);
+                    if found == leaf_key::<$x>() {
+                        Ok($decode)
+                    } else {
+                        Err(LeafMismatch {
+                            expected: leaf_key::<$x>(),
+                            found,
+                        })
+                    }
+                }
+            }
+        )*
+    };
+}
+try_from_entry!(
+    HighestFunctionParameterAndManufacturerID: |entry| Self::from(entry.clone()),
+    ProcessorInfoAndFeatureBits: |entry| Self::from(entry.clone()),
+    Leaf2CacheAndTlb: |entry| Self::from(entry.clone()),
+    ThermalAndPowerManagement: |entry| Self::from(entry.clone()),
+    ExtendedFeaturesSubLeaf0: |entry| Self {
+        ebx: Leaf0x7_SubLeaf0_Ebx { bits: entry.ebx },
+        ecx: Leaf0x7_SubLeaf0_Ecx { bits: entry.ecx },
+        edx: Leaf0x7_SubLeaf0_Edx { bits: entry.edx },
+    },
+    Leaf0x7_SubLeaf1_Eax: |entry| Self { bits: entry.eax },
+    Leaf0xD_SubLeaf1_Eax: |entry| Self { bits: entry.eax },
+    Leaf0x12_SubLeaf0_Eax: |entry| Self { bits: entry.eax },
+    Leaf0x14_SubLeaf0_Ebx: |entry| Self { bits: entry.ebx },
+    Leaf0x19_SubLeaf0_Ebx: |entry| Self { bits: entry.ebx },
+    ExtendedProcessorInfoAndFeatureBits: |entry| Self {
+        edx: Leaf0x8000_0001_SubLeaf0_Edx { bits: entry.edx },
+        ecx: Leaf0x8000_0001_SubLeaf0_Ecx { bits: entry.ecx },
+    },
+    VirtualAndPhysicalAddressSizes: |entry| Self {
+        eax: Leaf0x8000_0008_SubLeaf0_Eax(entry.eax),
+        ebx: Leaf0x8000_0008_SubLeaf0_Ebx { bits: entry.ebx },
+        ecx: Leaf0x8000_0008_SubLeaf0_Ecx(entry.ecx),
+    },
+    Leaf0x8000_001F_SubLeaf0_Eax: |entry| Self { bits: entry.eax },
+);
+
+// -----------------------------------------------------------------------------
+// Cpuid reading
+// -----------------------------------------------------------------------------
+
+/// A source of cpuid results.
+///
+/// This allows constructing [`Cpuid`] from sources other than the host e.g. an emulator or a
+/// previously captured table.
+pub trait CpuidReader {
+    type Error;
+    /// Returns the registers for a given leaf (function) and sub-leaf (index).
+    ///
+    /// # Errors
+    ///
+    /// When the leaf cannot be read.
+    fn read(&mut self, leaf: u32, sub_leaf: u32) -> Result<CpuidResult, Self::Error>;
+}
+
+/// Reads cpuid from the host with the `cpuid` instruction.
+///
+/// Every query sets `ecx` to the sub-leaf, including for leaves which ignore it. The leaves
+/// [`Cpuid`] describes which are indexed by `ecx` are 7, `0xD`, `0x12` and `0x14`, while others
+/// (e.g. 4, `0xB`, `0xF`, `0x10`, `0x17`, `0x18`, `0x1D`, `0x1F` and `0x8000_001D`) are found in
+/// [`Cpuid::misc`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HostCpuidReader;
+impl CpuidReader for HostCpuidReader {
+    type Error = Infallible;
+
+    fn read(&mut self, leaf: u32, sub_leaf: u32) -> Result<CpuidResult, Self::Error> {
+        Ok(__cpuid_count(leaf, sub_leaf))
+    }
+}
+
+/// Reads cpuid from the host with the `cpuid` instruction, fencing each query.
+///
+/// `cpuid` is a serializing instruction, making it a common barrier when timing other
+/// instructions. The fences prevent the surrounding loads and stores being reordered around the
+/// query so it can be relied upon as a barrier e.g. when benchmarking. Otherwise this is
+/// identical to [`HostCpuidReader`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SerializingHostCpuidReader;
+impl CpuidReader for SerializingHostCpuidReader {
+    type Error = Infallible;
+
+    fn read(&mut self, leaf: u32, sub_leaf: u32) -> Result<CpuidResult, Self::Error> {
+        // `sse2` is always available on `x86_64`.
+        unsafe {
+            _mm_mfence();
+            _mm_lfence();
+        }
+        let result = __cpuid_count(leaf, sub_leaf);
+        unsafe {
+            _mm_lfence();
+        }
+        Ok(result)
+    }
+}
+
+/// Reads cpuid from another reader, zeroing leaves above the reported highest leaf which report
+/// `0xFFFF_FFFF` in every register, see [`Cpuid::from_reader`].
+struct SentinelCpuidReader<'a, R> {
+    reader: &'a mut R,
+    highest_basic_leaf: Option<u32>,
+    highest_extended_leaf: Option<u32>,
+    /// The leaves zeroed rather than read.
+    zeroed: Vec<u32>,
+}
+impl<R: CpuidReader> CpuidReader for SentinelCpuidReader<'_, R> {
+    type Error = R::Error;
+
+    fn read(&mut self, leaf: u32, sub_leaf: u32) -> Result<CpuidResult, Self::Error> {
+        let result = self.reader.read(leaf, sub_leaf)?;
+        let (base, highest) = match leaf {
+            ..0x4000_0000 => (0, &mut self.highest_basic_leaf),
+            0x8000_0000.. => (0x8000_0000, &mut self.highest_extended_leaf),
+            _ => return Ok(result),
+        };
+        if leaf == base {
+            *highest = Some(result.eax);
+            return Ok(result);
+        }
+        let CpuidResult { eax, ebx, ecx, edx } = result;
+        if [eax, ebx, ecx, edx] != [u32::MAX; 4] {
+            return Ok(result);
+        }
+        let highest = match *highest {
+            Some(highest) => highest,
+            None => *highest.insert(self.reader.read(base, 0)?.eax),
+        };
+        if leaf <= highest {
+            Ok(result)
+        } else {
+            self.zeroed.push(leaf);
+            Ok(CpuidResult {
+                eax: 0,
+                ebx: 0,
+                ecx: 0,
+                edx: 0,
+            })
+        }
+    }
+}
+
+/// Reads cpuid from a closure.
+struct FnCpuidReader<F>(F);
+impl<F: FnMut(u32, u32) -> CpuidResult> CpuidReader for FnCpuidReader<F> {
+    type Error = Infallible;
+
+    fn read(&mut self, leaf: u32, sub_leaf: u32) -> Result<CpuidResult, Self::Error> {
+        Ok((self.0)(leaf, sub_leaf))
+    }
+}
+
+/// Reads cpuid from a map of `(leaf, sub_leaf)` to registers.
+struct LeafMapReader<'a>(&'a BTreeMap<(u32, u32), CpuidResult>);
+impl CpuidReader for LeafMapReader<'_> {
+    type Error = DecodeError;
+
+    fn read(&mut self, leaf: u32, sub_leaf: u32) -> Result<CpuidResult, Self::Error> {
+        self.0
+            .get(&(leaf, sub_leaf))
+            .copied()
+            .ok_or(DecodeError::MissingLeaf { leaf, sub_leaf })
+    }
+}
+
+/// Error type for decoding [`Cpuid`] from a table of cpuid results.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum DecodeError {
+    /// A leaf [`Cpuid`] describes is not present.
+    MissingLeaf { leaf: u32, sub_leaf: u32 },
+    /// The length of the given bytes is not a multiple of [`Cpuid::ENTRY_BYTES`].
+    InvalidLength { len: usize },
+    /// A leaf is given more than once.
+    DuplicateLeaf { leaf: u32, sub_leaf: u32 },
+}
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingLeaf { leaf, sub_leaf } => {
+                write!(f, "Missing leaf {leaf:#x} sub-leaf {sub_leaf:#x}")
+            }
+            Self::InvalidLength { len } => write!(
+                f,
+                "Invalid length {len}, expected a multiple of {}",
+                Cpuid::ENTRY_BYTES
+            ),
+            Self::DuplicateLeaf { leaf, sub_leaf } => {
+                write!(f, "Duplicate leaf {leaf:#x} sub-leaf {sub_leaf:#x}")
+            }
+        }
+    }
+}
+impl std::error::Error for DecodeError {}
+
+/// An anomaly in cpuid results which does not prevent decoding, see [`Cpuid::try_from_reader`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum DecodeWarning {
+    /// The manufacturer id (leaf 0) is not valid UTF-8.
+    InvalidManufacturerId([u8; 12]),
+    /// Bits documented as reserved are set in a register.
+    ReservedBits { leaf: u32, sub_leaf: u32, bits: u32 },
+    /// The bits of leaf `0x8000_0001` `edx` duplicating leaf 1 `edx` differ from it, see
+    /// [`Cpuid::check_duplicate_bits`].
+    DuplicateBitsMismatch { bits: u32 },
+    /// AVX-512 subfeatures are set without the `avx512_f` foundation they require, see
+    /// [`Avx512Profile::is_consistent`].
+    Avx512WithoutFoundation,
+    /// The given AMD only feature (e.g. `monitorx`) is set with an Intel manufacturer id.
+    AmdFeatureOnIntel(&'static str),
+}
+impl fmt::Display for DecodeWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidManufacturerId(bytes) => {
+                write!(f, "Manufacturer id {bytes:?} is not valid UTF-8")
+            }
+            Self::ReservedBits {
+                leaf,
+                sub_leaf,
+                bits,
+            } => write!(
+                f,
+                "Reserved bits {bits:#x} set in leaf {leaf:#x} sub-leaf {sub_leaf:#x}"
+            ),
+            Self::DuplicateBitsMismatch { bits } => write!(
+                f,
+                "Duplicate bits {bits:#x} differ between leaf 0x1 edx and leaf 0x80000001 edx"
+            ),
+            Self::Avx512WithoutFoundation => {
+                write!(f, "AVX-512 subfeatures set without avx512_f")
+            }
+            Self::AmdFeatureOnIntel(name) => {
+                write!(
+                    f,
+                    "AMD only feature {name} set with an Intel manufacturer id"
+                )
+            }
+        }
+    }
+}
+
+bitflags! {
+    /// The fields [`Cpuid::eq_ignoring`] ignores.
+    pub struct IgnoreFields: u32 {
+        /// The local APIC id (leaf 1 `ebx` bits 24 to 31), which depends on which CPU ran the
+        /// query.
+        const APIC_ID = 1 << 0;
+        /// The brand string (see [`BrandString`] and the leaf `0x17` brand string) and the leaf 1
+        /// brand index.
+        const BRAND_STRING = 1 << 1;
+        /// The topology leaves in [`Cpuid::misc`] (`0xB`, `0x1F` and `0x8000_001E`) and the
+        /// maximum addressable logical processor ids (leaf 1 `ebx` bits 16 to 23).
+        const TOPOLOGY = 1 << 2;
+        /// The frequency leaves in [`Cpuid::misc`] (`0x15` and `0x16`).
+        const FREQUENCY = 1 << 3;
     }
 }
 
+/// How a leaf of one [`Cpuid`] compares to the same leaf of another, see
+/// [`Cpuid::compare_verbose`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum LeafOrdering {
+    /// The leaf covers the other but is not covered by it.
+    Covers,
+    /// The leaf is covered by the other but does not cover it.
+    Covered,
+    /// The leaves cover each other.
+    Equal,
+    /// Neither leaf covers the other.
+    Incomparable,
+}
+
 pub trait Leaf<const INDEX: usize> {
     type Output;
     fn leaf(&self) -> &Self::Output;
@@ -826,6 +2077,13 @@ impl Leaf<1> for Cpuid {
         &self.leaf0x01_process_info_and_feature_bits
     }
 }
+impl Leaf<2> for Cpuid {
+    type Output = Leaf2CacheAndTlb;
+
+    fn leaf(&self) -> &Self::Output {
+        &self.leaf0x02_cache_and_tlb
+    }
+}
 impl Leaf<6> for Cpuid {
     type Output = ThermalAndPowerManagement;
 
@@ -861,6 +2119,13 @@ impl Leaf<20> for Cpuid {
         &self.leaf0x14_cpuid_feature_bits
     }
 }
+impl Leaf<23> for Cpuid {
+    type Output = SocVendorAttributes;
+
+    fn leaf(&self) -> &Self::Output {
+        &self.leaf0x17_soc_vendor_attributes
+    }
+}
 impl Leaf<25> for Cpuid {
     type Output = Leaf0x19_SubLeaf0_Ebx;
 
@@ -875,6 +2140,27 @@ impl Leaf<0x8000_0001> for Cpuid {
         &self.leaf0x8000_0001_highest_function_parameter_an_manufacturer_id
     }
 }
+impl Leaf<0x8000_0002> for Cpuid {
+    type Output = [u8; 16];
+
+    fn leaf(&self) -> &Self::Output {
+        self.leaf0x8000_0002_brand_string.chunk(0)
+    }
+}
+impl Leaf<0x8000_0003> for Cpuid {
+    type Output = [u8; 16];
+
+    fn leaf(&self) -> &Self::Output {
+        self.leaf0x8000_0002_brand_string.chunk(1)
+    }
+}
+impl Leaf<0x8000_0004> for Cpuid {
+    type Output = [u8; 16];
+
+    fn leaf(&self) -> &Self::Output {
+        self.leaf0x8000_0002_brand_string.chunk(2)
+    }
+}
 impl Leaf<0x8000_0008> for Cpuid {
     type Output = VirtualAndPhysicalAddressSizes;
 
@@ -908,6 +2194,13 @@ impl SubLeaf<0> for ProcessorInfoAndFeatureBits {
         self
     }
 }
+impl SubLeaf<0> for Leaf2CacheAndTlb {
+    type Output = Self;
+
+    fn sub_leaf(&self) -> &Self::Output {
+        self
+    }
+}
 impl SubLeaf<0> for ThermalAndPowerManagement {
     type Output = Self;
 
@@ -950,6 +2243,13 @@ impl SubLeaf<0> for Leaf0x14_SubLeaf0_Ebx {
         self
     }
 }
+impl SubLeaf<0> for SocVendorAttributes {
+    type Output = Self;
+
+    fn sub_leaf(&self) -> &Self::Output {
+        self
+    }
+}
 impl SubLeaf<0> for Leaf0x19_SubLeaf0_Ebx {
     type Output = Self;
 
@@ -964,6 +2264,13 @@ impl SubLeaf<0> for ExtendedProcessorInfoAndFeatureBits {
         self
     }
 }
+impl SubLeaf<0> for BrandString {
+    type Output = Self;
+
+    fn sub_leaf(&self) -> &Self::Output {
+        self
+    }
+}
 impl SubLeaf<0> for VirtualAndPhysicalAddressSizes {
     type Output = Self;
 
@@ -979,31 +2286,159 @@ impl SubLeaf<0> for Leaf0x8000_001F_SubLeaf0_Eax {
     }
 }
 
+/// The leaf (function) and sub-leaf (index) a type describes.
+pub trait LeafNumber {
+    const LEAF: u32;
+    const SUB_LEAF: u32;
+}
+impl LeafNumber for HighestFunctionParameterAndManufacturerID {
+    const LEAF: u32 = 0;
+    const SUB_LEAF: u32 = 0;
+}
+impl LeafNumber for ProcessorInfoAndFeatureBits {
+    const LEAF: u32 = 1;
+    const SUB_LEAF: u32 = 0;
+}
+impl LeafNumber for Leaf2CacheAndTlb {
+    const LEAF: u32 = 2;
+    const SUB_LEAF: u32 = 0;
+}
+impl LeafNumber for ThermalAndPowerManagement {
+    const LEAF: u32 = 6;
+    const SUB_LEAF: u32 = 0;
+}
+// Describes sub-leaves 0 and 1, we use the first.
+impl LeafNumber for ExtendedFeatures {
+    const LEAF: u32 = 7;
+    const SUB_LEAF: u32 = 0;
+}
+impl LeafNumber for ExtendedFeaturesSubLeaf0 {
+    const LEAF: u32 = 7;
+    const SUB_LEAF: u32 = 0;
+}
+impl LeafNumber for Leaf0x7_SubLeaf1_Eax {
+    const LEAF: u32 = 7;
+    const SUB_LEAF: u32 = 1;
+}
+impl LeafNumber for Leaf0xD_SubLeaf1_Eax {
+    const LEAF: u32 = 13;
+    const SUB_LEAF: u32 = 1;
+}
+impl LeafNumber for Leaf0x12_SubLeaf0_Eax {
+    const LEAF: u32 = 18;
+    const SUB_LEAF: u32 = 0;
+}
+impl LeafNumber for Leaf0x14_SubLeaf0_Ebx {
+    const LEAF: u32 = 20;
+    const SUB_LEAF: u32 = 0;
+}
+// Describes sub-leaves 0 to 3, we use the first.
+impl LeafNumber for SocVendorAttributes {
+    const LEAF: u32 = 0x17;
+    const SUB_LEAF: u32 = 0;
+}
+impl LeafNumber for Leaf0x19_SubLeaf0_Ebx {
+    const LEAF: u32 = 25;
+    const SUB_LEAF: u32 = 0;
+}
+impl LeafNumber for ExtendedProcessorInfoAndFeatureBits {
+    const LEAF: u32 = 0x8000_0001;
+    const SUB_LEAF: u32 = 0;
+}
+// Describes leaves `0x8000_0002` to `0x8000_0004`, we use the first.
+impl LeafNumber for BrandString {
+    const LEAF: u32 = 0x8000_0002;
+    const SUB_LEAF: u32 = 0;
+}
+impl LeafNumber for VirtualAndPhysicalAddressSizes {
+    const LEAF: u32 = 0x8000_0008;
+    const SUB_LEAF: u32 = 0;
+}
+impl LeafNumber for Leaf0x8000_001F_SubLeaf0_Eax {
+    const LEAF: u32 = 0x8000_001F;
+    const SUB_LEAF: u32 = 0;
+}
+
+/// Returns the `(leaf, sub_leaf)` `T` describes.
+fn leaf_key<T: LeafNumber>() -> (u32, u32) {
+    (T::LEAF, T::SUB_LEAF)
+}
+
+/// Returns a [`DecodeWarning::ReservedBits`] for each register of `reserved` with bits set.
+fn reserved_bits_warnings(
+    reserved: impl IntoIterator<Item = ((u32, u32), u32)>,
+) -> impl Iterator<Item = DecodeWarning> {
+    reserved
+        .into_iter()
+        .filter(|(_, bits)| *bits != 0)
+        .map(|((leaf, sub_leaf), bits)| DecodeWarning::ReservedBits {
+            leaf,
+            sub_leaf,
+            bits,
+        })
+}
+
+/// Reads the leaf `T` describes from `reader`.
+fn read_leaf<T: LeafNumber, R: CpuidReader>(reader: &mut R) -> Result<CpuidResult, R::Error> {
+    reader.read(T::LEAF, T::SUB_LEAF)
+}
+
+/// Reads the leaf `T` describes from the host, see [`HostCpuidReader`].
+fn read_host_leaf<T: LeafNumber>() -> CpuidResult {
+    let Ok(result) = read_leaf::<T, _>(&mut HostCpuidReader);
+    result
+}
+
 /// A string wrapper around a byte array.
 #[derive(Clone, Eq, PartialEq)]
 #[repr(C)]
 pub struct FixedString<const N: usize>(pub [u8; N]);
+impl<const N: usize> FixedString<N> {
+    /// If every byte is NUL, as left by [`Cpuid::anonymize`].
+    #[must_use]
+    pub fn is_blank(&self) -> bool {
+        self.0.iter().all(|byte| *byte == 0)
+    }
+}
+/// All NULs, see [`FixedString::is_blank`].
+impl<const N: usize> Default for FixedString<N> {
+    fn default() -> Self {
+        Self([0; N])
+    }
+}
 impl<const N: usize> fmt::Debug for FixedString<N> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", str::from_utf8(&self.0).unwrap())
+        // A CPU may report garbage, so we do not assume valid UTF-8.
+        write!(f, "{}", String::from_utf8_lossy(&self.0))
     }
 }
+/// Serializes as a string when valid UTF-8, otherwise (e.g. the garbage an exotic CPU may report)
+/// as an array of bytes.
 impl<const N: usize> Serialize for FixedString<N> {
     fn serialize<S: serde::Serializer>(&self, ser: S) -> Result<S::Ok, S::Error> {
-        Serialize::serialize(
-            str::from_utf8(&self.0)
-                .map_err(|_| serde::ser::Error::custom("invalid utf8 manufacturer id"))?,
-            ser,
-        )
+        match str::from_utf8(&self.0) {
+            Ok(s) => s.serialize(ser),
+            Err(_) => self.0.as_slice().serialize(ser),
+        }
     }
 }
+/// From a string or an array of bytes, see [`FixedString`]'s `Serialize` implementation.
 impl<'a, const N: usize> Deserialize<'a> for FixedString<N> {
     fn deserialize<D: serde::Deserializer<'a>>(des: D) -> Result<Self, D::Error> {
-        let base = <&str>::deserialize(des)?;
-        let bytes = base
-            .as_bytes()
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            // Owned as escaped strings (e.g. NUL padding) cannot be borrowed.
+            String(String),
+            Bytes(Vec<u8>),
+        }
+        let bytes = match Repr::deserialize(des)? {
+            Repr::String(s) => s.into_bytes(),
+            Repr::Bytes(bytes) => bytes,
+        };
+        let bytes = bytes
             .try_into()
-            .map_err(|_| serde::de::Error::custom("incorrectly sized manufacturer id"))?;
+            .map_err(|_| serde::de::Error::custom("incorrectly sized fixed string"))?;
         Ok(FixedString(bytes))
     }
 }
@@ -1014,6 +2449,10 @@ impl<'a, const N: usize> Deserialize<'a> for FixedString<N> {
 pub struct HighestFunctionParameterAndManufacturerID {
     /// We use [`FixedString`] here over `[u8;12]` so it serializes to and from a string making the
     /// file more easily editable.
+    ///
+    /// A blank manufacturer id (see [`Cpuid::anonymize`]) is not serialized, and a missing one
+    /// deserializes as blank.
+    #[serde(default, skip_serializing_if = "FixedString::is_blank")]
     pub manufacturer_id: FixedString<12>,
     pub highest_calling_parameter: u32,
 }
@@ -1059,10 +2498,31 @@ impl HighestFunctionParameterAndManufacturerID {
         self.highest_calling_parameter
     }
 
+    /// Sets the manufacturer id e.g. `"GenuineIntel"` or `"AuthenticAMD"`.
+    ///
+    /// Manufacturer ids are always 12 bytes, we do not pad shorter strings as this would produce an
+    /// id no real CPU reports.
+    ///
+    /// # Errors
+    ///
+    /// Errors when the given string `s` is not exactly `12` bytes long
+    /// (`if s.len() == 12 { ... Ok(()) } else { Err(...) }`).
+    pub fn set_manufacturer(&mut self, s: &str) -> Result<(), &'static str> {
+        match s.len() {
+            12 => {
+                self.manufacturer_id =
+                    FixedString(unsafe { s.as_bytes().try_into().unwrap_unchecked() });
+                Ok(())
+            }
+            x if x < 12 => Err("s.len() < 12"),
+            _ => Err("s.len() > 12"),
+        }
+    }
+
     /// Since we do not currently need to support cross paltform snapshots (AMD <-> Intel) we can
     /// simply require the mnanufactuer id's match.
-    #[logfn(Trace)]
-    #[logfn_inputs(Info)]
+    #[cfg_attr(not(feature = "tracing"), logfn(Trace), logfn_inputs(Info))]
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", ret))]
     fn covers(&self, other: &Self) -> bool {
         self.manufacturer_id == other.manufacturer_id
             && self.highest_calling_parameter >= other.highest_calling_parameter
@@ -1086,8 +2546,7 @@ impl fmt::Debug for HighestFunctionParameterAndManufacturerID {
 }
 impl Default for HighestFunctionParameterAndManufacturerID {
     fn default() -> Self {
-        let CpuidResult { eax, ebx, ecx, edx } = unsafe { __cpuid(0) };
-        Self::from((eax, ebx, ecx, edx))
+        Self::from(read_host_leaf::<Self>())
     }
 }
 impl From<RawCpuidEntry> for HighestFunctionParameterAndManufacturerID {
@@ -1098,6 +2557,12 @@ impl From<RawCpuidEntry> for HighestFunctionParameterAndManufacturerID {
         Self::from((eax, ebx, ecx, edx))
     }
 }
+/// From the registers of one query e.g. from [`cpuid!`].
+impl From<CpuidResult> for HighestFunctionParameterAndManufacturerID {
+    fn from(CpuidResult { eax, ebx, ecx, edx }: CpuidResult) -> Self {
+        Self::from((eax, ebx, ecx, edx))
+    }
+}
 /// From `(eax,ebx,ecx,edx)`.
 impl From<(u32, u32, u32, u32)> for HighestFunctionParameterAndManufacturerID {
     fn from((eax, ebx, ecx, edx): (u32, u32, u32, u32)) -> Self {
@@ -1128,7 +2593,7 @@ impl ProcessorInfoAndFeatureBits {
 
     #[must_use]
     pub fn ebx(&self) -> u32 {
-        u32::from_ne_bytes([
+        u32::from_le_bytes([
             self.additional_information.brand_index,
             self.additional_information.clflush_line_size,
             self.additional_information
@@ -1153,8 +2618,8 @@ impl ProcessorInfoAndFeatureBits {
     }
 
     // If the feature set of `self` covers the feature set of `other`.
-    #[logfn(Trace)]
-    #[logfn_inputs(Info)]
+    #[cfg_attr(not(feature = "tracing"), logfn(Trace), logfn_inputs(Info))]
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", ret))]
     fn covers(&self, other: &Self) -> bool {
         self.processor_version_information
             .covers(&other.processor_version_information)
@@ -1174,8 +2639,7 @@ impl ProcessorInfoAndFeatureBits {
 }
 impl Default for ProcessorInfoAndFeatureBits {
     fn default() -> Self {
-        let CpuidResult { eax, ebx, ecx, edx } = unsafe { __cpuid_count(1, 0) };
-        Self::from((eax, ebx, ecx, edx))
+        Self::from(read_host_leaf::<Self>())
     }
 }
 impl From<RawCpuidEntry> for ProcessorInfoAndFeatureBits {
@@ -1186,12 +2650,26 @@ impl From<RawCpuidEntry> for ProcessorInfoAndFeatureBits {
         Self::from((eax, ebx, ecx, edx))
     }
 }
+/// From the registers of one query e.g. from [`cpuid!`].
+impl From<CpuidResult> for ProcessorInfoAndFeatureBits {
+    fn from(CpuidResult { eax, ebx, ecx, edx }: CpuidResult) -> Self {
+        Self::from((eax, ebx, ecx, edx))
+    }
+}
 /// From `(eax,ebx,ecx,edx)`.
 impl From<(u32, u32, u32, u32)> for ProcessorInfoAndFeatureBits {
     fn from((eax, ebx, ecx, edx): (u32, u32, u32, u32)) -> Self {
         Self {
             processor_version_information: ProcessorVersionInformation(eax),
-            additional_information: unsafe { transmute::<_, AdditionalInformation>(ebx) },
+            additional_information: {
+                let bytes = ebx.to_le_bytes();
+                AdditionalInformation {
+                    brand_index: bytes[0],
+                    clflush_line_size: bytes[1],
+                    maximum_addressable_logical_processor_ids: bytes[2],
+                    local_apic_id: bytes[3],
+                }
+            },
             feature_information: FeatureInformation {
                 ecx: Leaf0x1_SubLeaf0_Ecx { bits: ecx },
                 edx: Leaf0x1_SubLeaf0_Edx { bits: edx },
@@ -1204,6 +2682,9 @@ impl From<(u32, u32, u32, u32)> for ProcessorInfoAndFeatureBits {
 #[repr(C)]
 pub struct ProcessorVersionInformation(u32);
 impl ProcessorVersionInformation {
+    /// The reserved bits, 14th, 15th and 28th to 31st.
+    const RESERVED: u32 = 0b1111_0000_0000_0000_1100_0000_0000_0000;
+
     #[must_use]
     pub fn stepping_id(&self) -> u8 {
         (self.0 & 0b0000_0000_0000_0000_0000_0000_0000_1111) as u8
@@ -1234,6 +2715,28 @@ impl ProcessorVersionInformation {
         ((self.0 & 0b0000_1111_1111_0000_0000_0000_0000_0000) >> 20) as u8
     }
 
+    /// Returns the family as displayed by software, the family id plus, when the family id is
+    /// `0xF`, the extended family id.
+    #[must_use]
+    pub fn display_family(&self) -> u16 {
+        if self.family_id() == 0xF {
+            u16::from(self.family_id()) + u16::from(self.extended_family_id())
+        } else {
+            u16::from(self.family_id())
+        }
+    }
+
+    /// Returns the model as displayed by software, with the extended model id as the high nibble
+    /// when the family id is `0x6` or `0xF`.
+    #[must_use]
+    pub fn display_model(&self) -> u8 {
+        if self.family_id() == 0x6 || self.family_id() == 0xF {
+            (self.extended_model_id() << 4) | self.model()
+        } else {
+            self.model()
+        }
+    }
+
     /// # Errors
     ///
     /// Errors when the given value `x` is greater than or equal to `16`
@@ -1303,8 +2806,27 @@ impl ProcessorVersionInformation {
         self.0 = (self.0 & !0b0000_1111_1111_0000_0000_0000_0000_0000) | (u32::from(x) << 20);
     }
 
-    #[logfn(Trace)]
-    #[logfn_inputs(Info)]
+    /// Returns the reserved bits, in place.
+    #[must_use]
+    pub fn reserved(&self) -> u32 {
+        self.0 & Self::RESERVED
+    }
+
+    /// # Errors
+    ///
+    /// Errors when the given value `x` has bits set outside the reserved bits
+    /// (`if x & !RESERVED == 0 { ... Ok(()) } else { Err(...) }`).
+    pub fn set_reserved(&mut self, x: u32) -> Result<(), &str> {
+        if x & !Self::RESERVED == 0 {
+            self.0 = (self.0 & !Self::RESERVED) | x;
+            Ok(())
+        } else {
+            Err("x & !RESERVED != 0")
+        }
+    }
+
+    #[cfg_attr(not(feature = "tracing"), logfn(Trace), logfn_inputs(Info))]
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", ret))]
     fn covers(&self, other: &Self) -> bool {
         self.0 == other.0
     }
@@ -1347,6 +2869,16 @@ impl fmt::Debug for ProcessorVersionInformation {
             .finish()
     }
 }
+impl fmt::LowerHex for ProcessorVersionInformation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::LowerHex::fmt(&self.0, f)
+    }
+}
+impl fmt::UpperHex for ProcessorVersionInformation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::UpperHex::fmt(&self.0, f)
+    }
+}
 
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 #[repr(C)]
@@ -1358,8 +2890,8 @@ pub struct AdditionalInformation {
     pub local_apic_id: u8,
 }
 impl AdditionalInformation {
-    #[logfn(Trace)]
-    #[logfn_inputs(Info)]
+    #[cfg_attr(not(feature = "tracing"), logfn(Trace), logfn_inputs(Info))]
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", ret))]
     fn covers(&self, other: &Self) -> bool {
         self.brand_index == other.brand_index
             && self.clflush_line_size == other.clflush_line_size
@@ -1379,12 +2911,21 @@ pub struct FeatureInformation {
     pub edx: Leaf0x1_SubLeaf0_Edx,
 }
 impl FeatureInformation {
-    #[logfn(Trace)]
-    #[logfn_inputs(Info)]
+    #[cfg_attr(not(feature = "tracing"), logfn(Trace), logfn_inputs(Info))]
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", ret))]
     fn covers(&self, other: &Self) -> bool {
         self.ecx.contains(other.ecx) && self.edx.contains(other.edx)
     }
 }
+/// From the registers of one query e.g. from [`cpuid!`].
+impl From<CpuidResult> for FeatureInformation {
+    fn from(CpuidResult { ecx, edx, .. }: CpuidResult) -> Self {
+        Self {
+            ecx: Leaf0x1_SubLeaf0_Ecx { bits: ecx },
+            edx: Leaf0x1_SubLeaf0_Edx { bits: edx },
+        }
+    }
+}
 impl fmt::Debug for FeatureInformation {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match (self.ecx.is_empty(), self.edx.is_empty()) {
@@ -1395,6 +2936,91 @@ impl fmt::Debug for FeatureInformation {
         }
     }
 }
+/// Leaf 2, the legacy cache and TLB descriptors.
+///
+/// <https://en.wikipedia.org/wiki/CPUID#EAX=2:_Cache_and_TLB_Descriptor_information>
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[repr(C)]
+pub struct Leaf2CacheAndTlb {
+    pub eax: u32,
+    pub ebx: u32,
+    pub ecx: u32,
+    pub edx: u32,
+}
+impl Leaf2CacheAndTlb {
+    /// Returns the number of times leaf 2 must be queried to read every descriptor (`eax` bits 0
+    /// to 7), 1 on every CPU since the Pentium 4.
+    #[must_use]
+    pub fn times_to_query(&self) -> u8 {
+        (self.eax & 0b1111_1111) as u8
+    }
+
+    /// Returns the descriptor bytes in register order, excluding null bytes, the times to query
+    /// (see [`Self::times_to_query`]) and the registers with bit 31 set as these hold no valid
+    /// descriptors.
+    #[must_use]
+    pub fn descriptor_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for (i, register) in [self.eax, self.ebx, self.ecx, self.edx].iter().enumerate() {
+            if register & (1 << 31) == 0 {
+                let skip = usize::from(i == 0);
+                bytes.extend(
+                    register.to_le_bytes()[skip..]
+                        .iter()
+                        .filter(|byte| **byte != 0),
+                );
+            }
+        }
+        bytes
+    }
+
+    /// Returns the descriptors, see [`Self::descriptor_bytes`].
+    #[must_use]
+    pub fn descriptors(&self) -> Vec<CacheDescriptor> {
+        self.descriptor_bytes()
+            .into_iter()
+            .map(CacheDescriptor::from)
+            .collect()
+    }
+
+    #[cfg_attr(not(feature = "tracing"), logfn(Trace), logfn_inputs(Info))]
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", ret))]
+    fn covers(&self, other: &Self) -> bool {
+        let descriptors = self.descriptor_bytes();
+        other
+            .descriptor_bytes()
+            .iter()
+            .all(|descriptor| descriptors.contains(descriptor))
+    }
+
+    #[must_use]
+    pub fn sub_leaf<const N: usize>(&self) -> &<Self as SubLeaf<N>>::Output
+    where
+        Self: SubLeaf<N>,
+    {
+        <Self as SubLeaf<N>>::sub_leaf(self)
+    }
+}
+impl Default for Leaf2CacheAndTlb {
+    fn default() -> Self {
+        Self::from(read_host_leaf::<Self>())
+    }
+}
+impl From<RawCpuidEntry> for Leaf2CacheAndTlb {
+    fn from(entry: RawCpuidEntry) -> Self {
+        let RawCpuidEntry {
+            eax, ebx, ecx, edx, ..
+        } = entry;
+        Self { eax, ebx, ecx, edx }
+    }
+}
+/// From the registers of one query e.g. from [`cpuid!`].
+impl From<CpuidResult> for Leaf2CacheAndTlb {
+    fn from(CpuidResult { eax, ebx, ecx, edx }: CpuidResult) -> Self {
+        Self { eax, ebx, ecx, edx }
+    }
+}
+
 /// <https://en.wikipedia.org/wiki/CPUID#EAX=6:_Thermal_and_power_management>
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 #[repr(C)]
@@ -1423,8 +3049,8 @@ impl ThermalAndPowerManagement {
         self.features.eax.bits()
     }
 
-    #[logfn(Trace)]
-    #[logfn_inputs(Info)]
+    #[cfg_attr(not(feature = "tracing"), logfn(Trace), logfn_inputs(Info))]
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", ret))]
     fn covers(&self, other: &Self) -> bool {
         self.features.covers(&other.features)
             && self
@@ -1442,8 +3068,7 @@ impl ThermalAndPowerManagement {
 }
 impl Default for ThermalAndPowerManagement {
     fn default() -> Self {
-        let CpuidResult { eax, ebx, ecx, edx } = unsafe { __cpuid_count(6, 0) };
-        Self::from((eax, ebx, ecx, edx))
+        Self::from(read_host_leaf::<Self>())
     }
 }
 impl From<RawCpuidEntry> for ThermalAndPowerManagement {
@@ -1454,6 +3079,12 @@ impl From<RawCpuidEntry> for ThermalAndPowerManagement {
         Self::from((eax, ebx, ecx, edx))
     }
 }
+/// From the registers of one query e.g. from [`cpuid!`].
+impl From<CpuidResult> for ThermalAndPowerManagement {
+    fn from(CpuidResult { eax, ebx, ecx, edx }: CpuidResult) -> Self {
+        Self::from((eax, ebx, ecx, edx))
+    }
+}
 /// From `(eax,ebx,ecx,edx)`.
 impl From<(u32, u32, u32, u32)> for ThermalAndPowerManagement {
     fn from((eax, ebx, ecx, _edx): (u32, u32, u32, u32)) -> Self {
@@ -1476,8 +3107,8 @@ pub struct ThermalAndPowerManagementFeatures {
     pub ecx: Leaf0x6_SubLeaf0_Ecx,
 }
 impl ThermalAndPowerManagementFeatures {
-    #[logfn(Trace)]
-    #[logfn_inputs(Info)]
+    #[cfg_attr(not(feature = "tracing"), logfn(Trace), logfn_inputs(Info))]
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", ret))]
     fn covers(&self, other: &Self) -> bool {
         self.eax.contains(other.eax) && self.ecx.contains(other.ecx)
     }
@@ -1501,8 +3132,8 @@ impl Leaf6SubLeaf0Ebx {
         (self.0 & 0b0000_1111) as u8
     }
 
-    #[logfn(Trace)]
-    #[logfn_inputs(Info)]
+    #[cfg_attr(not(feature = "tracing"), logfn(Trace), logfn_inputs(Info))]
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", ret))]
     fn covers(&self, other: &Self) -> bool {
         self.number_of_interrupt_thresholds() >= other.number_of_interrupt_thresholds()
     }
@@ -1512,6 +3143,16 @@ impl fmt::Debug for Leaf6SubLeaf0Ebx {
         write!(f, "{}", self.number_of_interrupt_thresholds())
     }
 }
+impl fmt::LowerHex for Leaf6SubLeaf0Ebx {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::LowerHex::fmt(&self.0, f)
+    }
+}
+impl fmt::UpperHex for Leaf6SubLeaf0Ebx {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::UpperHex::fmt(&self.0, f)
+    }
+}
 
 /// <https://en.wikipedia.org/wiki/CPUID#EAX=7,_ECX=0:_Extended_Features> & <https://en.wikipedia.org/wiki/CPUID#EAX=7,_ECX=1:_Extended_Features>
 #[derive(Clone, Eq, PartialEq, Serialize, Deserialize)]
@@ -1527,8 +3168,8 @@ impl ExtendedFeatures {
         Self::default()
     }
 
-    #[logfn(Trace)]
-    #[logfn_inputs(Info)]
+    #[cfg_attr(not(feature = "tracing"), logfn(Trace), logfn_inputs(Info))]
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", ret))]
     fn covers(&self, other: &Self) -> bool {
         self.sub_leaf0.covers(&other.sub_leaf0) && self.sub_leaf1.contains(other.sub_leaf1)
     }
@@ -1548,13 +3189,13 @@ impl Default for ExtendedFeatures {
             ebx: ebx0,
             ecx: ecx0,
             edx: edx0,
-        } = unsafe { __cpuid_count(7, 0) };
+        } = read_host_leaf::<ExtendedFeaturesSubLeaf0>();
         let CpuidResult {
             eax: eax1,
             ebx: ebx1,
             ecx: ecx1,
             edx: edx1,
-        } = unsafe { __cpuid_count(7, 1) };
+        } = read_host_leaf::<Leaf0x7_SubLeaf1_Eax>();
         Self::from(((eax0, ebx0, ecx0, edx0), (eax1, ebx1, ecx1, edx1)))
     }
 }
@@ -1622,48 +3263,151 @@ impl ExtendedFeaturesSubLeaf0 {
         self.edx.bits()
     }
 
-    #[logfn(Trace)]
-    #[logfn_inputs(Info)]
+    #[cfg_attr(not(feature = "tracing"), logfn(Trace), logfn_inputs(Info))]
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", ret))]
     fn covers(&self, other: &Self) -> bool {
-        self.ebx.contains(other.ebx) && self.ecx.contains(other.ecx) && self.edx.contains(other.edx)
+        // MAWAU is a value, the bound tables of `BNDLDX` and `BNDSTX` depend on it exactly, so it
+        // is compared for equality rather than as flags.
+        let ecx_flags = |ecx: &Leaf0x7_SubLeaf0_Ecx| ecx.bits & !Leaf0x7_SubLeaf0_Ecx::MAWAU;
+        self.ebx.contains(other.ebx)
+            && ecx_flags(&self.ecx) & ecx_flags(&other.ecx) == ecx_flags(&other.ecx)
+            && self.ecx.mawau() == other.ecx.mawau()
+            && self.edx.contains(other.edx)
+    }
+}
+/// From the registers of one query e.g. from [`cpuid!`].
+impl From<CpuidResult> for ExtendedFeaturesSubLeaf0 {
+    fn from(CpuidResult { ebx, ecx, edx, .. }: CpuidResult) -> Self {
+        Self {
+            ebx: Leaf0x7_SubLeaf0_Ebx { bits: ebx },
+            ecx: Leaf0x7_SubLeaf0_Ecx { bits: ecx },
+            edx: Leaf0x7_SubLeaf0_Edx { bits: edx },
+        }
     }
 }
 impl fmt::Debug for ExtendedFeatures {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let ebx_printed = if self.sub_leaf0.ebx.is_empty() {
-            false
-        } else {
-            write!(f, "{:?}", self.sub_leaf0.ebx)?;
-            true
-        };
-        let ecx_printed = if self.sub_leaf0.ecx.is_empty() {
-            false
-        } else {
-            if ebx_printed {
-                write!(f, " | ")?;
-            }
-            write!(f, "{:?}", self.sub_leaf0.ecx)?;
-            true
-        };
-        let edx_printed = if self.sub_leaf0.edx.is_empty() {
-            false
-        } else {
-            if ecx_printed {
-                write!(f, " | ")?;
-            }
-            write!(f, "{:?}", self.sub_leaf0.edx)?;
-            true
+        let registers: [(bool, &dyn fmt::Debug); 4] = [
+            (self.sub_leaf0.ebx.is_empty(), &self.sub_leaf0.ebx),
+            (self.sub_leaf0.ecx.is_empty(), &self.sub_leaf0.ecx),
+            (self.sub_leaf0.edx.is_empty(), &self.sub_leaf0.edx),
+            (self.sub_leaf1.is_empty(), &self.sub_leaf1),
+        ];
+        let mut separator = "";
+        for (_, register) in registers.iter().filter(|(empty, _)| !empty) {
+            write!(f, "{separator}{register:?}")?;
+            separator = " | ";
+        }
+        if separator.is_empty() {
+            write!(f, "(empty)")?;
+        }
+        Ok(())
+    }
+}
+
+/// Leaf `0x17`, the SOC vendor attribute enumeration of embedded and system on chip (SOC)
+/// platforms.
+///
+/// <https://www.intel.com/content/www/us/en/developer/articles/technical/intel-sdm.html> Vol. 2A
+/// `CPUID` leaf 17H. This identifies the SOC rather than describing functionality, so
+/// [`Cpuid::covers`] ignores it.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[repr(C)]
+pub struct SocVendorAttributes {
+    /// Sub-leaf 0 `eax`, the highest sub-leaf (`MaxSOCID_Index`).
+    pub max_sub_leaf: u32,
+    /// Sub-leaf 0 `ebx`, the SOC vendor id in bits 0 to 15 and `IsVendorScheme` in bit 16.
+    pub vendor: u32,
+    /// Sub-leaf 0 `ecx`, the project id.
+    pub project_id: u32,
+    /// Sub-leaf 0 `edx`, the stepping id.
+    pub stepping_id: u32,
+    /// Sub-leaves 1 to 3, the NUL padded SOC vendor brand string.
+    ///
+    /// This is blank when `max_sub_leaf` is less than 3 as the sub-leaves are then unsupported.
+    pub brand_string: FixedString<48>,
+}
+impl SocVendorAttributes {
+    /// The reserved bits of sub-leaf 0 `ebx`, 17th to 31st.
+    const RESERVED: u32 = 0b1111_1111_1111_1110_0000_0000_0000_0000;
+
+    /// Returns the SOC vendor id, assigned by the vendor when [`Self::is_vendor_scheme`] and
+    /// otherwise an industry standard id (e.g. a JEDEC id).
+    #[must_use]
+    pub fn soc_vendor_id(&self) -> u16 {
+        (self.vendor & 0b0000_0000_0000_0000_1111_1111_1111_1111) as u16
+    }
+
+    /// If the SOC vendor id is assigned by the vendor rather than by an industry standard.
+    #[must_use]
+    pub fn is_vendor_scheme(&self) -> bool {
+        self.vendor & (1 << 16) != 0
+    }
+
+    /// Returns the registers of sub-leaves 1 to 3, holding the brand string.
+    #[must_use]
+    pub fn brand_string_registers(&self) -> [(u32, u32, u32, u32); 3] {
+        let word = |i: usize| {
+            let bytes = &self.brand_string.0[4 * i..4 * i + 4];
+            u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
         };
-        let _eax_printed = if self.sub_leaf1.is_empty() {
-            false
-        } else {
-            if edx_printed {
-                write!(f, " | ")?;
+        [0, 1, 2].map(|sub_leaf| {
+            let i = 4 * sub_leaf;
+            (word(i), word(i + 1), word(i + 2), word(i + 3))
+        })
+    }
+
+    /// Returns the reserved bits, in place.
+    #[must_use]
+    pub fn reserved(&self) -> u32 {
+        self.vendor & Self::RESERVED
+    }
+
+    /// Reads sub-leaves 0 to 3 from `reader`.
+    fn read<R: CpuidReader>(reader: &mut R) -> Result<Self, R::Error> {
+        let mut sub_leaves = [(0, 0, 0, 0); 4];
+        for (sub_leaf, registers) in (0..).zip(&mut sub_leaves) {
+            let CpuidResult { eax, ebx, ecx, edx } = reader.read(Self::LEAF, sub_leaf)?;
+            *registers = (eax, ebx, ecx, edx);
+        }
+        Ok(Self::from(sub_leaves))
+    }
+
+    #[must_use]
+    pub fn sub_leaf<const N: usize>(&self) -> &<Self as SubLeaf<N>>::Output
+    where
+        Self: SubLeaf<N>,
+    {
+        <Self as SubLeaf<N>>::sub_leaf(self)
+    }
+}
+impl Default for SocVendorAttributes {
+    fn default() -> Self {
+        let Ok(attributes) = Self::read(&mut HostCpuidReader);
+        attributes
+    }
+}
+/// From `(eax,ebx,ecx,edx)` of sub-leaves 0 to 3.
+impl From<[(u32, u32, u32, u32); 4]> for SocVendorAttributes {
+    fn from(sub_leaves: [(u32, u32, u32, u32); 4]) -> Self {
+        let [(max_sub_leaf, vendor, project_id, stepping_id), brand @ ..] = sub_leaves;
+        let mut brand_string = FixedString([0; 48]);
+        if max_sub_leaf >= 3 {
+            let bytes = brand
+                .iter()
+                .flat_map(|(eax, ebx, ecx, edx)| [eax, ebx, ecx, edx])
+                .flat_map(|register| register.to_le_bytes());
+            for (byte, brand_byte) in brand_string.0.iter_mut().zip(bytes) {
+                *byte = brand_byte;
             }
-            write!(f, "{:?}", self.sub_leaf1)?;
-            true
-        };
-        Ok(())
+        }
+        Self {
+            max_sub_leaf,
+            vendor,
+            project_id,
+            stepping_id,
+            brand_string,
+        }
     }
 }
 
@@ -1687,8 +3431,8 @@ impl ExtendedProcessorInfoAndFeatureBits {
         self.ecx.bits()
     }
 
-    #[logfn(Trace)]
-    #[logfn_inputs(Info)]
+    #[cfg_attr(not(feature = "tracing"), logfn(Trace), logfn_inputs(Info))]
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", ret))]
     fn covers(&self, other: &Self) -> bool {
         self.edx.contains(other.edx) && self.ecx.contains(other.ecx)
     }
@@ -1701,6 +3445,15 @@ impl ExtendedProcessorInfoAndFeatureBits {
         <Self as SubLeaf<N>>::sub_leaf(self)
     }
 }
+/// From the registers of one query e.g. from [`cpuid!`].
+impl From<CpuidResult> for ExtendedProcessorInfoAndFeatureBits {
+    fn from(CpuidResult { ecx, edx, .. }: CpuidResult) -> Self {
+        Self {
+            edx: Leaf0x8000_0001_SubLeaf0_Edx { bits: edx },
+            ecx: Leaf0x8000_0001_SubLeaf0_Ecx { bits: ecx },
+        }
+    }
+}
 impl fmt::Debug for ExtendedProcessorInfoAndFeatureBits {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match (self.edx.is_empty(), self.ecx.is_empty()) {
@@ -1712,6 +3465,76 @@ impl fmt::Debug for ExtendedProcessorInfoAndFeatureBits {
     }
 }
 
+/// Leaves `0x8000_0002` to `0x8000_0004`, the processor brand string.
+///
+/// <https://en.wikipedia.org/wiki/CPUID#EAX=80000002h,80000003h,80000004h:_Processor_Brand_String>
+/// This identifies the processor model, so [`Cpuid::covers`] requires it equal.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[repr(C)]
+pub struct BrandString {
+    /// The NUL padded brand string, the registers of each leaf in `eax`, `ebx`, `ecx`, `edx`
+    /// order.
+    ///
+    /// A blank brand string (see [`Cpuid::anonymize`]) is not serialized, and a missing one
+    /// deserializes as blank.
+    #[serde(default, skip_serializing_if = "FixedString::is_blank")]
+    pub brand_string: FixedString<48>,
+}
+impl BrandString {
+    /// Returns the 16 bytes of leaf `0x8000_0002 + i`.
+    fn chunk(&self, i: usize) -> &[u8; 16] {
+        &self.brand_string.0.as_chunks::<16>().0[i]
+    }
+
+    /// Returns the registers of leaves `0x8000_0002` to `0x8000_0004`.
+    #[must_use]
+    pub fn registers(&self) -> [(u32, u32, u32, u32); 3] {
+        [0, 1, 2].map(|i| {
+            let (words, _) = self.chunk(i).as_chunks::<4>();
+            let word = |j: usize| u32::from_le_bytes(words[j]);
+            (word(0), word(1), word(2), word(3))
+        })
+    }
+
+    /// Reads leaves `0x8000_0002` to `0x8000_0004` from `reader`.
+    fn read<R: CpuidReader>(reader: &mut R) -> Result<Self, R::Error> {
+        let mut leaves = [(0, 0, 0, 0); 3];
+        for (leaf, registers) in brand::BRAND_STRING_LEAVES.into_iter().zip(&mut leaves) {
+            let CpuidResult { eax, ebx, ecx, edx } = reader.read(leaf, 0)?;
+            *registers = (eax, ebx, ecx, edx);
+        }
+        Ok(Self::from(leaves))
+    }
+
+    #[must_use]
+    pub fn sub_leaf<const N: usize>(&self) -> &<Self as SubLeaf<N>>::Output
+    where
+        Self: SubLeaf<N>,
+    {
+        <Self as SubLeaf<N>>::sub_leaf(self)
+    }
+}
+impl Default for BrandString {
+    fn default() -> Self {
+        let Ok(brand_string) = Self::read(&mut HostCpuidReader);
+        brand_string
+    }
+}
+/// From `(eax,ebx,ecx,edx)` of leaves `0x8000_0002` to `0x8000_0004`.
+impl From<[(u32, u32, u32, u32); 3]> for BrandString {
+    fn from(leaves: [(u32, u32, u32, u32); 3]) -> Self {
+        let mut brand_string = FixedString([0; 48]);
+        let bytes = leaves
+            .iter()
+            .flat_map(|(eax, ebx, ecx, edx)| [eax, ebx, ecx, edx])
+            .flat_map(|register| register.to_le_bytes());
+        for (byte, brand_byte) in brand_string.0.iter_mut().zip(bytes) {
+            *byte = brand_byte;
+        }
+        Self { brand_string }
+    }
+}
+
 /// <https://en.wikipedia.org/wiki/CPUID#EAX=80000008h:_Virtual_and_Physical_address_Sizes>
 #[derive(Clone, Eq, PartialEq, Serialize, Deserialize)]
 #[repr(C)]
@@ -1739,8 +3562,8 @@ impl VirtualAndPhysicalAddressSizes {
         self.ecx.0
     }
 
-    #[logfn(Trace)]
-    #[logfn_inputs(Info)]
+    #[cfg_attr(not(feature = "tracing"), logfn(Trace), logfn_inputs(Info))]
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", ret))]
     fn covers(&self, other: &Self) -> bool {
         self.eax.covers(&other.eax) && self.ebx.contains(other.ebx) && self.ecx.covers(&other.ecx)
     }
@@ -1753,6 +3576,16 @@ impl VirtualAndPhysicalAddressSizes {
         <Self as SubLeaf<N>>::sub_leaf(self)
     }
 }
+/// From the registers of one query e.g. from [`cpuid!`].
+impl From<CpuidResult> for VirtualAndPhysicalAddressSizes {
+    fn from(CpuidResult { eax, ebx, ecx, .. }: CpuidResult) -> Self {
+        Self {
+            eax: Leaf0x8000_0008_SubLeaf0_Eax(eax),
+            ebx: Leaf0x8000_0008_SubLeaf0_Ebx { bits: ebx },
+            ecx: Leaf0x8000_0008_SubLeaf0_Ecx(ecx),
+        }
+    }
+}
 impl fmt::Debug for VirtualAndPhysicalAddressSizes {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("VirtualAndPhysicalAddressSizes")
@@ -1785,6 +3618,9 @@ impl fmt::Debug for VirtualAndPhysicalAddressSizes {
 #[repr(C)]
 pub struct Leaf0x8000_0008_SubLeaf0_Eax(u32);
 impl Leaf0x8000_0008_SubLeaf0_Eax {
+    /// The reserved bits, 16th to 31st.
+    const RESERVED: u32 = 0b1111_1111_1111_1111_0000_0000_0000_0000;
+
     #[must_use]
     pub fn number_of_physical_address_bits(&self) -> u8 {
         (self.0 & 0b0000_0000_0000_0000_0000_0000_1111_1111) as u8
@@ -1803,7 +3639,25 @@ impl Leaf0x8000_0008_SubLeaf0_Eax {
         self.0 = (self.0 & !0b0000_0000_0000_0000_1111_1111_0000_0000) | (u32::from(x) << 8);
     }
 
-    // 16th to 31st bits reserved
+    /// Returns the reserved bits, in place.
+    #[must_use]
+    pub fn reserved(&self) -> u32 {
+        self.0 & Self::RESERVED
+    }
+
+    /// # Errors
+    ///
+    /// Errors when the given value `x` has bits set outside the reserved bits
+    /// (`if x & !RESERVED == 0 { ... Ok(()) } else { Err(...) }`).
+    pub fn set_reserved(&mut self, x: u32) -> Result<(), &str> {
+        if x & !Self::RESERVED == 0 {
+            self.0 = (self.0 & !Self::RESERVED) | x;
+            Ok(())
+        } else {
+            Err("x & !RESERVED != 0")
+        }
+    }
+
     /// Covers:
     ///
     /// > The only problem that would appear would be if the CPU on which the snapshot was created
@@ -1817,12 +3671,48 @@ impl Leaf0x8000_0008_SubLeaf0_Eax {
     /// > memory to cover the whole address space of the CPU. Regardless, we shouldn’t resume a
     /// > microVM on a host with smaller address size if it was snapshotted on a host with a larger
     /// > address size.
-    #[logfn(Trace)]
-    #[logfn_inputs(Info)]
+    #[cfg_attr(not(feature = "tracing"), logfn(Trace), logfn_inputs(Info))]
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", ret))]
     fn covers(&self, other: &Self) -> bool {
         self.number_of_physical_address_bits() >= other.number_of_physical_address_bits()
             && self.number_of_linear_address_bits() >= other.number_of_linear_address_bits()
     }
+
+    /// Identical to `covers` except when the address sizes differ, yet are still covered, this is
+    /// logged at `Debug`.
+    ///
+    /// In a pool of identical hosts the address sizes should always match, logging covered
+    /// differences lets operators notice heterogeneity before it prevents a migration in the other
+    /// direction.
+    #[cfg_attr(not(feature = "tracing"), logfn(Trace), logfn_inputs(Info))]
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", ret))]
+    #[must_use]
+    pub fn covers_saturating(&self, other: &Self) -> bool {
+        let covers = self.covers(other);
+        if covers
+            && (self.number_of_physical_address_bits() != other.number_of_physical_address_bits()
+                || self.number_of_linear_address_bits() != other.number_of_linear_address_bits())
+        {
+            log::debug!(
+                "Address sizes differ but are covered: physical {} >= {}, linear {} >= {}",
+                self.number_of_physical_address_bits(),
+                other.number_of_physical_address_bits(),
+                self.number_of_linear_address_bits(),
+                other.number_of_linear_address_bits()
+            );
+        }
+        covers
+    }
+}
+impl fmt::LowerHex for Leaf0x8000_0008_SubLeaf0_Eax {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::LowerHex::fmt(&self.0, f)
+    }
+}
+impl fmt::UpperHex for Leaf0x8000_0008_SubLeaf0_Eax {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::UpperHex::fmt(&self.0, f)
+    }
 }
 impl TryFrom<HashMap<&str, u8>> for Leaf0x8000_0008_SubLeaf0_Eax {
     type Error = String;
@@ -1847,11 +3737,23 @@ impl TryFrom<HashMap<&str, u8>> for Leaf0x8000_0008_SubLeaf0_Eax {
 #[repr(C)]
 pub struct Leaf0x8000_0008_SubLeaf0_Ecx(u32);
 impl Leaf0x8000_0008_SubLeaf0_Ecx {
+    /// The reserved bits, 8th to 11th and 18th to 31st.
+    const RESERVED: u32 = 0b1111_1111_1111_1100_0000_1111_0000_0000;
+
     #[must_use]
     pub fn number_of_physical_cores_minus_1(&self) -> u8 {
         (self.0 & 0b0000_0000_0000_0000_0000_0000_1111_1111) as u8
     }
 
+    /// The number of physical cores, 1 to 256 as the field is 8 bits (0th to 7th) holding the
+    /// count minus 1.
+    ///
+    /// This is a `u16` as 256 does not fit a `u8`.
+    #[must_use]
+    pub fn number_of_physical_cores(&self) -> u16 {
+        u16::from(self.number_of_physical_cores_minus_1()) + 1
+    }
+
     // 8th to 11th bits reserved
     #[must_use]
     pub fn log2_of_maximum_apic_id(&self) -> u8 {
@@ -1894,9 +3796,27 @@ impl Leaf0x8000_0008_SubLeaf0_Ecx {
         }
     }
 
-    // 18th to 31st bits reserved
-    #[logfn(Trace)]
-    #[logfn_inputs(Info)]
+    /// Returns the reserved bits, in place.
+    #[must_use]
+    pub fn reserved(&self) -> u32 {
+        self.0 & Self::RESERVED
+    }
+
+    /// # Errors
+    ///
+    /// Errors when the given value `x` has bits set outside the reserved bits
+    /// (`if x & !RESERVED == 0 { ... Ok(()) } else { Err(...) }`).
+    pub fn set_reserved(&mut self, x: u32) -> Result<(), &str> {
+        if x & !Self::RESERVED == 0 {
+            self.0 = (self.0 & !Self::RESERVED) | x;
+            Ok(())
+        } else {
+            Err("x & !RESERVED != 0")
+        }
+    }
+
+    #[cfg_attr(not(feature = "tracing"), logfn(Trace), logfn_inputs(Info))]
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", ret))]
     fn covers(&self, other: &Self) -> bool {
         self.number_of_physical_cores_minus_1() >= other.number_of_physical_cores_minus_1()
             && self.log2_of_maximum_apic_id() >= other.log2_of_maximum_apic_id()
@@ -1904,6 +3824,16 @@ impl Leaf0x8000_0008_SubLeaf0_Ecx {
                 >= other.performance_timestamp_counter_size()
     }
 }
+impl fmt::LowerHex for Leaf0x8000_0008_SubLeaf0_Ecx {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::LowerHex::fmt(&self.0, f)
+    }
+}
+impl fmt::UpperHex for Leaf0x8000_0008_SubLeaf0_Ecx {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::UpperHex::fmt(&self.0, f)
+    }
+}
 impl TryFrom<HashMap<&str, u8>> for Leaf0x8000_0008_SubLeaf0_Ecx {
     type Error = String;
 
@@ -1930,18 +3860,46 @@ impl TryFrom<HashMap<&str, u8>> for Leaf0x8000_0008_SubLeaf0_Ecx {
 
 #[cfg(test)]
 mod tests {
+    use std::collections::BTreeSet;
     use std::fs::{read_to_string, File};
     use std::io::Write;
-    use std::sync::Once;
+    use std::ops::RangeInclusive;
+    use std::ptr;
+    use std::sync::{Mutex, Once};
 
     use simple_logger::SimpleLogger;
 
     static INIT_LOGGER: Once = Once::new();
+    /// Every message logged during the tests, allowing tests to assert on logging.
+    static LOGGED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+    /// Forwards to [`SimpleLogger`] while recording messages in [`LOGGED`].
+    struct TestLogger(SimpleLogger);
+    impl log::Log for TestLogger {
+        fn enabled(&self, metadata: &log::Metadata) -> bool {
+            self.0.enabled(metadata)
+        }
+
+        fn log(&self, record: &log::Record) {
+            if self.enabled(record.metadata()) {
+                LOGGED
+                    .lock()
+                    .unwrap()
+                    .push(format!("{} {}", record.level(), record.args()));
+            }
+            self.0.log(record);
+        }
+
+        fn flush(&self) {
+            self.0.flush();
+        }
+    }
 
     /// Setup function that is only run once, even if called multiple times.
     fn init_logger() {
         INIT_LOGGER.call_once(|| {
-            SimpleLogger::new().init().unwrap();
+            log::set_max_level(log::LevelFilter::Trace);
+            log::set_boxed_logger(Box::new(TestLogger(SimpleLogger::new()))).unwrap();
         });
     }
 
@@ -1950,13 +3908,13 @@ mod tests {
     fn print() {
         init_logger();
         let cpuid = Cpuid::new();
-        println!("cpuid: {:#?}", cpuid);
+        println!("cpuid: {cpuid:#?}");
     }
     #[test]
     fn serialize_deserialzie() {
         init_logger();
         let cpuid = Cpuid::new();
-        println!("cpuid: {:#?}", cpuid);
+        println!("cpuid: {cpuid:#?}");
         let serialized = serde_json::to_string_pretty(&cpuid).unwrap();
         let mut file = File::create("cpuid-x86_64.json").unwrap();
         file.write_all(serialized.as_bytes()).unwrap();
@@ -1964,8 +3922,71 @@ mod tests {
 
         let reserialized = read_to_string("cpuid-x86_64.json").unwrap();
         let deserialized: Cpuid = serde_json::from_str(&reserialized).unwrap();
-        println!("deserialized: {:#?}", deserialized);
+        println!("deserialized: {deserialized:#?}");
+        assert_eq!(cpuid, deserialized);
+    }
+    #[test]
+    fn serialize_edge_values() {
+        init_logger();
+        for registers in [0, u32::MAX] {
+            let mut cpuid = Cpuid::from_fn(|_, _| CpuidResult {
+                eax: registers,
+                ebx: registers,
+                ecx: registers,
+                edx: registers,
+            });
+            cpuid.misc.insert(
+                (0x4000_0000, 0),
+                (registers, registers, registers, registers),
+            );
+            cpuid.misc.insert(
+                (0xB, u32::MAX),
+                (registers, registers, registers, registers),
+            );
+            let serialized = serde_json::to_string(&cpuid).unwrap();
+            let deserialized: Cpuid = serde_json::from_str(&serialized).unwrap();
+            assert_eq!(deserialized, cpuid, "{registers:#x}");
+            assert_eq!(
+                deserialized.to_leaf_map(),
+                cpuid.to_leaf_map(),
+                "{registers:#x}"
+            );
+        }
+    }
+    #[test]
+    fn serialize_reserved_bits() {
+        init_logger();
+        let mut cpuid = Cpuid::new();
+        cpuid
+            .leaf0x01_process_info_and_feature_bits
+            .processor_version_information
+            .set_reserved(1 << 31)
+            .unwrap();
+        let serialized = serde_json::to_string(&cpuid).unwrap();
+        let deserialized: Cpuid = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(
+            deserialized
+                .leaf0x01_process_info_and_feature_bits
+                .processor_version_information
+                .reserved(),
+            1 << 31
+        );
         assert_eq!(cpuid, deserialized);
+
+        // Snapshots from before reserved bits were serialized are still accepted.
+        let mut legacy = serde_json::from_str::<serde_json::Value>(&serialized).unwrap();
+        legacy["leaf0x01_process_info_and_feature_bits"]["processor_version_information"]
+            .as_object_mut()
+            .unwrap()
+            .remove("reserved");
+        let deserialized: Cpuid = serde_json::from_str(&legacy.to_string()).unwrap();
+        assert_eq!(
+            deserialized
+                .leaf0x01_process_info_and_feature_bits
+                .processor_version_information
+                .reserved(),
+            0
+        );
     }
     // #[test]
     // fn checking() {
@@ -1987,6 +4008,9 @@ mod tests {
         let _leaf20 = Leaf::<20>::leaf(&cpuid);
         let _leaf25 = Leaf::<25>::leaf(&cpuid);
         let _leaf0x8000_0001 = Leaf::<0x8000_0001>::leaf(&cpuid);
+        let _leaf0x8000_0002 = Leaf::<0x8000_0002>::leaf(&cpuid);
+        let _leaf0x8000_0003 = Leaf::<0x8000_0003>::leaf(&cpuid);
+        let _leaf0x8000_0004 = Leaf::<0x8000_0004>::leaf(&cpuid);
         let _leaf0x8000_0008 = Leaf::<0x8000_0008>::leaf(&cpuid);
         let _leaf0x8000_001F = Leaf::<0x8000_001F>::leaf(&cpuid);
     }
@@ -1996,6 +4020,7 @@ mod tests {
         let cpuid = Cpuid::new();
         let _leaf0 = cpuid.leaf::<0>();
         let _leaf1 = cpuid.leaf::<1>();
+        let _leaf2 = cpuid.leaf::<2>();
         let _leaf6 = cpuid.leaf::<6>();
         let _leaf7 = cpuid.leaf::<7>();
         let _leaf13 = cpuid.leaf::<13>();
@@ -2003,22 +4028,104 @@ mod tests {
         let _leaf20 = cpuid.leaf::<20>();
         let _leaf25 = cpuid.leaf::<25>();
         let _leaf0x8000_0001 = cpuid.leaf::<0x8000_0001>();
+        let _leaf0x8000_0002 = cpuid.leaf::<0x8000_0002>();
+        let _leaf0x8000_0003 = cpuid.leaf::<0x8000_0003>();
+        let _leaf0x8000_0004 = cpuid.leaf::<0x8000_0004>();
         let _leaf0x8000_0008 = cpuid.leaf::<0x8000_0008>();
         let _leaf0x8000_001F = cpuid.leaf::<0x8000_001F>();
     }
     #[test]
+    fn leaf_fn_fields() {
+        init_logger();
+        let cpuid = Cpuid::new();
+        // Each leaf of `Cpuid::LEAVES` is accessible by `Cpuid::leaf`.
+        let leaves = [
+            ptr::eq(
+                cpuid.leaf::<0>(),
+                &raw const cpuid.leaf0x00_highest_function_parameter_an_manufacturer_id,
+            ),
+            ptr::eq(
+                cpuid.leaf::<1>(),
+                &raw const cpuid.leaf0x01_process_info_and_feature_bits,
+            ),
+            ptr::eq(cpuid.leaf::<2>(), &raw const cpuid.leaf0x02_cache_and_tlb),
+            ptr::eq(
+                cpuid.leaf::<6>(),
+                &raw const cpuid.leaf0x06_thermal_and_power_management,
+            ),
+            ptr::eq(
+                cpuid.leaf::<7>(),
+                &raw const cpuid.leaf0x07_extended_features,
+            ),
+            ptr::eq(
+                cpuid.leaf::<13>(),
+                &raw const cpuid.leaf0x0d_cpuid_feature_bits,
+            ),
+            ptr::eq(
+                cpuid.leaf::<18>(),
+                &raw const cpuid.leaf0x12_cpuid_feature_bits,
+            ),
+            ptr::eq(
+                cpuid.leaf::<20>(),
+                &raw const cpuid.leaf0x14_cpuid_feature_bits,
+            ),
+            ptr::eq(
+                cpuid.leaf::<23>(),
+                &raw const cpuid.leaf0x17_soc_vendor_attributes,
+            ),
+            ptr::eq(
+                cpuid.leaf::<25>(),
+                &raw const cpuid.leaf0x19_cpuid_feature_bits,
+            ),
+            ptr::eq(
+                cpuid.leaf::<0x8000_0001>(),
+                &raw const cpuid.leaf0x8000_0001_highest_function_parameter_an_manufacturer_id,
+            ),
+            ptr::eq(
+                cpuid.leaf::<0x8000_0002>().as_ptr(),
+                &raw const cpuid.leaf0x8000_0002_brand_string.brand_string.0[0],
+            ),
+            ptr::eq(
+                cpuid.leaf::<0x8000_0003>().as_ptr(),
+                &raw const cpuid.leaf0x8000_0002_brand_string.brand_string.0[16],
+            ),
+            ptr::eq(
+                cpuid.leaf::<0x8000_0004>().as_ptr(),
+                &raw const cpuid.leaf0x8000_0002_brand_string.brand_string.0[32],
+            ),
+            ptr::eq(
+                cpuid.leaf::<0x8000_0008>(),
+                &raw const cpuid.leaf0x8000_0008_virtual_and_physical_address_sizes,
+            ),
+            ptr::eq(
+                cpuid.leaf::<0x8000_001F>(),
+                &raw const cpuid.leaf0x8000_001F_cpuid_feature_bits,
+            ),
+        ];
+        assert_eq!(leaves.len(), Cpuid::LEAVES.len());
+        assert!(leaves.into_iter().all(|eq| eq));
+
+        let leaf7 = &cpuid.leaf0x07_extended_features;
+        assert!(ptr::eq(leaf7.sub_leaf::<0>(), &raw const leaf7.sub_leaf0));
+        assert!(ptr::eq(leaf7.sub_leaf::<1>(), &raw const leaf7.sub_leaf1));
+    }
+    #[test]
     fn sub_leaf_fn_index() {
         init_logger();
         let cpuid = Cpuid::new();
         let _sub_leaf0_0 = cpuid.leaf::<0>().sub_leaf::<0>();
         let _sub_leaf1_0 = cpuid.leaf::<1>().sub_leaf::<0>();
+        let _sub_leaf2_0 = cpuid.leaf::<2>().sub_leaf::<0>();
         let _sub_leaf6_0 = cpuid.leaf::<6>().sub_leaf::<0>();
         let _sub_leaf7_0 = cpuid.leaf::<7>().sub_leaf::<0>();
         let _sub_leaf7_1 = cpuid.leaf::<7>().sub_leaf::<1>();
         let _sub_leaf13_1 = cpuid.leaf::<13>().sub_leaf::<1>();
         let _sub_leaf18_0 = cpuid.leaf::<18>().sub_leaf::<0>();
         let _sub_leaf20_0 = cpuid.leaf::<20>().sub_leaf::<0>();
+        let _sub_leaf23_0 = cpuid.leaf::<23>().sub_leaf::<0>();
+        let _sub_leaf25_0 = cpuid.leaf::<25>().sub_leaf::<0>();
         let _sub_leaf0x8000_0001_0 = cpuid.leaf::<0x8000_0001>().sub_leaf::<0>();
+        let _sub_leaf0x8000_0002_0 = cpuid.leaf0x8000_0002_brand_string.sub_leaf::<0>();
         let _sub_leaf0x8000_0008_0 = cpuid.leaf::<0x8000_0008>().sub_leaf::<0>();
         let _sub_leaf0x8000_001F_0 = cpuid.leaf::<0x8000_001F>().sub_leaf::<0>();
     }
@@ -2062,4 +4169,1674 @@ mod tests {
 
         let _register0x8000_001F_0_eax = cpuid.leaf::<0x8000_001F>().sub_leaf::<0>().eax();
     }
+    #[test]
+    fn set_manufacturer() {
+        init_logger();
+        let mut leaf = HighestFunctionParameterAndManufacturerID::new();
+        assert_eq!(leaf.set_manufacturer("GenuineIntel"), Ok(()));
+        assert_eq!(&leaf.manufacturer_id.0, b"GenuineIntel");
+        assert_eq!(leaf.ebx(), u32::from_ne_bytes(*b"Genu"));
+        assert_eq!(leaf.edx(), u32::from_ne_bytes(*b"ineI"));
+        assert_eq!(leaf.ecx(), u32::from_ne_bytes(*b"ntel"));
+
+        let cpuid = Cpuid::new().with_manufacturer("AuthenticAMD").unwrap();
+        assert_eq!(
+            &cpuid
+                .leaf0x00_highest_function_parameter_an_manufacturer_id
+                .manufacturer_id
+                .0,
+            b"AuthenticAMD"
+        );
+    }
+    #[test]
+    fn covers_leaves() {
+        init_logger();
+        let host = Cpuid::new();
+        let mut guest = host.clone();
+        guest.leaf0x07_extended_features.sub_leaf0.ebx |= Leaf0x7_SubLeaf0_Ebx::avx512_f;
+        guest
+            .leaf0x8000_0008_virtual_and_physical_address_sizes
+            .ecx
+            .set_log2_of_maximum_apic_id(
+                host.leaf0x8000_0008_virtual_and_physical_address_sizes
+                    .ecx
+                    .log2_of_maximum_apic_id()
+                    + 1,
+            )
+            .unwrap();
+        // The host lacks the topology, and may lack the AVX-512 support, of the guest.
+        assert!(!host.covers(&guest));
+        assert!(!host.covers_leaves(&guest, &[0x8000_0008]));
+        assert!(host.covers_leaves(&guest, &[0x8000_0001, 0x4000_0000]));
+        assert_eq!(
+            host.covers_leaves(&guest, &[1, 7]),
+            host.leaf0x07_extended_features
+                .sub_leaf0
+                .ebx
+                .contains(Leaf0x7_SubLeaf0_Ebx::avx512_f)
+        );
+        assert!(host.covers_leaves(&host, &Cpuid::LEAVES));
+        assert!(host.covers_leaves(&guest, &[]));
+    }
+    #[test]
+    fn covers_every_leaf() {
+        init_logger();
+        let host = Cpuid::default();
+        let mut guests = Vec::new();
+        for flags in &crate::features::FLAGS_REGISTERS {
+            for (_, bit) in flags.flags {
+                let mut guest = host.clone();
+                *(flags.get_mut)(&mut guest) |= bit;
+                guests.push(guest);
+            }
+        }
+        // The leaves without flags.
+        guests.push(host.clone().with_manufacturer("GenuineIntel").unwrap());
+        let mut guest = host.clone();
+        guest.leaf0x02_cache_and_tlb.ebx = 0x2C;
+        guests.push(guest);
+        for i in [0, 16, 32] {
+            let mut guest = host.clone();
+            guest.leaf0x8000_0002_brand_string.brand_string.0[i] = b'A';
+            guests.push(guest);
+        }
+
+        // Each flag of every leaf is compared, except leaf 0x17 which is not.
+        let mut compared = BTreeSet::new();
+        for guest in &guests {
+            let differing = host
+                .compare_verbose(guest)
+                .into_iter()
+                .filter(|(_, ordering)| *ordering != LeafOrdering::Equal)
+                .map(|(leaf, _)| leaf)
+                .collect::<Vec<_>>();
+            assert_eq!(differing.len(), 1, "{guest:?}");
+            compared.extend(differing);
+        }
+        let mut guest = host.clone();
+        guest.leaf0x17_soc_vendor_attributes.vendor = 1;
+        assert!(host.covers(&guest) && guest.covers(&host));
+        assert_eq!(
+            compared.into_iter().collect::<Vec<_>>(),
+            Cpuid::LEAVES
+                .into_iter()
+                .filter(|leaf| *leaf != 0x17)
+                .collect::<Vec<_>>()
+        );
+    }
+    #[test]
+    fn without_leaf() {
+        init_logger();
+        let mut host = Cpuid::new();
+        host.leaf0x07_extended_features.sub_leaf0.ebx |= Leaf0x7_SubLeaf0_Ebx::fsgsbase;
+        host.misc.insert((0x4000_0000, 0), (1, 2, 3, 4));
+        let without = host.without_leaf(7);
+        assert!(host.covers(&without));
+        assert!(!without.covers(&host));
+        assert_eq!(without.leaf0x07_extended_features.sub_leaf0.ebx.bits, 0);
+        assert_eq!(without.leaf0x07_extended_features.sub_leaf1.bits, 0);
+        let diff = without.diff(&host);
+        assert!(diff.missing_features.is_empty());
+        // Every other leaf is unchanged.
+        assert_eq!(without.without_leaf(7), without);
+        assert_eq!(
+            host.without_leaf(0x4000_0000).misc[&(0x4000_0000, 0)],
+            (0, 0, 0, 0)
+        );
+        assert_eq!(
+            host.without_leaf(0x4000_0000).without_leaf(7),
+            without.without_leaf(0x4000_0000)
+        );
+        let mut restored = without.clone();
+        restored.leaf0x07_extended_features = host.leaf0x07_extended_features.clone();
+        assert_eq!(restored, host);
+    }
+    #[test]
+    fn highest_sub_leaf() {
+        init_logger();
+        let mut cpuid = Cpuid::new();
+        assert_eq!(cpuid.highest_sub_leaf(1), Some(0));
+        assert!(cpuid.highest_sub_leaf(7) >= Some(1));
+        assert_eq!(cpuid.highest_sub_leaf(0x4000_0000), None);
+
+        cpuid.misc.insert((0xB, 0), (0, 0, 0, 0));
+        cpuid.misc.insert((0xB, 2), (0, 0, 0, 0));
+        cpuid.misc.insert((0xC, 0), (0, 0, 0, 0));
+        assert_eq!(cpuid.highest_sub_leaf(0xB), Some(2));
+    }
+    #[test]
+    fn decode_warnings() {
+        init_logger();
+        let mut reader = FnCpuidReader(|leaf, _| match leaf {
+            0 => CpuidResult {
+                eax: 0x1F,
+                ebx: u32::from_ne_bytes([0xFF, 0xFE, b'n', b'u']),
+                ecx: u32::from_ne_bytes(*b"ntel"),
+                edx: u32::from_ne_bytes(*b"ineI"),
+            },
+            1 => CpuidResult {
+                eax: 0x8000_0000,
+                ebx: 0,
+                ecx: 0,
+                edx: 0,
+            },
+            _ => CpuidResult {
+                eax: 0,
+                ebx: 0,
+                ecx: 0,
+                edx: 0,
+            },
+        });
+        let Ok((cpuid, warnings)) = Cpuid::try_from_reader(&mut reader);
+        assert_eq!(
+            warnings,
+            [
+                DecodeWarning::InvalidManufacturerId(*b"\xFF\xFEnuineIntel"),
+                DecodeWarning::ReservedBits {
+                    leaf: 1,
+                    sub_leaf: 0,
+                    bits: 0x8000_0000
+                },
+            ]
+        );
+        // Formatting does not panic on the invalid manufacturer id.
+        assert!(format!("{cpuid:?}").contains("nuineIntel"));
+        assert_eq!(Cpuid::new().decode_warnings(), []);
+    }
+    #[test]
+    fn clear_reserved_bits() {
+        init_logger();
+        let mut cpuid = Cpuid::new();
+        cpuid.clear_reserved_bits();
+        let features = cpuid.active_features_sorted();
+        cpuid
+            .leaf0x01_process_info_and_feature_bits
+            .processor_version_information
+            .set_reserved(1 << 31)
+            .unwrap();
+        cpuid
+            .leaf0x06_thermal_and_power_management
+            .number_of_interrupt_thresholds
+            .0 |= 1 << 8;
+        let ecx = &mut cpuid.leaf0x07_extended_features.sub_leaf0.ecx;
+        ecx.bits = (ecx.bits & !Leaf0x7_SubLeaf0_Ecx::MAWAU) | (1 << 15) | (2 << 17);
+        // AMD's guest physical address size is not reserved.
+        let eax = &mut cpuid.leaf0x8000_0008_virtual_and_physical_address_sizes.eax;
+        eax.0 = (eax.0 & !0x00FF_0000) | 0x0030_0000 | (1 << 24);
+
+        let mut snapshot: Cpuid =
+            serde_json::from_str(&serde_json::to_string(&cpuid).unwrap()).unwrap();
+        assert_eq!(
+            snapshot.reserved_bits_set(),
+            [
+                DecodeWarning::ReservedBits {
+                    leaf: 1,
+                    sub_leaf: 0,
+                    bits: 1 << 31
+                },
+                DecodeWarning::ReservedBits {
+                    leaf: 6,
+                    sub_leaf: 0,
+                    bits: 1 << 8
+                },
+                DecodeWarning::ReservedBits {
+                    leaf: 7,
+                    sub_leaf: 0,
+                    bits: 1 << 15
+                },
+                DecodeWarning::ReservedBits {
+                    leaf: 0x8000_0008,
+                    sub_leaf: 0,
+                    bits: 1 << 24
+                },
+            ]
+        );
+        snapshot.clear_reserved_bits();
+        assert_eq!(snapshot.reserved_bits_set(), []);
+        assert_eq!(snapshot.decode_warnings(), []);
+        assert_eq!(snapshot.active_features_sorted(), features);
+        assert_eq!(snapshot.leaf0x07_extended_features.sub_leaf0.ecx.mawau(), 2);
+        assert_eq!(
+            snapshot
+                .leaf0x8000_0008_virtual_and_physical_address_sizes
+                .eax
+                .0
+                & 0x00FF_0000,
+            0x0030_0000
+        );
+    }
+    #[test]
+    fn check_duplicate_bits() {
+        init_logger();
+        let mut cpuid = Cpuid::default();
+        cpuid
+            .leaf0x01_process_info_and_feature_bits
+            .feature_information
+            .edx =
+            Leaf0x1_SubLeaf0_Edx::fpu | Leaf0x1_SubLeaf0_Edx::pae | Leaf0x1_SubLeaf0_Edx::mmx;
+        // Intel reports the duplicate bits clear.
+        assert_eq!(cpuid.check_duplicate_bits(), None);
+        // AMD duplicates them.
+        let edx = &mut cpuid
+            .leaf0x8000_0001_highest_function_parameter_an_manufacturer_id
+            .edx;
+        edx.bits = Leaf0x8000_0001_SubLeaf0_Edx::syscall.bits | (1 << 0) | (1 << 6) | (1 << 23);
+        assert_eq!(cpuid.check_duplicate_bits(), None);
+        // `pae` set in one leaf only.
+        cpuid
+            .leaf0x8000_0001_highest_function_parameter_an_manufacturer_id
+            .edx
+            .bits &= !(1 << 6);
+        assert_eq!(
+            cpuid.check_duplicate_bits(),
+            Some(DecodeWarning::DuplicateBitsMismatch { bits: 1 << 6 })
+        );
+        assert_eq!(
+            cpuid.decode_warnings(),
+            [DecodeWarning::DuplicateBitsMismatch { bits: 1 << 6 }]
+        );
+    }
+    #[test]
+    fn leaf0_ignores_sub_leaf() {
+        init_logger();
+        let CpuidResult { eax, ebx, ecx, edx } = core::arch::x86_64::__cpuid(0);
+        let Ok(read) = HostCpuidReader.read(0, 0);
+        for result in [__cpuid_count(0, 0), __cpuid_count(0, 1), read] {
+            assert_eq!(
+                (result.eax, result.ebx, result.ecx, result.edx),
+                (eax, ebx, ecx, edx)
+            );
+        }
+        assert_eq!(
+            HighestFunctionParameterAndManufacturerID::default(),
+            HighestFunctionParameterAndManufacturerID::from((eax, ebx, ecx, edx))
+        );
+    }
+    #[test]
+    fn hex() {
+        init_logger();
+        let cpuid = Cpuid::new();
+        let leaf1 = &cpuid.leaf0x01_process_info_and_feature_bits;
+        let eax = __cpuid_count(1, 0).eax;
+        assert_eq!(
+            format!("{:08x}", leaf1.processor_version_information),
+            format!("{eax:08x}")
+        );
+        assert_eq!(
+            format!("{:#X}", leaf1.processor_version_information),
+            format!("{eax:#X}")
+        );
+        assert_eq!(
+            format!(
+                "{:x}",
+                cpuid.leaf0x8000_0008_virtual_and_physical_address_sizes.eax
+            ),
+            format!(
+                "{:x}",
+                cpuid
+                    .leaf0x8000_0008_virtual_and_physical_address_sizes
+                    .eax()
+            )
+        );
+        assert_eq!(
+            format!("{:08x}", leaf1.feature_information.ecx),
+            format!("{:08x}", leaf1.ecx())
+        );
+    }
+    #[test]
+    fn cpuid_macro() {
+        init_logger();
+        let cpuid = Cpuid::new();
+        let leaf1 = &cpuid.leaf0x01_process_info_and_feature_bits;
+        assert_eq!(cpuid!(leaf = 1, sub_leaf = 0).ecx, leaf1.ecx());
+        assert_eq!(cpuid!(leaf = 1).edx, leaf1.edx());
+        assert_eq!(
+            cpuid!(7, 0).ebx,
+            cpuid.leaf0x07_extended_features.sub_leaf0.ebx()
+        );
+        assert_eq!(
+            cpuid!(0x8000_0008).eax,
+            cpuid
+                .leaf0x8000_0008_virtual_and_physical_address_sizes
+                .eax()
+        );
+    }
+    #[test]
+    fn from_results() {
+        init_logger();
+        let result = |eax, ebx, ecx, edx| CpuidResult { eax, ebx, ecx, edx };
+        let mut table = vec![
+            (
+                0,
+                0,
+                result(
+                    0x16,
+                    u32::from_ne_bytes(*b"Genu"),
+                    u32::from_ne_bytes(*b"ntel"),
+                    u32::from_ne_bytes(*b"ineI"),
+                ),
+            ),
+            (
+                1,
+                0,
+                result(0x0009_06EA, 0x0010_0800, 0x7FFA_FBFF, 0xBFEB_FBFF),
+            ),
+        ];
+        // Only leaves 0 and 1 are given.
+        assert_eq!(
+            Cpuid::from_results(&table),
+            Err(DecodeError::MissingLeaf {
+                leaf: 2,
+                sub_leaf: 0
+            })
+        );
+
+        table.extend(
+            Cpuid::from_fn(|_, _| result(0, 0, 0, 0))
+                .to_leaf_map()
+                .into_iter()
+                .filter(|((leaf, _), _)| *leaf > 1)
+                .map(|((leaf, sub_leaf), registers)| (leaf, sub_leaf, registers)),
+        );
+        let cpuid = Cpuid::from_results(&table).unwrap();
+        let leaf0 = &cpuid.leaf0x00_highest_function_parameter_an_manufacturer_id;
+        assert_eq!(&leaf0.manufacturer_id.0, b"GenuineIntel");
+        assert_eq!(leaf0.highest_calling_parameter, 0x16);
+        let leaf1 = &cpuid.leaf0x01_process_info_and_feature_bits;
+        assert_eq!(leaf1.processor_version_information.family_id(), 6);
+        assert_eq!(leaf1.additional_information.clflush_line_size, 8);
+        assert!(leaf1
+            .feature_information
+            .ecx
+            .contains(Leaf0x1_SubLeaf0_Ecx::sse3 | Leaf0x1_SubLeaf0_Ecx::avx));
+        assert!(cpuid.misc.is_empty());
+    }
+    #[test]
+    fn set_guest_phys_addr_bits() {
+        init_logger();
+        let mut host = Cpuid::new();
+        host.leaf0x8000_0008_virtual_and_physical_address_sizes
+            .eax
+            .set_number_of_physical_address_bits(46);
+        let mut guest = host.clone();
+        let bits = |cpuid: &Cpuid| {
+            cpuid
+                .leaf0x8000_0008_virtual_and_physical_address_sizes
+                .eax
+                .number_of_physical_address_bits()
+        };
+
+        assert_eq!(guest.set_guest_phys_addr_bits(40, &host), Ok(()));
+        assert_eq!(bits(&guest), 40);
+        assert!(host.covers(&guest));
+        // Clamped to the host.
+        assert_eq!(guest.set_guest_phys_addr_bits(52, &host), Ok(()));
+        assert_eq!(bits(&guest), 46);
+        assert!(host.covers(&guest));
+
+        assert!(guest.set_guest_phys_addr_bits(0, &host).is_err());
+        assert!(guest.set_guest_phys_addr_bits(65, &host).is_err());
+        assert_eq!(bits(&guest), 46);
+    }
+    #[test]
+    fn max_phys_addr() {
+        init_logger();
+        let mut cpuid = Cpuid::new();
+        cpuid
+            .leaf0x8000_0008_virtual_and_physical_address_sizes
+            .eax
+            .set_number_of_physical_address_bits(46);
+        assert_eq!(cpuid.max_phys_addr(), 46);
+        cpuid.misc.insert((0x8000_0000, 0), (0x8000_0008, 0, 0, 0));
+        assert_eq!(cpuid.max_phys_addr(), 46);
+
+        // Falls back on the architectural default when the extended leaf is unsupported.
+        cpuid.misc.insert((0x8000_0000, 0), (0x8000_0004, 0, 0, 0));
+        let edx = &mut cpuid
+            .leaf0x01_process_info_and_feature_bits
+            .feature_information
+            .edx;
+        edx.insert(Leaf0x1_SubLeaf0_Edx::pae);
+        assert_eq!(cpuid.max_phys_addr(), 36);
+        cpuid
+            .leaf0x01_process_info_and_feature_bits
+            .feature_information
+            .edx
+            .remove(Leaf0x1_SubLeaf0_Edx::pae);
+        assert_eq!(cpuid.max_phys_addr(), 32);
+
+        cpuid.misc.clear();
+        cpuid
+            .leaf0x8000_0008_virtual_and_physical_address_sizes
+            .eax
+            .set_number_of_physical_address_bits(0);
+        assert_eq!(cpuid.max_phys_addr(), 32);
+    }
+    #[test]
+    fn migration_compatible() {
+        init_logger();
+        let host = Cpuid::new();
+        let mut other_cpu = host.clone();
+        other_cpu
+            .leaf0x01_process_info_and_feature_bits
+            .additional_information
+            .local_apic_id ^= 1;
+        assert!(host.migration_compatible(&other_cpu));
+
+        // One way only
+        let mut avx512 = host.clone();
+        avx512.leaf0x07_extended_features.sub_leaf0.ebx |= Leaf0x7_SubLeaf0_Ebx::avx512_f;
+        avx512.leaf0x07_extended_features.sub_leaf0.ebx &= !Leaf0x7_SubLeaf0_Ebx::avx2;
+        let mut both = avx512.clone();
+        both.leaf0x07_extended_features.sub_leaf0.ebx |= Leaf0x7_SubLeaf0_Ebx::avx2;
+        assert!(both.covers(&avx512));
+        assert!(!both.migration_compatible(&avx512));
+        assert!(!avx512.migration_compatible(&both));
+
+        // Neither way
+        let mut avx2 = avx512.clone();
+        avx2.leaf0x07_extended_features.sub_leaf0.ebx &= !Leaf0x7_SubLeaf0_Ebx::avx512_f;
+        avx2.leaf0x07_extended_features.sub_leaf0.ebx |= Leaf0x7_SubLeaf0_Ebx::avx2;
+        assert!(!avx512.covers(&avx2) && !avx2.covers(&avx512));
+        assert!(!avx512.migration_compatible(&avx2));
+    }
+    #[test]
+    fn additional_information() {
+        init_logger();
+        let leaf = ProcessorInfoAndFeatureBits::from((0, 0x0310_0801, 0, 0));
+        assert_eq!(
+            leaf.additional_information,
+            AdditionalInformation {
+                brand_index: 0x01,
+                clflush_line_size: 0x08,
+                maximum_addressable_logical_processor_ids: 0x10,
+                local_apic_id: 0x03,
+            }
+        );
+        assert_eq!(leaf.ebx(), 0x0310_0801);
+    }
+    #[test]
+    fn is_virtual_machine() {
+        init_logger();
+        let mut cpuid = Cpuid::new();
+        let ecx = &mut cpuid
+            .leaf0x01_process_info_and_feature_bits
+            .feature_information
+            .ecx;
+        ecx.remove(Leaf0x1_SubLeaf0_Ecx::hypervisor);
+        cpuid.misc.insert(
+            (0x4000_0000, 0),
+            (
+                0x4000_0001,
+                u32::from_ne_bytes(*b"KVMK"),
+                u32::from_ne_bytes(*b"VMKV"),
+                u32::from_ne_bytes(*b"M\0\0\0"),
+            ),
+        );
+        assert!(!cpuid.is_virtual_machine());
+        assert_eq!(cpuid.hypervisor_vendor(), None);
+
+        cpuid
+            .leaf0x01_process_info_and_feature_bits
+            .feature_information
+            .ecx
+            .insert(Leaf0x1_SubLeaf0_Ecx::hypervisor);
+        assert!(cpuid.is_virtual_machine());
+        assert_eq!(
+            cpuid.hypervisor_vendor(),
+            Some(FixedString(*b"KVMKVMKVM\0\0\0"))
+        );
+    }
+    #[test]
+    fn set_manufacturer_too_short() {
+        init_logger();
+        let mut leaf = HighestFunctionParameterAndManufacturerID::new();
+        let before = leaf.clone();
+        assert_eq!(leaf.set_manufacturer("Intel"), Err("s.len() < 12"));
+        assert_eq!(leaf, before);
+        assert!(Cpuid::new().with_manufacturer("").is_err());
+    }
+    #[test]
+    fn set_manufacturer_too_long() {
+        init_logger();
+        let mut leaf = HighestFunctionParameterAndManufacturerID::new();
+        let before = leaf.clone();
+        assert_eq!(leaf.set_manufacturer("GenuineIntel!"), Err("s.len() > 12"));
+        assert_eq!(leaf, before);
+        assert!(Cpuid::new().with_manufacturer("AuthenticAMD ").is_err());
+    }
+    #[test]
+    fn extended_features_debug() {
+        init_logger();
+        let mut features = ExtendedFeatures::from(((0, 0, 0, 0), (0, 0, 0, 0)));
+        assert_eq!(format!("{features:?}"), "(empty)");
+        features.sub_leaf0.edx = Leaf0x7_SubLeaf0_Edx::fsrm;
+        assert_eq!(format!("{features:?}"), "fsrm");
+        features.sub_leaf0.ebx = Leaf0x7_SubLeaf0_Ebx::avx2;
+        features.sub_leaf1 = Leaf0x7_SubLeaf1_Eax::avx_vnni;
+        // The separator after `ebx` does not depend on `ecx` being set.
+        assert_eq!(format!("{features:?}"), "avx2 | fsrm | avx_vnni");
+        features.sub_leaf0.ecx = Leaf0x7_SubLeaf0_Ecx::umip | Leaf0x7_SubLeaf0_Ecx::pku;
+        assert_eq!(
+            format!("{features:?}"),
+            "avx2 | umip | pku | fsrm | avx_vnni"
+        );
+    }
+    #[test]
+    fn query_single() {
+        init_logger();
+        let cpuid = Cpuid::new();
+        let leaf1 = Cpuid::query_single::<1, 0>();
+        let info = &cpuid.leaf0x01_process_info_and_feature_bits;
+        assert_eq!(leaf1.eax, info.processor_version_information.0);
+        // The local APIC id, bits 24 to 31, depends on which CPU ran the query.
+        let additional = &info.additional_information;
+        assert_eq!(
+            leaf1.ebx.to_le_bytes()[..3],
+            [
+                additional.brand_index,
+                additional.clflush_line_size,
+                additional.maximum_addressable_logical_processor_ids
+            ]
+        );
+        assert_eq!(leaf1.ecx, info.feature_information.ecx.bits());
+        assert_eq!(leaf1.edx, info.feature_information.edx.bits());
+
+        let leaf7 = Cpuid::query_single::<7, 1>();
+        assert_eq!(leaf7.eax, cpuid.leaf0x07_extended_features.sub_leaf1.bits());
+        assert_eq!(Cpuid::query_single::<0, 0>(), cpuid!(0));
+    }
+    #[test]
+    fn compare_verbose() {
+        init_logger();
+        let mut cpuid = Cpuid::default();
+        let mut other = cpuid.clone();
+        cpuid.leaf0x07_extended_features.sub_leaf0.ebx |= Leaf0x7_SubLeaf0_Ebx::avx2;
+        other
+            .leaf0x01_process_info_and_feature_bits
+            .feature_information
+            .ecx |= Leaf0x1_SubLeaf0_Ecx::sse3;
+        other.leaf0x07_extended_features.sub_leaf0.ecx |= Leaf0x7_SubLeaf0_Ecx::umip;
+        cpuid.leaf0x0d_cpuid_feature_bits = Leaf0xD_SubLeaf1_Eax::xsaveopt;
+
+        let ordering = cpuid.compare_verbose(&other);
+        assert_eq!(
+            ordering.iter().map(|(leaf, _)| *leaf).collect::<Vec<_>>(),
+            Cpuid::LEAVES
+        );
+        let of = |leaf| ordering.iter().find(|(l, _)| *l == leaf).unwrap().1;
+        assert_eq!(of(1), LeafOrdering::Covered);
+        // `cpuid` has avx2 while `other` has umip.
+        assert_eq!(of(7), LeafOrdering::Incomparable);
+        assert_eq!(of(0xD), LeafOrdering::Covers);
+        assert_eq!(of(0x8000_0008), LeafOrdering::Equal);
+        assert!(!cpuid.covers(&other) && !other.covers(&cpuid));
+
+        // Reversed, the orderings reverse.
+        let reversed = other.compare_verbose(&cpuid);
+        let of = |leaf| reversed.iter().find(|(l, _)| *l == leaf).unwrap().1;
+        assert_eq!(of(1), LeafOrdering::Covers);
+        assert_eq!(of(7), LeafOrdering::Incomparable);
+        assert_eq!(of(0xD), LeafOrdering::Covered);
+
+        assert!(cpuid
+            .compare_verbose(&cpuid)
+            .iter()
+            .all(|(_, ordering)| *ordering == LeafOrdering::Equal));
+    }
+    #[test]
+    fn covers_mawau() {
+        init_logger();
+        let mut host = Cpuid::new();
+        let ecx = &mut host.leaf0x07_extended_features.sub_leaf0.ecx;
+        ecx.bits = (ecx.bits & !Leaf0x7_SubLeaf0_Ecx::MAWAU) | (3 << 17);
+        ecx.insert(Leaf0x7_SubLeaf0_Ecx::rdpid);
+        assert_eq!(host.leaf0x07_extended_features.sub_leaf0.ecx.mawau(), 3);
+        let mut guest = host.clone();
+        let ecx = &mut guest.leaf0x07_extended_features.sub_leaf0.ecx;
+        ecx.bits = (ecx.bits & !Leaf0x7_SubLeaf0_Ecx::MAWAU) | (1 << 17);
+        // Bit-wise the host MAWAU contains the guest MAWAU, but the values differ.
+        assert!(!host.covers(&guest));
+        assert!(!guest.covers(&host));
+        assert_eq!(guest.diff(&host).mismatched, ["mawau"]);
+        assert_eq!(
+            host.intersection(&guest),
+            Err(BaselineError::Mismatch("mawau"))
+        );
+
+        // With equal MAWAU the flags are still compared as flags.
+        guest.leaf0x07_extended_features.sub_leaf0.ecx =
+            host.leaf0x07_extended_features.sub_leaf0.ecx;
+        guest
+            .leaf0x07_extended_features
+            .sub_leaf0
+            .ecx
+            .remove(Leaf0x7_SubLeaf0_Ecx::rdpid);
+        assert!(host.covers(&guest));
+        assert!(!guest.covers(&host));
+    }
+    #[test]
+    fn covers_saturating_logs_differing_address_sizes() {
+        init_logger();
+        let mut host = Leaf0x8000_0008_SubLeaf0_Eax(0);
+        host.set_number_of_physical_address_bits(46);
+        host.set_number_of_linear_address_bits(57);
+        let mut guest = host.clone();
+        guest.set_number_of_physical_address_bits(43);
+
+        assert_eq!(host.covers_saturating(&guest), host.covers(&guest));
+        assert!(host.covers_saturating(&guest));
+        assert!(!guest.covers_saturating(&host));
+        assert!(LOGGED.lock().unwrap().iter().any(|message| message
+            == "DEBUG Address sizes differ but are covered: physical 46 >= 43, linear 57 >= 57"));
+    }
+    #[test]
+    fn new_serializing() {
+        init_logger();
+        let mut cpuid = Cpuid::new();
+        let mut serializing = Cpuid::new_serializing();
+        // The local APIC id depends on which CPU ran the query.
+        cpuid.normalize();
+        serializing.normalize();
+        assert_eq!(cpuid, serializing);
+    }
+    /// A [`CpuidReader`] recording each leaf and sub-leaf queried of the reader it wraps.
+    struct RecordingCpuidReader<R> {
+        reader: R,
+        queries: Vec<(u32, u32)>,
+    }
+    impl<R: CpuidReader> CpuidReader for RecordingCpuidReader<R> {
+        type Error = R::Error;
+
+        fn read(&mut self, leaf: u32, sub_leaf: u32) -> Result<CpuidResult, Self::Error> {
+            self.queries.push((leaf, sub_leaf));
+            self.reader.read(leaf, sub_leaf)
+        }
+    }
+    #[test]
+    fn from_reader_queries() {
+        init_logger();
+        // Each field is read from its own leaf, e.g. leaf 0x8000_001F is not read from 0x8000_0008.
+        let expected = [
+            (0x0, 0),
+            (0x1, 0),
+            (0x2, 0),
+            (0x6, 0),
+            (0x7, 0),
+            (0x7, 1),
+            (0xD, 1),
+            (0x12, 0),
+            (0x14, 0),
+            (0x17, 0),
+            (0x17, 1),
+            (0x17, 2),
+            (0x17, 3),
+            (0x19, 0),
+            (0x8000_0001, 0),
+            (0x8000_0002, 0),
+            (0x8000_0003, 0),
+            (0x8000_0004, 0),
+            (0x8000_0008, 0),
+            (0x8000_001F, 0),
+        ];
+        let mut reader = RecordingCpuidReader {
+            reader: FnCpuidReader(|leaf, sub_leaf| CpuidResult {
+                eax: leaf,
+                ebx: sub_leaf,
+                ecx: 0,
+                edx: 0,
+            }),
+            queries: Vec::new(),
+        };
+        let Ok(cpuid) = Cpuid::from_reader(&mut reader);
+        assert_eq!(reader.queries, expected);
+        assert_eq!(cpuid.leaf0x8000_001F_cpuid_feature_bits.bits(), 0x8000_001F);
+        // Including through serialization, the SEV bits do not alias leaf 0x8000_0008 `eax`.
+        let deserialized: Cpuid =
+            serde_json::from_str(&serde_json::to_string(&cpuid).unwrap()).unwrap();
+        assert_eq!(
+            deserialized.leaf0x8000_001F_cpuid_feature_bits.bits(),
+            0x8000_001F
+        );
+        assert_eq!(
+            deserialized
+                .leaf0x8000_0008_virtual_and_physical_address_sizes
+                .eax(),
+            0x8000_0008
+        );
+    }
+    #[test]
+    fn from_reader_all_ones() {
+        init_logger();
+        // A CPU reporting leaves 7 and 0x8000_0001 as its highest, answering higher leaves with
+        // all ones.
+        let mut reader = RecordingCpuidReader {
+            reader: FnCpuidReader(|leaf, _| {
+                let ones = CpuidResult {
+                    eax: u32::MAX,
+                    ebx: u32::MAX,
+                    ecx: u32::MAX,
+                    edx: u32::MAX,
+                };
+                match leaf {
+                    0 => CpuidResult { eax: 7, ..ones },
+                    0x8000_0000 => CpuidResult {
+                        eax: 0x8000_0001,
+                        ..ones
+                    },
+                    _ => ones,
+                }
+            }),
+            queries: Vec::new(),
+        };
+        let Ok(cpuid) = Cpuid::read_live(&mut reader, |reader| Cpuid::from_reader(reader));
+        assert_eq!(
+            reader.queries.iter().filter(|query| query.0 == 0).count(),
+            1
+        );
+        assert!(reader.queries.contains(&(0x8000_0000, 0)));
+
+        // Within the reported highest leaves all ones is kept.
+        assert!(cpuid.leaf_present(7) && cpuid.leaf_present(0x8000_0001));
+        assert_eq!(
+            cpuid.leaf0x07_extended_features.sub_leaf0.ebx.bits,
+            u32::MAX
+        );
+        assert_eq!(
+            cpuid
+                .leaf0x8000_0001_highest_function_parameter_an_manufacturer_id
+                .ecx
+                .bits,
+            u32::MAX
+        );
+        // Above the reported highest leaves all ones is zeroed and the leaf not present.
+        for leaf in [
+            0xD,
+            0x12,
+            0x14,
+            0x17,
+            0x19,
+            0x8000_0002,
+            0x8000_0008,
+            0x8000_001F,
+        ] {
+            assert!(!cpuid.leaf_present(leaf), "{leaf:#x}");
+        }
+        assert_eq!(cpuid.leaf0x0d_cpuid_feature_bits.bits, 0);
+        assert_eq!(cpuid.leaf0x19_cpuid_feature_bits.bits, 0);
+        assert_eq!(cpuid.leaf0x8000_001F_cpuid_feature_bits.bits, 0);
+    }
+    #[test]
+    fn all_ones_round_trip() {
+        init_logger();
+        // Given results are kept as they are, the heuristic only applies to live reads.
+        let ones = Cpuid::from_fn(|_, _| CpuidResult {
+            eax: u32::MAX,
+            ebx: u32::MAX,
+            ecx: u32::MAX,
+            edx: u32::MAX,
+        });
+        assert!(Cpuid::LEAVES.iter().all(|leaf| ones.leaf_present(*leaf)));
+        assert_eq!(ones.leaf0x19_cpuid_feature_bits.bits, u32::MAX);
+        assert_eq!(Cpuid::from_leaf_map(&ones.to_leaf_map()), Ok(ones.clone()));
+
+        let without = ones.without_leaf(7);
+        assert_eq!(without.leaf0x07_extended_features.sub_leaf0.ebx.bits, 0);
+        assert_eq!(without.leaf0x19_cpuid_feature_bits.bits, u32::MAX);
+
+        let mut merged = ones.clone();
+        merged.merge(&Cpuid::default(), MergePolicy::PreferSelf);
+        assert_eq!(merged, ones);
+    }
+    #[test]
+    fn leaf_bytes() {
+        init_logger();
+        let mut cpuid = Cpuid::new();
+        cpuid.misc.insert(
+            (0xB, 1),
+            (0x0403_0201, 0x0807_0605, 0x0C0B_0A09, 0x100F_0E0D),
+        );
+        assert_eq!(
+            cpuid.leaf_bytes(0xB, 1),
+            Some([1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16])
+        );
+        let CpuidResult { eax, ebx, ecx, edx } = cpuid.to_leaf_map()[&(1, 0)];
+        let expected = [eax, ebx, ecx, edx]
+            .iter()
+            .flat_map(|register| register.to_le_bytes())
+            .collect::<Vec<_>>();
+        assert_eq!(cpuid.leaf_bytes(1, 0).map(Vec::from), Some(expected));
+        assert_eq!(cpuid.leaf_bytes(1, 1), None);
+    }
+    #[test]
+    fn strip_topology() {
+        init_logger();
+        let host = |cores_minus_1, logical_processor_ids| {
+            let mut cpuid = Cpuid::new();
+            cpuid
+                .leaf0x01_process_info_and_feature_bits
+                .additional_information
+                .maximum_addressable_logical_processor_ids = logical_processor_ids;
+            cpuid
+                .leaf0x8000_0008_virtual_and_physical_address_sizes
+                .ecx
+                .set_number_of_physical_cores_minus_1(cores_minus_1);
+            cpuid.misc.insert((0xB, 0), (1, 2, 0x100, 3));
+            cpuid
+        };
+        let (small, large) = (host(3, 8), host(63, 128));
+        assert!(!small.covers(&large));
+
+        let mut template = large.clone();
+        template.strip_topology();
+        assert!(small.covers(&template) && large.covers(&template));
+        assert!(!template.misc.contains_key(&(0xB, 0)));
+        let ecx = &template
+            .leaf0x8000_0008_virtual_and_physical_address_sizes
+            .ecx;
+        assert_eq!(
+            (
+                ecx.number_of_physical_cores(),
+                ecx.log2_of_maximum_apic_id()
+            ),
+            (1, 0)
+        );
+        // The capabilities are kept.
+        assert_eq!(
+            template
+                .leaf0x01_process_info_and_feature_bits
+                .feature_information,
+            large
+                .leaf0x01_process_info_and_feature_bits
+                .feature_information
+        );
+        assert_eq!(
+            template
+                .leaf0x8000_0008_virtual_and_physical_address_sizes
+                .eax,
+            large.leaf0x8000_0008_virtual_and_physical_address_sizes.eax
+        );
+    }
+    #[test]
+    fn from_cpuid_result() {
+        init_logger();
+        let result = CpuidResult {
+            eax: 0x0009_06EA,
+            ebx: 0x0210_0800,
+            ecx: 0x7FFA_FBFF,
+            edx: 0xBFEB_FBFF,
+        };
+
+        let leaf0 = HighestFunctionParameterAndManufacturerID::from(CpuidResult {
+            eax: 0x16,
+            ebx: u32::from_le_bytes(*b"Genu"),
+            ecx: u32::from_le_bytes(*b"ntel"),
+            edx: u32::from_le_bytes(*b"ineI"),
+        });
+        assert_eq!(leaf0.highest_calling_parameter, 0x16);
+        assert_eq!(&leaf0.manufacturer_id.0, b"GenuineIntel");
+
+        let leaf1 = ProcessorInfoAndFeatureBits::from(result);
+        assert_eq!(leaf1.processor_version_information.0, 0x0009_06EA);
+        let additional = &leaf1.additional_information;
+        assert_eq!(
+            (
+                additional.brand_index,
+                additional.clflush_line_size,
+                additional.maximum_addressable_logical_processor_ids,
+                additional.local_apic_id
+            ),
+            (0, 8, 0x10, 2)
+        );
+        assert_eq!(leaf1.feature_information, FeatureInformation::from(result));
+        let features = FeatureInformation::from(result);
+        assert_eq!(
+            (features.ecx.bits(), features.edx.bits()),
+            (0x7FFA_FBFF, 0xBFEB_FBFF)
+        );
+
+        let leaf6 = ThermalAndPowerManagement::from(result);
+        assert_eq!(leaf6.features.eax.bits(), 0x0009_06EA);
+        assert_eq!(leaf6.number_of_interrupt_thresholds.0, 0x0210_0800);
+        assert_eq!(leaf6.features.ecx.bits(), 0x7FFA_FBFF);
+
+        let leaf7 = ExtendedFeaturesSubLeaf0::from(result);
+        assert_eq!(
+            (leaf7.ebx(), leaf7.ecx(), leaf7.edx()),
+            (0x0210_0800, 0x7FFA_FBFF, 0xBFEB_FBFF)
+        );
+
+        let leaf0x8000_0001 = ExtendedProcessorInfoAndFeatureBits::from(result);
+        assert_eq!(
+            (leaf0x8000_0001.ecx(), leaf0x8000_0001.edx()),
+            (0x7FFA_FBFF, 0xBFEB_FBFF)
+        );
+
+        let leaf0x8000_0008 = VirtualAndPhysicalAddressSizes::from(CpuidResult {
+            eax: 0x3028,
+            ebx: 0x0100_0000,
+            ecx: 0x0000_7007,
+            edx: 0,
+        });
+        assert_eq!(
+            (
+                leaf0x8000_0008.eax.number_of_physical_address_bits(),
+                leaf0x8000_0008.eax.number_of_linear_address_bits()
+            ),
+            (40, 48)
+        );
+        assert_eq!(leaf0x8000_0008.ebx(), 0x0100_0000);
+        assert_eq!(
+            (
+                leaf0x8000_0008.ecx.number_of_physical_cores(),
+                leaf0x8000_0008.ecx.log2_of_maximum_apic_id()
+            ),
+            (8, 7)
+        );
+    }
+    #[test]
+    fn number_of_physical_cores() {
+        init_logger();
+        let mut ecx = Leaf0x8000_0008_SubLeaf0_Ecx(0);
+        assert_eq!(ecx.number_of_physical_cores(), 1);
+        ecx.set_number_of_physical_cores_minus_1(255);
+        assert_eq!(ecx.number_of_physical_cores_minus_1(), 255);
+        assert_eq!(ecx.number_of_physical_cores(), 256);
+    }
+    #[test]
+    fn live_conformance() {
+        init_logger();
+        let cpuid = Cpuid::new();
+        for (leaf, sub_leaf, registers) in cpuid.iter_leaves() {
+            let live = __cpuid_count(leaf, sub_leaf);
+            // The bits of `eax`, `ebx`, `ecx` and `edx` the fields model, the registers not
+            // modeled (which `iter_leaves` zeroes) are masked out.
+            let masks = match (leaf, sub_leaf) {
+                // The local APIC id (`ebx` bits 24 to 31) depends on which CPU ran the query.
+                (0x1, 0) => [!0, 0x00FF_FFFF, !0, !0],
+                (0x6 | 0x8000_0008, 0) => [!0, !0, !0, 0],
+                // `eax` is the highest sub-leaf.
+                (0x7, 0) => [0, !0, !0, !0],
+                (0x7 | 0xD, 1) | (0x12 | 0x8000_001F, 0) => [!0, 0, 0, 0],
+                (0x14 | 0x19, 0) => [0, !0, 0, 0],
+                // The brand string is only read when the sub-leaves are reported.
+                (0x17, 1..=3) if cpuid.leaf0x17_soc_vendor_attributes.max_sub_leaf < 3 => [0; 4],
+                (0x8000_0001, 0) => [0, 0, !0, !0],
+                _ => [!0; 4],
+            };
+            let masked = |CpuidResult { eax, ebx, ecx, edx }| {
+                [
+                    eax & masks[0],
+                    ebx & masks[1],
+                    ecx & masks[2],
+                    edx & masks[3],
+                ]
+            };
+            assert_eq!(
+                masked(registers),
+                masked(live),
+                "leaf {leaf:#x} sub-leaf {sub_leaf:#x}"
+            );
+        }
+    }
+    #[test]
+    fn default_zeroed() {
+        init_logger();
+        let cpuid = Cpuid::default();
+        assert!(cpuid.misc.is_empty());
+        assert!(cpuid.iter_leaves().all(|(_, _, registers)| registers
+            == CpuidResult {
+                eax: 0,
+                ebx: 0,
+                ecx: 0,
+                edx: 0,
+            }));
+        // A real CPU reports at least leaf 1 and a manufacturer id.
+        assert_ne!(cpuid, Cpuid::new());
+    }
+    #[test]
+    fn leaf_try_from_entry() {
+        init_logger();
+        let entry = RawCpuidEntry::new(1, 0, 0, 0x0009_06EA, 0x0010_0800, 0x7FFA_FBFF, 0xBFEB_FBFF);
+        let leaf1 = ProcessorInfoAndFeatureBits::try_from(&entry).unwrap();
+        assert_eq!(leaf1, ProcessorInfoAndFeatureBits::from(entry.clone()));
+        assert_eq!(
+            (leaf1.eax(), leaf1.ebx(), leaf1.ecx(), leaf1.edx()),
+            (0x0009_06EA, 0x0010_0800, 0x7FFA_FBFF, 0xBFEB_FBFF)
+        );
+        assert!(leaf1
+            .feature_information
+            .ecx
+            .contains(Leaf0x1_SubLeaf0_Ecx::sse3));
+
+        assert_eq!(
+            ExtendedProcessorInfoAndFeatureBits::try_from(&entry),
+            Err(LeafMismatch {
+                expected: (0x8000_0001, 0),
+                found: (1, 0)
+            })
+        );
+        let sub_leaf1 = RawCpuidEntry::new(7, 1, 0, 1 << 5, 0, 0, 0);
+        assert_eq!(
+            Leaf0x7_SubLeaf1_Eax::try_from(&sub_leaf1),
+            Ok(Leaf0x7_SubLeaf1_Eax::avx512_bf16)
+        );
+        assert!(ExtendedFeaturesSubLeaf0::try_from(&sub_leaf1).is_err());
+    }
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn covers_spans() {
+        use std::sync::{Arc, Mutex};
+
+        use tracing::field::{Field, Visit};
+        use tracing::span::{Attributes, Id, Record};
+        use tracing::{Event, Metadata, Subscriber};
+
+        /// The name and `leaf` field of each span.
+        type Spans = Vec<(&'static str, Option<u64>)>;
+        /// A [`Subscriber`] recording [`Spans`].
+        struct SpanRecorder(Arc<Mutex<Spans>>);
+        impl Subscriber for SpanRecorder {
+            fn enabled(&self, _: &Metadata<'_>) -> bool {
+                true
+            }
+
+            fn new_span(&self, span: &Attributes<'_>) -> Id {
+                struct LeafVisitor(Option<u64>);
+                impl Visit for LeafVisitor {
+                    fn record_u64(&mut self, field: &Field, value: u64) {
+                        if field.name() == "leaf" {
+                            self.0 = Some(value);
+                        }
+                    }
+
+                    fn record_debug(&mut self, _: &Field, _: &dyn fmt::Debug) {}
+                }
+                let mut visitor = LeafVisitor(None);
+                span.record(&mut visitor);
+                let mut spans = self.0.lock().unwrap();
+                spans.push((span.metadata().name(), visitor.0));
+                Id::from_u64(u64::try_from(spans.len()).unwrap())
+            }
+
+            fn record(&self, _: &Id, _: &Record<'_>) {}
+
+            fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+            fn event(&self, _: &Event<'_>) {}
+
+            fn enter(&self, _: &Id) {}
+
+            fn exit(&self, _: &Id) {}
+        }
+
+        let cpuid = Cpuid::new();
+        let spans = Arc::new(Mutex::new(Vec::new()));
+        tracing::subscriber::with_default(SpanRecorder(Arc::clone(&spans)), || {
+            assert!(cpuid.covers(&cpuid));
+        });
+        let spans = spans.lock().unwrap();
+        assert_eq!(spans[0], ("covers", None));
+        let leaves = spans
+            .iter()
+            .filter(|(name, _)| *name == "covers_leaf")
+            .map(|(_, leaf)| leaf.unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(leaves, Cpuid::LEAVES.map(u64::from));
+    }
+    #[test]
+    fn eq_ignoring() {
+        init_logger();
+        let a = Cpuid::new();
+        let differ = |ignore: IgnoreFields, change: fn(&mut Cpuid)| {
+            let mut b = a.clone();
+            change(&mut b);
+            assert!(!a.eq_ignoring(&b, IgnoreFields::empty()));
+            assert!(!a.eq_ignoring(&b, IgnoreFields::all() - ignore));
+            assert!(a.eq_ignoring(&b, ignore));
+            assert!(a.eq_ignoring(&b, IgnoreFields::all()));
+        };
+        differ(IgnoreFields::APIC_ID, |b| {
+            b.leaf0x01_process_info_and_feature_bits
+                .additional_information
+                .local_apic_id ^= 1;
+        });
+        differ(IgnoreFields::BRAND_STRING, |b| {
+            b.leaf0x8000_0002_brand_string.brand_string.0[16] ^= 1;
+            b.leaf0x01_process_info_and_feature_bits
+                .additional_information
+                .brand_index ^= 1;
+        });
+        differ(IgnoreFields::TOPOLOGY, |b| {
+            b.misc.insert((0xB, 1), (4, 8, 0x201, 3));
+            b.leaf0x01_process_info_and_feature_bits
+                .additional_information
+                .maximum_addressable_logical_processor_ids ^= 1;
+        });
+        differ(IgnoreFields::FREQUENCY, |b| {
+            b.misc.insert((0x16, 0), (2000, 4000, 100, 0));
+        });
+
+        // Features are never ignored.
+        let mut b = a.clone();
+        b.leaf0x07_extended_features.sub_leaf0.ebx ^= Leaf0x7_SubLeaf0_Ebx::avx2;
+        assert!(!a.eq_ignoring(&b, IgnoreFields::all()));
+        b.leaf0x07_extended_features.sub_leaf0.ebx ^= Leaf0x7_SubLeaf0_Ebx::avx2;
+        b.leaf0x01_process_info_and_feature_bits
+            .additional_information
+            .local_apic_id ^= 1;
+        assert!(a.feature_eq(&b));
+        assert_ne!(a, b);
+    }
+    #[test]
+    fn normalize() {
+        init_logger();
+        let mut a = Cpuid::new();
+        a.leaf0x01_process_info_and_feature_bits
+            .additional_information
+            .local_apic_id = 3;
+        a.misc.insert((0xB, 0), (1, 2, 0x100, 3));
+        a.misc.insert((0xB, 1), (4, 8, 0x201, 3));
+        let mut b = a.clone();
+        b.leaf0x01_process_info_and_feature_bits
+            .additional_information
+            .local_apic_id = 7;
+        b.misc.insert((0xB, 0), (1, 2, 0x100, 7));
+        b.misc.insert((0xB, 1), (4, 8, 0x201, 7));
+        assert_ne!(a, b);
+
+        a.normalize();
+        b.normalize();
+        assert_eq!(a, b);
+        // Capabilities are left intact.
+        assert_eq!(a.misc[&(0xB, 1)], (4, 8, 0x201, 0));
+    }
+    #[test]
+    fn from_fn() {
+        init_logger();
+        let mut queries = Vec::new();
+        let cpuid = Cpuid::from_fn(|leaf, sub_leaf| {
+            queries.push((leaf, sub_leaf));
+            match (leaf, sub_leaf) {
+                // "GenuineIntel"
+                (0, 0) => CpuidResult {
+                    eax: 0x1F,
+                    ebx: 0x756e_6547,
+                    ecx: 0x6c65_746e,
+                    edx: 0x4965_6e69,
+                },
+                (1, 0) => CpuidResult {
+                    eax: 0x0005_0654,
+                    ebx: 0x0300_0800,
+                    ecx: 1,
+                    edx: 1,
+                },
+                (0x8000_0008, 0) => CpuidResult {
+                    eax: 0x3030,
+                    ebx: 0,
+                    ecx: 0,
+                    edx: 0,
+                },
+                _ => CpuidResult {
+                    eax: 0,
+                    ebx: 0,
+                    ecx: 0,
+                    edx: 0,
+                },
+            }
+        });
+        assert!(queries.contains(&(7, 1)));
+
+        let leaf0 = &cpuid.leaf0x00_highest_function_parameter_an_manufacturer_id;
+        assert_eq!(&leaf0.manufacturer_id.0, b"GenuineIntel");
+        assert_eq!(leaf0.highest_calling_parameter, 0x1F);
+        let leaf1 = &cpuid.leaf0x01_process_info_and_feature_bits;
+        assert_eq!(leaf1.processor_version_information.stepping_id(), 4);
+        assert_eq!(leaf1.processor_version_information.model(), 5);
+        assert_eq!(leaf1.processor_version_information.family_id(), 6);
+        assert_eq!(leaf1.processor_version_information.extended_model_id(), 5);
+        assert_eq!(leaf1.additional_information.clflush_line_size, 8);
+        assert_eq!(leaf1.additional_information.local_apic_id, 3);
+        assert_eq!(leaf1.feature_information.ecx, Leaf0x1_SubLeaf0_Ecx::sse3);
+        assert_eq!(leaf1.feature_information.edx, Leaf0x1_SubLeaf0_Edx::fpu);
+        let leaf0x8000_0008 = &cpuid.leaf0x8000_0008_virtual_and_physical_address_sizes.eax;
+        assert_eq!(leaf0x8000_0008.number_of_physical_address_bits(), 48);
+        assert_eq!(leaf0x8000_0008.number_of_linear_address_bits(), 48);
+        assert!(cpuid.leaf0x07_extended_features.sub_leaf0.ebx.is_empty());
+        assert!(cpuid.misc.is_empty());
+    }
+    #[test]
+    fn all_excludes_reserved_bits() {
+        init_logger();
+        let mask = |bits: &[RangeInclusive<u32>]| {
+            bits.iter()
+                .cloned()
+                .flatten()
+                .fold(0, |acc, bit| acc | (1 << bit))
+        };
+        // The bits documented as reserved (or used by non-flag fields) in each register.
+        let registers = [
+            (
+                "Leaf0x1_SubLeaf0_Ecx",
+                Leaf0x1_SubLeaf0_Ecx::all().bits(),
+                mask(&[16..=16]),
+            ),
+            (
+                "Leaf0x1_SubLeaf0_Edx",
+                Leaf0x1_SubLeaf0_Edx::all().bits(),
+                mask(&[10..=10, 20..=20]),
+            ),
+            (
+                "Leaf0x6_SubLeaf0_Eax",
+                Leaf0x6_SubLeaf0_Eax::all().bits(),
+                mask(&[3..=3, 7..=31]),
+            ),
+            (
+                "Leaf0x6_SubLeaf0_Ecx",
+                Leaf0x6_SubLeaf0_Ecx::all().bits(),
+                mask(&[2..=2, 4..=31]),
+            ),
+            (
+                "Leaf0x7_SubLeaf0_Ebx",
+                Leaf0x7_SubLeaf0_Ebx::all().bits(),
+                0,
+            ),
+            (
+                "Leaf0x7_SubLeaf0_Ecx",
+                Leaf0x7_SubLeaf0_Ecx::all().bits(),
+                // Bits 17 to 21 are MAWAU.
+                mask(&[15..=15, 17..=21, 24..=24, 26..=26]),
+            ),
+            (
+                "Leaf0x7_SubLeaf0_Edx",
+                Leaf0x7_SubLeaf0_Edx::all().bits(),
+                mask(&[0..=1, 6..=7, 12..=12, 17..=17, 21..=21]),
+            ),
+            (
+                "Leaf0x7_SubLeaf1_Eax",
+                Leaf0x7_SubLeaf1_Eax::all().bits(),
+                mask(&[0..=3, 6..=9, 13..=16, 19..=21, 23..=31]),
+            ),
+            (
+                "Leaf0xD_SubLeaf1_Eax",
+                Leaf0xD_SubLeaf1_Eax::all().bits(),
+                mask(&[4..=31]),
+            ),
+            (
+                "Leaf0x12_SubLeaf0_Eax",
+                Leaf0x12_SubLeaf0_Eax::all().bits(),
+                mask(&[2..=4, 7..=31]),
+            ),
+            (
+                "Leaf0x14_SubLeaf0_Ebx",
+                Leaf0x14_SubLeaf0_Ebx::all().bits(),
+                mask(&[0..=3, 5..=31]),
+            ),
+            (
+                "Leaf0x19_SubLeaf0_Ebx",
+                Leaf0x19_SubLeaf0_Ebx::all().bits(),
+                mask(&[1..=1, 3..=3, 5..=31]),
+            ),
+            (
+                "Leaf0x8000_0001_SubLeaf0_Edx",
+                Leaf0x8000_0001_SubLeaf0_Edx::all().bits(),
+                mask(&[10..=10, 18..=18, 21..=21, 28..=28]),
+            ),
+            (
+                "Leaf0x8000_0001_SubLeaf0_Ecx",
+                Leaf0x8000_0001_SubLeaf0_Ecx::all().bits(),
+                mask(&[14..=14, 18..=18, 20..=20, 25..=25, 31..=31]),
+            ),
+            (
+                "Leaf0x8000_0008_SubLeaf0_Ebx",
+                Leaf0x8000_0008_SubLeaf0_Ebx::all().bits(),
+                mask(&[5..=7, 10..=11, 16..=16, 18..=19, 22..=22, 27..=31]),
+            ),
+            (
+                "Leaf0x8000_001F_SubLeaf0_Eax",
+                Leaf0x8000_001F_SubLeaf0_Eax::all().bits(),
+                mask(&[6..=9, 17..=31]),
+            ),
+        ];
+        for (name, all, reserved) in registers {
+            assert_eq!(
+                all & reserved,
+                0,
+                "{name} defines reserved bits {:#034b}",
+                all & reserved
+            );
+        }
+    }
+    #[test]
+    fn try_from_bytes() {
+        init_logger();
+        let mut cpuid = Cpuid::new();
+        cpuid.misc.insert((0x4000_0000, 0), (1, 2, 3, 4));
+        let bytes = cpuid.to_bytes();
+        assert!(bytes.len().is_multiple_of(Cpuid::ENTRY_BYTES));
+        assert_eq!(Cpuid::try_from(bytes.as_slice()), Ok(cpuid));
+    }
+    #[test]
+    fn try_from_bytes_too_short() {
+        init_logger();
+        let bytes = Cpuid::new().to_bytes();
+        let len = bytes.len() - 1;
+        assert_eq!(
+            Cpuid::try_from(&bytes[..len]),
+            Err(DecodeError::InvalidLength { len })
+        );
+        // Truncating a whole entry loses the last leaf.
+        let len = bytes.len() - Cpuid::ENTRY_BYTES;
+        assert_eq!(
+            Cpuid::try_from(&bytes[..len]),
+            Err(DecodeError::MissingLeaf {
+                leaf: 0x8000_001F,
+                sub_leaf: 0
+            })
+        );
+    }
+    #[test]
+    fn try_from_bytes_too_long() {
+        init_logger();
+        let mut bytes = Cpuid::new().to_bytes();
+        bytes.push(0);
+        assert_eq!(
+            Cpuid::try_from(bytes.as_slice()),
+            Err(DecodeError::InvalidLength { len: bytes.len() })
+        );
+        bytes.pop();
+
+        // A duplicated entry is rejected rather than overwriting the first.
+        let mut duplicated = bytes.clone();
+        duplicated.extend_from_slice(&bytes[..Cpuid::ENTRY_BYTES]);
+        assert_eq!(
+            Cpuid::try_from(duplicated.as_slice()),
+            Err(DecodeError::DuplicateLeaf {
+                leaf: 0,
+                sub_leaf: 0
+            })
+        );
+
+        // An extra entry of a leaf not described is stored in `misc`.
+        let mut extra = bytes;
+        for word in [0x4000_0000, 0, 1, 2, 3, 4] {
+            extra.extend_from_slice(&u32::to_le_bytes(word));
+        }
+        let cpuid = Cpuid::try_from(extra.as_slice()).unwrap();
+        assert_eq!(cpuid.misc.get(&(0x4000_0000, 0)), Some(&(1, 2, 3, 4)));
+    }
+    #[test]
+    fn leaf_numbers() {
+        /// Returns [`Cpuid`] with only the registers of `key` set.
+        fn only(key: (u32, u32)) -> Cpuid {
+            Cpuid::from_fn(|leaf, sub_leaf| {
+                let registers = if (leaf, sub_leaf) == key { u32::MAX } else { 0 };
+                CpuidResult {
+                    eax: registers,
+                    ebx: registers,
+                    ecx: registers,
+                    edx: registers,
+                }
+            })
+        }
+        /// If the leaf number of the `Leaf<N>` output is `N` and its query fills `Leaf<N>`.
+        fn fills<const N: usize>() -> bool
+        where
+            Cpuid: Leaf<N>,
+            <Cpuid as Leaf<N>>::Output: LeafNumber + PartialEq,
+        {
+            let key = leaf_key::<<Cpuid as Leaf<N>>::Output>();
+            key.0 as usize == N && only(key).leaf::<N>() != Cpuid::default().leaf::<N>()
+        }
+        init_logger();
+        // The leaf number of each type is the query filling the field of that type.
+        let zeroed = Cpuid::default();
+        assert_ne!(
+            only(leaf_key::<ExtendedFeaturesSubLeaf0>())
+                .leaf0x07_extended_features
+                .sub_leaf0,
+            zeroed.leaf0x07_extended_features.sub_leaf0
+        );
+        assert_ne!(
+            only(leaf_key::<Leaf0x7_SubLeaf1_Eax>())
+                .leaf0x07_extended_features
+                .sub_leaf1,
+            zeroed.leaf0x07_extended_features.sub_leaf1
+        );
+        assert_ne!(
+            only(leaf_key::<BrandString>()).leaf0x8000_0002_brand_string,
+            zeroed.leaf0x8000_0002_brand_string
+        );
+        // Sub-leaves other than those read fill nothing.
+        assert_eq!(only((7, 2)), zeroed);
+        assert_eq!(only((0xD, 0)), zeroed);
+        // The leaf numbers used by the `Leaf` trait match.
+        assert!(fills::<0>());
+        assert!(fills::<1>());
+        assert!(fills::<2>());
+        assert!(fills::<6>());
+        assert!(fills::<7>());
+        assert!(fills::<13>());
+        assert!(fills::<18>());
+        assert!(fills::<20>());
+        assert!(fills::<23>());
+        assert!(fills::<25>());
+        assert!(fills::<0x8000_0001>());
+        assert!(fills::<0x8000_0008>());
+        assert!(fills::<0x8000_001F>());
+    }
+    #[test]
+    fn leaf_map_round_trip() {
+        init_logger();
+        let mut cpuid = Cpuid::new();
+        cpuid.misc.insert((0x4000_0000, 0), (1, 2, 3, 4));
+        let map = cpuid.to_leaf_map();
+        assert_eq!(
+            map.get(&(0x4000_0000, 0)),
+            Some(&CpuidResult {
+                eax: 1,
+                ebx: 2,
+                ecx: 3,
+                edx: 4
+            })
+        );
+        assert_eq!(Cpuid::from_leaf_map(&map), Ok(cpuid));
+
+        let mut missing = map;
+        missing.remove(&(7, 1));
+        assert_eq!(
+            Cpuid::from_leaf_map(&missing),
+            Err(DecodeError::MissingLeaf {
+                leaf: 7,
+                sub_leaf: 1
+            })
+        );
+    }
+    #[test]
+    fn iter_leaves() {
+        init_logger();
+        let mut cpuid = Cpuid::new();
+        cpuid.misc.insert((0x4000_0000, 0), (1, 2, 3, 4));
+        let leaves = cpuid.iter_leaves().collect::<Vec<_>>();
+        let (leaf, sub_leaf, result) = leaves[0];
+        assert_eq!((leaf, sub_leaf), (0, 0));
+        assert_eq!(
+            result.eax,
+            cpuid
+                .leaf0x00_highest_function_parameter_an_manufacturer_id
+                .highest_calling_parameter
+        );
+        // Every modeled leaf in order, without `misc`.
+        let mut modeled = leaves.iter().map(|(leaf, ..)| *leaf).collect::<Vec<_>>();
+        assert!(leaves
+            .windows(2)
+            .all(|pair| (pair[0].0, pair[0].1) < (pair[1].0, pair[1].1)));
+        modeled.dedup();
+        assert_eq!(modeled, Cpuid::LEAVES);
+        let map = cpuid.to_leaf_map();
+        for (leaf, sub_leaf, result) in leaves {
+            assert_eq!(map[&(leaf, sub_leaf)], result);
+        }
+    }
+    #[test]
+    fn cache_and_tlb() {
+        init_logger();
+        // A Core 2 Duo.
+        let leaf2 = Leaf2CacheAndTlb::from(CpuidResult {
+            eax: 0x05B0_B101,
+            ebx: 0x0056_57F0,
+            ecx: 0,
+            edx: 0x2CB4_3049,
+        });
+        assert_eq!(leaf2.times_to_query(), 1);
+        assert_eq!(
+            leaf2.descriptor_bytes(),
+            [0xB1, 0xB0, 0x05, 0xF0, 0x57, 0x56, 0x49, 0x30, 0xB4, 0x2C]
+        );
+        let descriptors = leaf2.descriptors();
+        assert_eq!(descriptors[3], CacheDescriptor::Prefetch { line_size: 64 });
+        assert_eq!(
+            descriptors[6],
+            CacheDescriptor::L2(CacheParameters {
+                size_kib: 4096,
+                ways: 16,
+                line_size: 64
+            })
+        );
+        assert_eq!(
+            descriptors[9],
+            CacheDescriptor::L1Data(CacheParameters {
+                size_kib: 32,
+                ways: 8,
+                line_size: 64
+            })
+        );
+
+        // Registers with bit 31 set hold no valid descriptors.
+        let invalid = Leaf2CacheAndTlb {
+            ebx: 0x8000_57F0,
+            ..leaf2.clone()
+        };
+        assert_eq!(invalid.descriptors().len(), 7);
+
+        // A superset of the descriptors covers.
+        let with_leaf2 = |leaf0x02_cache_and_tlb| Cpuid {
+            leaf0x02_cache_and_tlb,
+            ..Cpuid::default()
+        };
+        let host = with_leaf2(leaf2.clone());
+        let guest = with_leaf2(Leaf2CacheAndTlb {
+            eax: 0,
+            ebx: 0,
+            ecx: 0,
+            edx: 0x2C30,
+        });
+        assert!(host.covers(&guest) && !guest.covers(&host));
+        assert!(!with_leaf2(invalid).covers(&host));
+        assert!(guest.diff(&host).is_empty());
+        assert_eq!(host.diff(&guest).mismatched, ["cache_and_tlb_descriptors"]);
+
+        assert_eq!(
+            Cpuid::new().leaf::<2>(),
+            &Leaf2CacheAndTlb::from(Cpuid::query_single::<2, 0>())
+        );
+    }
+    #[test]
+    fn soc_vendor_attributes() {
+        init_logger();
+        let mut brand = [0; 48];
+        brand[..24].copy_from_slice(b"Example SOC Vendor Brand");
+        let word =
+            |i: usize| u32::from_le_bytes([brand[i], brand[i + 1], brand[i + 2], brand[i + 3]]);
+        let mut reader = FnCpuidReader(|leaf, sub_leaf| match (leaf, sub_leaf) {
+            // Vendor id 0x1234 by the vendor scheme, project id 5 and stepping id 2.
+            (0x17, 0) => CpuidResult {
+                eax: 3,
+                ebx: 0x0001_1234,
+                ecx: 5,
+                edx: 2,
+            },
+            (0x17, 1..=3) => {
+                let i = 16 * (sub_leaf as usize - 1);
+                CpuidResult {
+                    eax: word(i),
+                    ebx: word(i + 4),
+                    ecx: word(i + 8),
+                    edx: word(i + 12),
+                }
+            }
+            _ => CpuidResult {
+                eax: 0,
+                ebx: 0,
+                ecx: 0,
+                edx: 0,
+            },
+        });
+        let Ok(cpuid) = Cpuid::from_reader(&mut reader);
+        let attributes = cpuid.leaf::<23>();
+        assert_eq!(attributes.max_sub_leaf, 3);
+        assert_eq!(attributes.soc_vendor_id(), 0x1234);
+        assert!(attributes.is_vendor_scheme());
+        assert_eq!(attributes.project_id, 5);
+        assert_eq!(attributes.stepping_id, 2);
+        assert_eq!(attributes.brand_string.0, brand);
+        assert_eq!(attributes.reserved(), 0);
+        assert_eq!(
+            Cpuid::from_leaf_map(&cpuid.to_leaf_map()),
+            Ok(cpuid.clone())
+        );
+        let deserialized =
+            serde_json::from_str::<Cpuid>(&serde_json::to_string(&cpuid).unwrap()).unwrap();
+        assert_eq!(deserialized, cpuid);
+
+        // The brand string sub-leaves are unsupported.
+        let attributes = SocVendorAttributes::from([
+            (2, 0x1234, 5, 2),
+            (1, 1, 1, 1),
+            (1, 1, 1, 1),
+            (1, 1, 1, 1),
+        ]);
+        assert!(attributes.brand_string.is_blank());
+        assert!(!attributes.is_vendor_scheme());
+
+        // Informational, so ignored by `covers`.
+        let mut other = cpuid.clone();
+        other.leaf0x17_soc_vendor_attributes = attributes;
+        assert!(other.covers(&cpuid) && cpuid.covers(&other));
+    }
+    #[test]
+    fn leaf_map_ordering() {
+        init_logger();
+        let mut cpuid = Cpuid::new();
+        cpuid.misc.insert((0x4000_0000, 0), (0, 0, 0, 0));
+        cpuid.misc.insert((4, 1), (0, 0, 0, 0));
+        let keys = cpuid.to_leaf_map().into_keys().collect::<Vec<_>>();
+        assert_eq!(
+            keys,
+            [
+                (0, 0),
+                (1, 0),
+                (2, 0),
+                (4, 1),
+                (6, 0),
+                (7, 0),
+                (7, 1),
+                (13, 1),
+                (18, 0),
+                (20, 0),
+                (23, 0),
+                (23, 1),
+                (23, 2),
+                (23, 3),
+                (25, 0),
+                (0x4000_0000, 0),
+                (0x8000_0001, 0),
+                (0x8000_0002, 0),
+                (0x8000_0003, 0),
+                (0x8000_0004, 0),
+                (0x8000_0008, 0),
+                (0x8000_001F, 0)
+            ]
+        );
+    }
 }
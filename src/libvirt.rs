@@ -0,0 +1,333 @@
+//! Feature names as used by libvirt in a domain XML `<cpu>` e.g. `<feature name='avx2'/>`.
+//!
+//! libvirt mostly uses the Linux `/proc/cpuinfo` names, but writes some with dashes or dots (e.g.
+//! `sse4.1` and `md-clear`).
+
+use crate::features::{Register, FLAGS_REGISTERS};
+use crate::{
+    Cpuid, Leaf0x1_SubLeaf0_Ecx, Leaf0x1_SubLeaf0_Edx, Leaf0x7_SubLeaf0_Ebx, Leaf0x7_SubLeaf0_Ecx,
+    Leaf0x7_SubLeaf0_Edx, Leaf0x7_SubLeaf1_Eax, Leaf0x8000_0001_SubLeaf0_Ecx,
+    Leaf0x8000_0001_SubLeaf0_Edx, Leaf0x8000_0008_SubLeaf0_Ebx, Leaf0xD_SubLeaf1_Eax,
+};
+
+const LEAF0X1_EDX: &[(&str, u32)] = &[
+    ("fpu", Leaf0x1_SubLeaf0_Edx::fpu.bits()),
+    ("vme", Leaf0x1_SubLeaf0_Edx::vme.bits()),
+    ("de", Leaf0x1_SubLeaf0_Edx::de.bits()),
+    ("pse", Leaf0x1_SubLeaf0_Edx::pse.bits()),
+    ("tsc", Leaf0x1_SubLeaf0_Edx::tsc.bits()),
+    ("msr", Leaf0x1_SubLeaf0_Edx::msr.bits()),
+    ("pae", Leaf0x1_SubLeaf0_Edx::pae.bits()),
+    ("mce", Leaf0x1_SubLeaf0_Edx::mce.bits()),
+    ("cx8", Leaf0x1_SubLeaf0_Edx::cx8.bits()),
+    ("apic", Leaf0x1_SubLeaf0_Edx::apic.bits()),
+    ("sep", Leaf0x1_SubLeaf0_Edx::sep.bits()),
+    ("mtrr", Leaf0x1_SubLeaf0_Edx::mtrr.bits()),
+    ("pge", Leaf0x1_SubLeaf0_Edx::pge.bits()),
+    ("mca", Leaf0x1_SubLeaf0_Edx::mca.bits()),
+    ("cmov", Leaf0x1_SubLeaf0_Edx::cmov.bits()),
+    ("pat", Leaf0x1_SubLeaf0_Edx::pat.bits()),
+    ("pse36", Leaf0x1_SubLeaf0_Edx::pse_36.bits()),
+    ("pn", Leaf0x1_SubLeaf0_Edx::psn.bits()),
+    ("clflush", Leaf0x1_SubLeaf0_Edx::clfsh.bits()),
+    ("ds", Leaf0x1_SubLeaf0_Edx::ds.bits()),
+    ("acpi", Leaf0x1_SubLeaf0_Edx::acpi.bits()),
+    ("mmx", Leaf0x1_SubLeaf0_Edx::mmx.bits()),
+    ("fxsr", Leaf0x1_SubLeaf0_Edx::fxsr.bits()),
+    ("sse", Leaf0x1_SubLeaf0_Edx::sse.bits()),
+    ("sse2", Leaf0x1_SubLeaf0_Edx::sse2.bits()),
+    ("ss", Leaf0x1_SubLeaf0_Edx::ss.bits()),
+    ("ht", Leaf0x1_SubLeaf0_Edx::htt.bits()),
+    ("tm", Leaf0x1_SubLeaf0_Edx::tm.bits()),
+    ("ia64", Leaf0x1_SubLeaf0_Edx::ia64.bits()),
+    ("pbe", Leaf0x1_SubLeaf0_Edx::pbe.bits()),
+];
+const LEAF0X1_ECX: &[(&str, u32)] = &[
+    ("pni", Leaf0x1_SubLeaf0_Ecx::sse3.bits()),
+    ("pclmuldq", Leaf0x1_SubLeaf0_Ecx::pclmulqdq.bits()),
+    ("dtes64", Leaf0x1_SubLeaf0_Ecx::dtes64.bits()),
+    ("monitor", Leaf0x1_SubLeaf0_Ecx::monitor.bits()),
+    ("ds_cpl", Leaf0x1_SubLeaf0_Ecx::ds_cpl.bits()),
+    ("vmx", Leaf0x1_SubLeaf0_Ecx::vmx.bits()),
+    ("smx", Leaf0x1_SubLeaf0_Ecx::smx.bits()),
+    ("est", Leaf0x1_SubLeaf0_Ecx::est.bits()),
+    ("tm2", Leaf0x1_SubLeaf0_Ecx::tm2.bits()),
+    ("ssse3", Leaf0x1_SubLeaf0_Ecx::ssse3.bits()),
+    ("cid", Leaf0x1_SubLeaf0_Ecx::cnxt_id.bits()),
+    ("fma", Leaf0x1_SubLeaf0_Ecx::fma.bits()),
+    ("cx16", Leaf0x1_SubLeaf0_Ecx::cx16.bits()),
+    ("xtpr", Leaf0x1_SubLeaf0_Ecx::xtpr.bits()),
+    ("pdcm", Leaf0x1_SubLeaf0_Ecx::pdcm.bits()),
+    ("pcid", Leaf0x1_SubLeaf0_Ecx::pcid.bits()),
+    ("dca", Leaf0x1_SubLeaf0_Ecx::dca.bits()),
+    ("sse4.1", Leaf0x1_SubLeaf0_Ecx::sse4_1.bits()),
+    ("sse4.2", Leaf0x1_SubLeaf0_Ecx::sse4_2.bits()),
+    ("x2apic", Leaf0x1_SubLeaf0_Ecx::x2apic.bits()),
+    ("movbe", Leaf0x1_SubLeaf0_Ecx::movbe.bits()),
+    ("popcnt", Leaf0x1_SubLeaf0_Ecx::popcnt.bits()),
+    ("tsc-deadline", Leaf0x1_SubLeaf0_Ecx::tsc_deadline.bits()),
+    ("aes", Leaf0x1_SubLeaf0_Ecx::aes.bits()),
+    ("xsave", Leaf0x1_SubLeaf0_Ecx::xsave.bits()),
+    ("osxsave", Leaf0x1_SubLeaf0_Ecx::osxsave.bits()),
+    ("avx", Leaf0x1_SubLeaf0_Ecx::avx.bits()),
+    ("f16c", Leaf0x1_SubLeaf0_Ecx::f16c.bits()),
+    ("rdrand", Leaf0x1_SubLeaf0_Ecx::rdrnd.bits()),
+    ("hypervisor", Leaf0x1_SubLeaf0_Ecx::hypervisor.bits()),
+];
+const LEAF0X7_EBX: &[(&str, u32)] = &[
+    ("fsgsbase", Leaf0x7_SubLeaf0_Ebx::fsgsbase.bits()),
+    ("tsc_adjust", Leaf0x7_SubLeaf0_Ebx::IA32_TSC_ADJUST.bits()),
+    ("sgx", Leaf0x7_SubLeaf0_Ebx::sgx.bits()),
+    ("bmi1", Leaf0x7_SubLeaf0_Ebx::bmi1.bits()),
+    ("hle", Leaf0x7_SubLeaf0_Ebx::hle.bits()),
+    ("avx2", Leaf0x7_SubLeaf0_Ebx::avx2.bits()),
+    ("smep", Leaf0x7_SubLeaf0_Ebx::smep.bits()),
+    ("bmi2", Leaf0x7_SubLeaf0_Ebx::bmi2.bits()),
+    ("erms", Leaf0x7_SubLeaf0_Ebx::erms.bits()),
+    ("invpcid", Leaf0x7_SubLeaf0_Ebx::invpcid.bits()),
+    ("rtm", Leaf0x7_SubLeaf0_Ebx::rtm.bits()),
+    ("cmt", Leaf0x7_SubLeaf0_Ebx::pqdm.bits()),
+    ("mpx", Leaf0x7_SubLeaf0_Ebx::mpx.bits()),
+    ("avx512f", Leaf0x7_SubLeaf0_Ebx::avx512_f.bits()),
+    ("avx512dq", Leaf0x7_SubLeaf0_Ebx::avx512_dq.bits()),
+    ("rdseed", Leaf0x7_SubLeaf0_Ebx::rdseed.bits()),
+    ("adx", Leaf0x7_SubLeaf0_Ebx::adx.bits()),
+    ("smap", Leaf0x7_SubLeaf0_Ebx::smap.bits()),
+    ("avx512ifma", Leaf0x7_SubLeaf0_Ebx::avx512_ifma.bits()),
+    ("pcommit", Leaf0x7_SubLeaf0_Ebx::pccommit.bits()),
+    ("clflushopt", Leaf0x7_SubLeaf0_Ebx::clflushopt.bits()),
+    ("clwb", Leaf0x7_SubLeaf0_Ebx::clwb.bits()),
+    ("intel-pt", Leaf0x7_SubLeaf0_Ebx::intel_pt.bits()),
+    ("avx512pf", Leaf0x7_SubLeaf0_Ebx::avx512_pf.bits()),
+    ("avx512er", Leaf0x7_SubLeaf0_Ebx::avx512_er.bits()),
+    ("avx512cd", Leaf0x7_SubLeaf0_Ebx::avx512_cd.bits()),
+    ("sha-ni", Leaf0x7_SubLeaf0_Ebx::sha.bits()),
+    ("avx512bw", Leaf0x7_SubLeaf0_Ebx::avx512_bw.bits()),
+    ("avx512vl", Leaf0x7_SubLeaf0_Ebx::avx512_vl.bits()),
+];
+const LEAF0X7_ECX: &[(&str, u32)] = &[
+    ("avx512vbmi", Leaf0x7_SubLeaf0_Ecx::avx512_vbmi.bits()),
+    ("umip", Leaf0x7_SubLeaf0_Ecx::umip.bits()),
+    ("pku", Leaf0x7_SubLeaf0_Ecx::pku.bits()),
+    ("ospke", Leaf0x7_SubLeaf0_Ecx::ospke.bits()),
+    ("waitpkg", Leaf0x7_SubLeaf0_Ecx::waitpkg.bits()),
+    ("avx512vbmi2", Leaf0x7_SubLeaf0_Ecx::avx512_vbmi2.bits()),
+    ("gfni", Leaf0x7_SubLeaf0_Ecx::gfni.bits()),
+    ("vaes", Leaf0x7_SubLeaf0_Ecx::vaes.bits()),
+    ("vpclmulqdq", Leaf0x7_SubLeaf0_Ecx::vpclmulqdq.bits()),
+    ("avx512vnni", Leaf0x7_SubLeaf0_Ecx::avx512_vnni.bits()),
+    ("avx512bitalg", Leaf0x7_SubLeaf0_Ecx::avx512_bitalg.bits()),
+    (
+        "avx512-vpopcntdq",
+        Leaf0x7_SubLeaf0_Ecx::avx512_vpopcntdq.bits(),
+    ),
+    ("la57", Leaf0x7_SubLeaf0_Ecx::_5_level_paging.bits()),
+    ("rdpid", Leaf0x7_SubLeaf0_Ecx::rdpid.bits()),
+    ("cldemote", Leaf0x7_SubLeaf0_Ecx::cldemote.bits()),
+    ("movdiri", Leaf0x7_SubLeaf0_Ecx::MOVDIRI.bits()),
+    ("movdir64b", Leaf0x7_SubLeaf0_Ecx::MOVDIR64B.bits()),
+    ("sgxlc", Leaf0x7_SubLeaf0_Ecx::sgx_lc.bits()),
+    ("pks", Leaf0x7_SubLeaf0_Ecx::pks.bits()),
+];
+const LEAF0X7_EDX: &[(&str, u32)] = &[
+    ("avx512-4vnniw", Leaf0x7_SubLeaf0_Edx::avx512_4vnniw.bits()),
+    ("avx512-4fmaps", Leaf0x7_SubLeaf0_Edx::avx512_4fmaps.bits()),
+    ("fsrm", Leaf0x7_SubLeaf0_Edx::fsrm.bits()),
+    (
+        "avx512-vp2intersect",
+        Leaf0x7_SubLeaf0_Edx::avx512_vp2intersect.bits(),
+    ),
+    ("md-clear", Leaf0x7_SubLeaf0_Edx::md_clear.bits()),
+    ("serialize", Leaf0x7_SubLeaf0_Edx::SERIALIZE.bits()),
+    ("tsx-ldtrk", Leaf0x7_SubLeaf0_Edx::TSXLDTRK.bits()),
+    ("arch-lbr", Leaf0x7_SubLeaf0_Edx::lbr.bits()),
+    ("amx-bf16", Leaf0x7_SubLeaf0_Edx::amx_bf16.bits()),
+    ("avx512-fp16", Leaf0x7_SubLeaf0_Edx::AVX512_FP16.bits()),
+    ("amx-tile", Leaf0x7_SubLeaf0_Edx::amx_tile.bits()),
+    ("amx-int8", Leaf0x7_SubLeaf0_Edx::amx_int8.bits()),
+    (
+        "spec-ctrl",
+        Leaf0x7_SubLeaf0_Edx::IBRS_IBPB_spec_ctrl.bits(),
+    ),
+    ("stibp", Leaf0x7_SubLeaf0_Edx::stibp.bits()),
+    (
+        "arch-capabilities",
+        Leaf0x7_SubLeaf0_Edx::IA32_ARCH_CAPABILITIES.bits(),
+    ),
+    (
+        "core-capability",
+        Leaf0x7_SubLeaf0_Edx::IA32_CORE_CAPABILITIES.bits(),
+    ),
+    ("ssbd", Leaf0x7_SubLeaf0_Edx::ssbd.bits()),
+];
+const LEAF0X7_SUB_LEAF1_EAX: &[(&str, u32)] = &[
+    ("avx-vnni", Leaf0x7_SubLeaf1_Eax::avx_vnni.bits()),
+    ("avx512-bf16", Leaf0x7_SubLeaf1_Eax::avx512_bf16.bits()),
+    ("fzrm", Leaf0x7_SubLeaf1_Eax::fast_zero_rep_movsb.bits()),
+    ("fsrs", Leaf0x7_SubLeaf1_Eax::fast_short_rep_stosb.bits()),
+    (
+        "fsrc",
+        Leaf0x7_SubLeaf1_Eax::fast_short_rep_cmpsb_scasb.bits(),
+    ),
+];
+const LEAF0XD_SUB_LEAF1_EAX: &[(&str, u32)] = &[
+    ("xsaveopt", Leaf0xD_SubLeaf1_Eax::xsaveopt.bits()),
+    ("xsavec", Leaf0xD_SubLeaf1_Eax::xsavec.bits()),
+    ("xgetbv1", Leaf0xD_SubLeaf1_Eax::xgetbv_ecx1.bits()),
+    ("xsaves", Leaf0xD_SubLeaf1_Eax::xss.bits()),
+];
+const LEAF0X8000_0001_EDX: &[(&str, u32)] = &[
+    ("syscall", Leaf0x8000_0001_SubLeaf0_Edx::syscall.bits()),
+    ("nx", Leaf0x8000_0001_SubLeaf0_Edx::nx.bits()),
+    ("mmxext", Leaf0x8000_0001_SubLeaf0_Edx::mmxext.bits()),
+    ("fxsr_opt", Leaf0x8000_0001_SubLeaf0_Edx::fxsr_opt.bits()),
+    ("pdpe1gb", Leaf0x8000_0001_SubLeaf0_Edx::pdpe1gb.bits()),
+    ("rdtscp", Leaf0x8000_0001_SubLeaf0_Edx::rdtscp.bits()),
+    ("lm", Leaf0x8000_0001_SubLeaf0_Edx::lm.bits()),
+    ("3dnowext", Leaf0x8000_0001_SubLeaf0_Edx::_3dnowext.bits()),
+    ("3dnow", Leaf0x8000_0001_SubLeaf0_Edx::_3dnow.bits()),
+];
+const LEAF0X8000_0001_ECX: &[(&str, u32)] = &[
+    ("lahf_lm", Leaf0x8000_0001_SubLeaf0_Ecx::lahf.bits()),
+    (
+        "cmp_legacy",
+        Leaf0x8000_0001_SubLeaf0_Ecx::cmp_legacy.bits(),
+    ),
+    ("svm", Leaf0x8000_0001_SubLeaf0_Ecx::svm.bits()),
+    ("extapic", Leaf0x8000_0001_SubLeaf0_Ecx::extapic.bits()),
+    ("cr8legacy", Leaf0x8000_0001_SubLeaf0_Ecx::cr8_legacy.bits()),
+    ("abm", Leaf0x8000_0001_SubLeaf0_Ecx::abm.bits()),
+    ("sse4a", Leaf0x8000_0001_SubLeaf0_Ecx::sse4a.bits()),
+    (
+        "misalignsse",
+        Leaf0x8000_0001_SubLeaf0_Ecx::missalignsse.bits(),
+    ),
+    (
+        "3dnowprefetch",
+        Leaf0x8000_0001_SubLeaf0_Ecx::_3dnowprefetch.bits(),
+    ),
+    ("osvw", Leaf0x8000_0001_SubLeaf0_Ecx::osvw.bits()),
+    ("ibs", Leaf0x8000_0001_SubLeaf0_Ecx::ibs.bits()),
+    ("xop", Leaf0x8000_0001_SubLeaf0_Ecx::xop.bits()),
+    ("skinit", Leaf0x8000_0001_SubLeaf0_Ecx::skinit.bits()),
+    ("wdt", Leaf0x8000_0001_SubLeaf0_Ecx::wdt.bits()),
+    ("lwp", Leaf0x8000_0001_SubLeaf0_Ecx::lwp.bits()),
+    ("fma4", Leaf0x8000_0001_SubLeaf0_Ecx::fma4.bits()),
+    ("tce", Leaf0x8000_0001_SubLeaf0_Ecx::tce.bits()),
+    (
+        "nodeid_msr",
+        Leaf0x8000_0001_SubLeaf0_Ecx::nodeid_msr.bits(),
+    ),
+    ("tbm", Leaf0x8000_0001_SubLeaf0_Ecx::tbm.bits()),
+    ("topoext", Leaf0x8000_0001_SubLeaf0_Ecx::topoext.bits()),
+    (
+        "perfctr_core",
+        Leaf0x8000_0001_SubLeaf0_Ecx::perfctr_core.bits(),
+    ),
+    (
+        "perfctr_nb",
+        Leaf0x8000_0001_SubLeaf0_Ecx::perfctr_nb.bits(),
+    ),
+];
+const LEAF0X8000_0008_EBX: &[(&str, u32)] = &[
+    ("clzero", Leaf0x8000_0008_SubLeaf0_Ebx::clzero.bits()),
+    (
+        "xsaveerptr",
+        Leaf0x8000_0008_SubLeaf0_Ebx::xrstor_fp_err.bits(),
+    ),
+    ("wbnoinvd", Leaf0x8000_0008_SubLeaf0_Ebx::wbnoinvd.bits()),
+    ("ibpb", Leaf0x8000_0008_SubLeaf0_Ebx::ibpb.bits()),
+    ("ibrs", Leaf0x8000_0008_SubLeaf0_Ebx::ibrs.bits()),
+    (
+        "amd-stibp",
+        Leaf0x8000_0008_SubLeaf0_Ebx::single_thread_ibp.bits(),
+    ),
+    ("amd-ssbd", Leaf0x8000_0008_SubLeaf0_Ebx::ssbd.bits()),
+    ("virt-ssbd", Leaf0x8000_0008_SubLeaf0_Ebx::virt_ssbd.bits()),
+    ("amd-no-ssb", Leaf0x8000_0008_SubLeaf0_Ebx::ssb_no.bits()),
+];
+
+/// The libvirt names of the flags in a register.
+type Names = &'static [(&'static str, u32)];
+
+/// The libvirt names of the flags in each register.
+const REGISTERS: [(u32, u32, Register, Names); 10] = [
+    (1, 0, Register::Edx, LEAF0X1_EDX),
+    (1, 0, Register::Ecx, LEAF0X1_ECX),
+    (7, 0, Register::Ebx, LEAF0X7_EBX),
+    (7, 0, Register::Ecx, LEAF0X7_ECX),
+    (7, 0, Register::Edx, LEAF0X7_EDX),
+    (7, 1, Register::Eax, LEAF0X7_SUB_LEAF1_EAX),
+    (0xD, 1, Register::Eax, LEAF0XD_SUB_LEAF1_EAX),
+    (0x8000_0001, 0, Register::Edx, LEAF0X8000_0001_EDX),
+    (0x8000_0001, 0, Register::Ecx, LEAF0X8000_0001_ECX),
+    (0x8000_0008, 0, Register::Ebx, LEAF0X8000_0008_EBX),
+];
+
+impl Cpuid {
+    /// Constructs [`Cpuid`] from a libvirt guest CPU model, the features of `base` with the
+    /// named features set.
+    ///
+    /// `names` are the `name` attributes of the model's `<feature>` elements. Names which are
+    /// unknown, or not described by [`Cpuid`], are logged and ignored.
+    #[must_use]
+    pub fn from_libvirt_features(names: &[&str], base: &Cpuid) -> Cpuid {
+        let mut cpuid = base.clone();
+        for name in names {
+            let found = REGISTERS
+                .iter()
+                .find_map(|(leaf, sub_leaf, register, names)| {
+                    let (_, bits) = names.iter().find(|(flag, _)| flag == name)?;
+                    let register = FLAGS_REGISTERS.iter().find(|flags| {
+                        (flags.leaf, flags.sub_leaf, flags.register)
+                            == (*leaf, *sub_leaf, *register)
+                    })?;
+                    Some((register, *bits))
+                });
+            let Some((register, bits)) = found else {
+                log::warn!("Unknown libvirt feature: {name}");
+                continue;
+            };
+            *(register.get_mut)(&mut cpuid) |= bits;
+        }
+        cpuid
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_libvirt_features() {
+        let base = Cpuid::default();
+        let cpuid = Cpuid::from_libvirt_features(
+            &[
+                "pni",
+                "sse4.2",
+                "avx2",
+                "md-clear",
+                "amd-ssbd",
+                "lahf_lm",
+                "not-a-feature",
+            ],
+            &base,
+        );
+
+        let mut expected = base;
+        let leaf1 = &mut expected
+            .leaf0x01_process_info_and_feature_bits
+            .feature_information;
+        leaf1.ecx = Leaf0x1_SubLeaf0_Ecx::sse3 | Leaf0x1_SubLeaf0_Ecx::sse4_2;
+        let leaf7 = &mut expected.leaf0x07_extended_features.sub_leaf0;
+        leaf7.ebx = Leaf0x7_SubLeaf0_Ebx::avx2;
+        leaf7.edx = Leaf0x7_SubLeaf0_Edx::md_clear;
+        expected
+            .leaf0x8000_0001_highest_function_parameter_an_manufacturer_id
+            .ecx = Leaf0x8000_0001_SubLeaf0_Ecx::lahf;
+        expected
+            .leaf0x8000_0008_virtual_and_physical_address_sizes
+            .ebx = Leaf0x8000_0008_SubLeaf0_Ebx::ssbd;
+        assert_eq!(cpuid, expected);
+    }
+}
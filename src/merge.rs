@@ -0,0 +1,286 @@
+//! Combining partial snapshots, e.g. a base and a vendor overlay.
+
+use core::arch::x86_64::CpuidResult;
+
+use crate::features::FLAGS_REGISTERS;
+use crate::{
+    Cpuid, Leaf0x8000_0008_SubLeaf0_Eax, Leaf0x8000_0008_SubLeaf0_Ecx, Leaf2CacheAndTlb,
+    Leaf6SubLeaf0Ebx,
+};
+
+/// How [`Cpuid::merge`] resolves values both snapshots hold.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum MergePolicy {
+    /// Values of `self` are kept.
+    PreferSelf,
+    /// Values of `other` replace those of `self`.
+    PreferOther,
+    /// Features set in either are set and scalars are the greater of both, every other value is
+    /// that of `self`.
+    Union,
+}
+
+/// Returns the bits of `a` and `b` under `mask` with the greater field, for a field of contiguous
+/// bits.
+fn max_field(a: u32, b: u32, mask: u32) -> u32 {
+    (a & mask).max(b & mask)
+}
+
+/// Adds the descriptors of `other` which `leaf2` lacks into the null bytes of `leaf2`, while any
+/// are left, registers with bit 31 set hold no descriptors.
+fn union_descriptors(leaf2: &mut Leaf2CacheAndTlb, other: &Leaf2CacheAndTlb) {
+    let descriptors = leaf2.descriptor_bytes();
+    let mut missing = other
+        .descriptor_bytes()
+        .into_iter()
+        .filter(|byte| !descriptors.contains(byte));
+    for (i, register) in [
+        &mut leaf2.eax,
+        &mut leaf2.ebx,
+        &mut leaf2.ecx,
+        &mut leaf2.edx,
+    ]
+    .into_iter()
+    .enumerate()
+    .filter(|(_, register)| **register & (1 << 31) == 0)
+    {
+        let mut bytes = register.to_le_bytes();
+        // The low byte of `eax` is the times to query.
+        for byte in bytes[usize::from(i == 0)..]
+            .iter_mut()
+            .filter(|byte| **byte == 0)
+        {
+            if let Some(descriptor) = missing.next() {
+                *byte = descriptor;
+            }
+        }
+        *register = u32::from_le_bytes(bytes);
+    }
+}
+
+impl Cpuid {
+    /// Merges `other` into `self`, resolving values both hold with `policy`.
+    ///
+    /// A leaf only one snapshot holds, one in [`Cpuid::misc`] or one of [`Cpuid::LEAVES`] the
+    /// other did not query (see [`Cpuid::leaf_present`]), is taken from the snapshot holding it
+    /// whatever the policy. For leaves both hold:
+    /// - [`MergePolicy::PreferSelf`] and [`MergePolicy::PreferOther`] take every register of the
+    ///   leaf from one snapshot.
+    /// - [`MergePolicy::Union`] ORs the flags of the registers described by bit flags, adds the
+    ///   leaf 2 descriptors of `other` which `self` lacks (while null bytes are left) and takes the
+    ///   greater of the scalars [`Cpuid::covers`] compares with `>=` (the highest leaf, the maximum
+    ///   addressable logical processor ids, the number of interrupt thresholds and the leaf
+    ///   `0x8000_0008` `eax` and `ecx` fields). Every other value (e.g. the manufacturer id, the
+    ///   values [`Cpuid::covers`] requires equal such as the leaf 7 `ecx` MAWAU and the brand
+    ///   string, the local APIC id and [`Cpuid::misc`] entries) is that of `self`.
+    pub fn merge(&mut self, other: &Self, policy: MergePolicy) {
+        let (base, overlay) = match policy {
+            MergePolicy::PreferSelf | MergePolicy::Union => (other, &*self),
+            MergePolicy::PreferOther => (&*self, other),
+        };
+        let mut map = base.to_leaf_map();
+        map.extend(
+            overlay
+                .to_leaf_map()
+                .into_iter()
+                .filter(|((leaf, _), _)| overlay.leaf_present(*leaf)),
+        );
+        let mut merged = Self::from_fn(|leaf, sub_leaf| {
+            map.get(&(leaf, sub_leaf)).copied().unwrap_or(CpuidResult {
+                eax: 0,
+                ebx: 0,
+                ecx: 0,
+                edx: 0,
+            })
+        });
+        merged.misc.clone_from(&base.misc);
+        merged.misc.extend(&overlay.misc);
+        merged.absent_leaves = self.absent_leaves & other.absent_leaves;
+
+        if policy == MergePolicy::Union {
+            // Only the flags, the leaf 7 `ecx` MAWAU value is carried over unchanged.
+            for register in &FLAGS_REGISTERS {
+                *(register.get_mut)(&mut merged) |= (register.get)(other) & register.named_bits();
+            }
+            union_descriptors(
+                &mut merged.leaf0x02_cache_and_tlb,
+                &other.leaf0x02_cache_and_tlb,
+            );
+
+            let leaf0 = &mut merged.leaf0x00_highest_function_parameter_an_manufacturer_id;
+            leaf0.highest_calling_parameter = leaf0.highest_calling_parameter.max(
+                other
+                    .leaf0x00_highest_function_parameter_an_manufacturer_id
+                    .highest_calling_parameter,
+            );
+
+            let additional = &mut merged
+                .leaf0x01_process_info_and_feature_bits
+                .additional_information;
+            additional.maximum_addressable_logical_processor_ids =
+                additional.maximum_addressable_logical_processor_ids.max(
+                    other
+                        .leaf0x01_process_info_and_feature_bits
+                        .additional_information
+                        .maximum_addressable_logical_processor_ids,
+                );
+
+            let thresholds = &mut merged
+                .leaf0x06_thermal_and_power_management
+                .number_of_interrupt_thresholds;
+            // Bits 4 to 31 are reserved.
+            *thresholds = Leaf6SubLeaf0Ebx(
+                (thresholds.0 & !0b1111)
+                    | max_field(
+                        thresholds.0,
+                        other
+                            .leaf0x06_thermal_and_power_management
+                            .number_of_interrupt_thresholds
+                            .0,
+                        0b1111,
+                    ),
+            );
+
+            let (sizes, other_sizes) = (
+                &mut merged.leaf0x8000_0008_virtual_and_physical_address_sizes,
+                &other.leaf0x8000_0008_virtual_and_physical_address_sizes,
+            );
+            sizes.eax = Leaf0x8000_0008_SubLeaf0_Eax([0x0000_00FF, 0x0000_FF00].iter().fold(
+                sizes.eax.0 & Leaf0x8000_0008_SubLeaf0_Eax::RESERVED,
+                |eax, mask| eax | max_field(sizes.eax.0, other_sizes.eax.0, *mask),
+            ));
+            sizes.ecx =
+                Leaf0x8000_0008_SubLeaf0_Ecx([0x0000_00FF, 0x0000_F000, 0x0003_0000].iter().fold(
+                    sizes.ecx.0 & Leaf0x8000_0008_SubLeaf0_Ecx::RESERVED,
+                    |ecx, mask| ecx | max_field(sizes.ecx.0, other_sizes.ecx.0, *mask),
+                ));
+        }
+        *self = merged;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Leaf0x1_SubLeaf0_Ecx, Leaf0x7_SubLeaf0_Ebx, Leaf0x7_SubLeaf0_Ecx};
+
+    /// Returns overlapping snapshots, each with a feature, a greater scalar and a misc entry the
+    /// other lacks, and a misc entry both hold with different values.
+    fn overlapping() -> (Cpuid, Cpuid) {
+        let mut a = Cpuid::new();
+        a.misc.clear();
+        let mut b = a.clone();
+        a.leaf0x07_extended_features.sub_leaf0.ebx = Leaf0x7_SubLeaf0_Ebx::avx2;
+        a.leaf0x8000_0008_virtual_and_physical_address_sizes
+            .eax
+            .set_number_of_physical_address_bits(46);
+        a.misc.insert((0x4000_0000, 0), (1, 1, 1, 1));
+        a.misc.insert((0x15, 0), (2, 2, 2, 2));
+        b.leaf0x07_extended_features.sub_leaf0.ebx = Leaf0x7_SubLeaf0_Ebx::avx512_f;
+        b.leaf0x8000_0008_virtual_and_physical_address_sizes
+            .eax
+            .set_number_of_physical_address_bits(52);
+        b.misc.insert((0x4000_0000, 0), (3, 3, 3, 3));
+        b.misc.insert((0x16, 0), (4, 4, 4, 4));
+        (a, b)
+    }
+
+    #[test]
+    fn merge_prefer() {
+        let (a, b) = overlapping();
+        for (policy, preferred) in [
+            (MergePolicy::PreferSelf, &a),
+            (MergePolicy::PreferOther, &b),
+        ] {
+            let mut merged = a.clone();
+            merged.merge(&b, policy);
+            assert_eq!(
+                merged.leaf0x07_extended_features,
+                preferred.leaf0x07_extended_features
+            );
+            assert_eq!(
+                merged.leaf0x8000_0008_virtual_and_physical_address_sizes,
+                preferred.leaf0x8000_0008_virtual_and_physical_address_sizes
+            );
+            assert_eq!(
+                merged.misc[&(0x4000_0000, 0)],
+                preferred.misc[&(0x4000_0000, 0)]
+            );
+            // Leaves only one holds are kept whatever the policy.
+            assert_eq!(merged.misc[&(0x15, 0)], (2, 2, 2, 2));
+            assert_eq!(merged.misc[&(0x16, 0)], (4, 4, 4, 4));
+            assert_eq!(merged.misc.len(), 3);
+        }
+    }
+    #[test]
+    fn merge_union() {
+        let (a, b) = overlapping();
+        let mut merged = a.clone();
+        merged.merge(&b, MergePolicy::Union);
+        assert_eq!(
+            merged.leaf0x07_extended_features.sub_leaf0.ebx,
+            Leaf0x7_SubLeaf0_Ebx::avx2 | Leaf0x7_SubLeaf0_Ebx::avx512_f
+        );
+        assert_eq!(merged.max_phys_addr(), 52);
+        assert!(merged.covers(&a) && merged.covers(&b));
+        assert_eq!(merged.misc[&(0x4000_0000, 0)], (1, 1, 1, 1));
+        assert_eq!(merged.misc.len(), 3);
+    }
+    #[test]
+    fn merge_union_values() {
+        let (mut a, mut b) = overlapping();
+        let leaf2 = |ebx| Leaf2CacheAndTlb {
+            eax: 0x0000_0001,
+            ebx,
+            ecx: 0,
+            edx: 0,
+        };
+        a.leaf0x02_cache_and_tlb = leaf2(0x0000_002C);
+        b.leaf0x02_cache_and_tlb = leaf2(0x0000_0030);
+        let mawau = |cpuid: &mut Cpuid, value: u32| {
+            let ecx = &mut cpuid.leaf0x07_extended_features.sub_leaf0.ecx;
+            ecx.bits = (ecx.bits & !Leaf0x7_SubLeaf0_Ecx::MAWAU) | (value << 17);
+        };
+        mawau(&mut a, 2);
+        mawau(&mut b, 2);
+        b.leaf0x07_extended_features.sub_leaf0.ecx |= Leaf0x7_SubLeaf0_Ecx::umip;
+        let mut merged = a.clone();
+        merged.merge(&b, MergePolicy::Union);
+        // The first null byte is in `eax`.
+        assert_eq!(
+            merged.leaf0x02_cache_and_tlb.descriptor_bytes(),
+            [0x30, 0x2C]
+        );
+        assert_eq!(merged.leaf0x07_extended_features.sub_leaf0.ecx.mawau(), 2);
+        assert!(merged.covers(&a) && merged.covers(&b));
+
+        // The values `covers` requires equal are those of `self`, not a value neither holds.
+        mawau(&mut b, 1);
+        b.set_brand_string("Bogus Processor").unwrap();
+        let mut merged = a.clone();
+        merged.merge(&b, MergePolicy::Union);
+        assert_eq!(merged.leaf0x07_extended_features.sub_leaf0.ecx.mawau(), 2);
+        assert_eq!(
+            merged.leaf0x8000_0002_brand_string,
+            a.leaf0x8000_0002_brand_string
+        );
+        assert!(merged.has_feature("umip"));
+        assert!(merged.covers(&a));
+    }
+    #[test]
+    fn merge_absent() {
+        let (mut a, mut b) = overlapping();
+        // Leaf 1 was not queried in `a`.
+        a.leaf0x01_process_info_and_feature_bits
+            .feature_information
+            .ecx = Leaf0x1_SubLeaf0_Ecx::empty();
+        a.absent_leaves = 1 << 1;
+        b.leaf0x01_process_info_and_feature_bits
+            .feature_information
+            .ecx = Leaf0x1_SubLeaf0_Ecx::sse3;
+        let mut merged = a.clone();
+        merged.merge(&b, MergePolicy::PreferSelf);
+        assert!(merged.has_feature("sse3"));
+        assert!(merged.leaf_present(1));
+    }
+}
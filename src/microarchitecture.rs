@@ -0,0 +1,144 @@
+//! Microarchitecture codenames by vendor, family and model.
+
+use crate::Cpuid;
+
+const INTEL: &[u8; 12] = b"GenuineIntel";
+const AMD: &[u8; 12] = b"AuthenticAMD";
+
+/// The microarchitecture of each manufacturer id, display family and display models.
+///
+/// Where generations share a model (e.g. Skylake, Cascade Lake and Cooper Lake Xeons are all
+/// model `0x55`, told apart by stepping) the first generation is named.
+const MICROARCHITECTURES: &[(&[u8; 12], u16, &[u8], &str)] = &[
+    (INTEL, 0x6, &[0x1A, 0x1E, 0x1F, 0x2E], "Nehalem"),
+    (INTEL, 0x6, &[0x25, 0x2C, 0x2F], "Westmere"),
+    (INTEL, 0x6, &[0x2A, 0x2D], "Sandy Bridge"),
+    (INTEL, 0x6, &[0x3A, 0x3E], "Ivy Bridge"),
+    (INTEL, 0x6, &[0x3C, 0x3F, 0x45, 0x46], "Haswell"),
+    (INTEL, 0x6, &[0x3D, 0x47, 0x4F, 0x56], "Broadwell"),
+    (INTEL, 0x6, &[0x4E, 0x55, 0x5E], "Skylake"),
+    (INTEL, 0x6, &[0x8E, 0x9E], "Kaby Lake"),
+    (INTEL, 0x6, &[0xA5, 0xA6], "Comet Lake"),
+    (INTEL, 0x6, &[0x66], "Cannon Lake"),
+    (INTEL, 0x6, &[0x6A, 0x6C, 0x7D, 0x7E], "Ice Lake"),
+    (INTEL, 0x6, &[0x8C, 0x8D], "Tiger Lake"),
+    (INTEL, 0x6, &[0xA7], "Rocket Lake"),
+    (INTEL, 0x6, &[0x97, 0x9A], "Alder Lake"),
+    (INTEL, 0x6, &[0xB7, 0xBA, 0xBF], "Raptor Lake"),
+    (INTEL, 0x6, &[0xAA, 0xAC], "Meteor Lake"),
+    (INTEL, 0x6, &[0x8F], "Sapphire Rapids"),
+    (INTEL, 0x6, &[0xCF], "Emerald Rapids"),
+    (INTEL, 0x6, &[0xAD], "Granite Rapids"),
+    (AMD, 0x15, &[0x01], "Bulldozer"),
+    (AMD, 0x15, &[0x02, 0x10, 0x13], "Piledriver"),
+    (AMD, 0x15, &[0x30, 0x38], "Steamroller"),
+    (AMD, 0x15, &[0x60, 0x65, 0x70], "Excavator"),
+    (AMD, 0x17, &[0x01, 0x11], "Zen"),
+    (AMD, 0x17, &[0x08, 0x18], "Zen+"),
+    (
+        AMD,
+        0x17,
+        &[0x31, 0x47, 0x60, 0x68, 0x71, 0x90, 0x98, 0xA0],
+        "Zen 2",
+    ),
+    (AMD, 0x19, &[0x01, 0x08, 0x21, 0x50], "Zen 3"),
+    (AMD, 0x19, &[0x40, 0x44], "Zen 3+"),
+    (
+        AMD,
+        0x19,
+        &[0x10, 0x11, 0x61, 0x70, 0x74, 0x78, 0xA0],
+        "Zen 4",
+    ),
+    (AMD, 0x1A, &[0x02, 0x11, 0x24, 0x44], "Zen 5"),
+];
+
+impl Cpuid {
+    /// Returns the microarchitecture codename e.g. `"Skylake"` or `"Zen 3"`, from the
+    /// manufacturer id and the display family and model (see
+    /// [`crate::ProcessorVersionInformation::display_family`]), `None` when the combination is not
+    /// known.
+    #[must_use]
+    pub fn microarchitecture(&self) -> Option<&'static str> {
+        let manufacturer_id = &self
+            .leaf0x00_highest_function_parameter_an_manufacturer_id
+            .manufacturer_id
+            .0;
+        let version = &self
+            .leaf0x01_process_info_and_feature_bits
+            .processor_version_information;
+        let (family, model) = (version.display_family(), version.display_model());
+        MICROARCHITECTURES
+            .iter()
+            .find(|(vendor, table_family, models, _)| {
+                *vendor == manufacturer_id && *table_family == family && models.contains(&model)
+            })
+            .map(|(.., name)| *name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::arch::x86_64::CpuidResult;
+
+    use super::*;
+    use crate::CpuModel;
+
+    /// Returns a [`Cpuid`] with the manufacturer id `vendor` and the leaf 1 `eax` `signature`.
+    fn cpuid(vendor: &[u8; 12], signature: u32) -> Cpuid {
+        let register =
+            |i: usize| u32::from_le_bytes([vendor[i], vendor[i + 1], vendor[i + 2], vendor[i + 3]]);
+        Cpuid::from_fn(|leaf, _| match leaf {
+            0 => CpuidResult {
+                eax: 0x1F,
+                ebx: register(0),
+                ecx: register(8),
+                edx: register(4),
+            },
+            1 => CpuidResult {
+                eax: signature,
+                ebx: 0,
+                ecx: 0,
+                edx: 0,
+            },
+            _ => CpuidResult {
+                eax: 0,
+                ebx: 0,
+                ecx: 0,
+                edx: 0,
+            },
+        })
+    }
+
+    #[test]
+    fn microarchitecture() {
+        // Family 6 model 0xB7 stepping 1, a Core i9-13900K.
+        let raptor_lake = cpuid(INTEL, 0x000B_0671);
+        let version = &raptor_lake
+            .leaf0x01_process_info_and_feature_bits
+            .processor_version_information;
+        assert_eq!(
+            (version.display_family(), version.display_model()),
+            (6, 0xB7)
+        );
+        assert_eq!(raptor_lake.microarchitecture(), Some("Raptor Lake"));
+        // Family 0x19 model 0x11 stepping 1, an EPYC 9004.
+        assert_eq!(cpuid(AMD, 0x00A1_0F11).microarchitecture(), Some("Zen 4"));
+        // Family 0x17 model 0x71, a Ryzen 3000.
+        assert_eq!(cpuid(AMD, 0x0087_0F10).microarchitecture(), Some("Zen 2"));
+
+        assert_eq!(
+            CpuModel::ALL.map(|model| Cpuid::preset(model).microarchitecture()),
+            [
+                Some("Nehalem"),
+                Some("Haswell"),
+                Some("Skylake"),
+                Some("Zen 2"),
+                Some("Zen 3")
+            ]
+        );
+
+        // The vendor is part of the key.
+        assert_eq!(cpuid(AMD, 0x0003_06C3).microarchitecture(), None);
+        assert_eq!(cpuid(INTEL, 0x0000_06FF).microarchitecture(), None);
+    }
+}
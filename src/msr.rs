@@ -0,0 +1,117 @@
+//! The MSRs implied by features, which a VMM must expose consistently with cpuid.
+
+use crate::Cpuid;
+
+/// The MSRs each feature implies, by number.
+pub(crate) const FEATURE_MSRS: &[(&str, &[u32])] = &[
+    // IA32_TIME_STAMP_COUNTER
+    ("tsc", &[0x10]),
+    // IA32_TSC_ADJUST
+    ("IA32_TSC_ADJUST", &[0x3B]),
+    // IA32_TSC_DEADLINE
+    ("tsc_deadline", &[0x6E0]),
+    // IA32_SYSENTER_CS, IA32_SYSENTER_ESP and IA32_SYSENTER_EIP
+    ("sep", &[0x174, 0x175, 0x176]),
+    // IA32_MTRRCAP and IA32_MTRR_DEF_TYPE
+    ("mtrr", &[0xFE, 0x2FF]),
+    // IA32_PAT
+    ("pat", &[0x277]),
+    // IA32_MCG_CAP and IA32_MCG_STATUS
+    ("mce", &[0x179, 0x17A]),
+    // IA32_PERF_CAPABILITIES
+    ("pdcm", &[0x345]),
+    // IA32_XSS
+    ("xss", &[0xDA0]),
+    // IA32_UMWAIT_CONTROL
+    ("waitpkg", &[0xE1]),
+    // IA32_SGXLEPUBKEYHASH0 to IA32_SGXLEPUBKEYHASH3
+    ("sgx_lc", &[0x8C, 0x8D, 0x8E, 0x8F]),
+    // IA32_PKRS
+    ("pks", &[0x6E1]),
+    // IA32_MCU_OPT_CTRL
+    ("SRBDS_CTRL", &[0x123]),
+    // IA32_LBR_CTL
+    ("lbr", &[0x14CE]),
+    // IA32_U_CET and IA32_S_CET
+    ("cet_ibt", &[0x6A0, 0x6A2]),
+    // IA32_SPEC_CTRL and IA32_PRED_CMD
+    ("IBRS_IBPB_spec_ctrl", &[0x48, 0x49]),
+    // IA32_SPEC_CTRL
+    ("stibp", &[0x48]),
+    // IA32_SPEC_CTRL, on Intel and AMD
+    ("ssbd", &[0x48]),
+    // IA32_FLUSH_CMD
+    ("L1D_FLUSH", &[0x10B]),
+    // IA32_ARCH_CAPABILITIES
+    ("IA32_ARCH_CAPABILITIES", &[0x10A]),
+    // IA32_CORE_CAPABILITIES
+    ("IA32_CORE_CAPABILITIES", &[0xCF]),
+    // IA32_STAR, IA32_LSTAR, IA32_CSTAR and IA32_FMASK
+    (
+        "syscall",
+        &[0xC000_0081, 0xC000_0082, 0xC000_0083, 0xC000_0084],
+    ),
+    // IA32_TSC_AUX
+    ("rdtscp", &[0xC000_0103]),
+    // IA32_TSC_AUX
+    ("rdpid", &[0xC000_0103]),
+    // IA32_PRED_CMD
+    ("ibpb", &[0x49]),
+    // IA32_SPEC_CTRL
+    ("ibrs", &[0x48]),
+    // VIRT_SPEC_CTRL
+    ("virt_ssbd", &[0xC001_011F]),
+];
+
+impl Cpuid {
+    /// Returns the numbers of the MSRs implied by the features set (e.g. `tsc_deadline` implies
+    /// `IA32_TSC_DEADLINE`, `0x6E0`), sorted and deduplicated, e.g. to set up an MSR passthrough
+    /// list consistent with the features a guest is given.
+    ///
+    /// Only MSRs the crate knows a feature implies are listed, this is not every MSR a guest may
+    /// access.
+    #[must_use]
+    pub fn implied_msrs(&self) -> Vec<u32> {
+        let mut msrs = FEATURE_MSRS
+            .iter()
+            .filter(|(feature, _)| self.has_feature(feature))
+            .flat_map(|(_, msrs)| msrs.iter().copied())
+            .collect::<Vec<_>>();
+        msrs.sort_unstable();
+        msrs.dedup();
+        msrs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::features::FLAGS_REGISTERS;
+    use crate::{Leaf0x1_SubLeaf0_Ecx, Leaf0x7_SubLeaf0_Edx};
+
+    #[test]
+    fn implied_msrs() {
+        let mut cpuid = Cpuid::default();
+        assert!(cpuid.implied_msrs().is_empty());
+        cpuid
+            .leaf0x01_process_info_and_feature_bits
+            .feature_information
+            .ecx = Leaf0x1_SubLeaf0_Ecx::tsc_deadline;
+        assert_eq!(cpuid.implied_msrs(), [0x6E0]);
+        cpuid.leaf0x07_extended_features.sub_leaf0.edx =
+            Leaf0x7_SubLeaf0_Edx::IBRS_IBPB_spec_ctrl | Leaf0x7_SubLeaf0_Edx::ssbd;
+        assert_eq!(cpuid.implied_msrs(), [0x48, 0x49, 0x6E0]);
+    }
+    #[test]
+    fn msr_features_named() {
+        for (feature, _) in FEATURE_MSRS {
+            assert!(
+                FLAGS_REGISTERS
+                    .iter()
+                    .flat_map(|register| register.flags)
+                    .any(|(flag, _)| flag == feature),
+                "{feature}"
+            );
+        }
+    }
+}
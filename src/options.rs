@@ -0,0 +1,380 @@
+//! Configurable reading of [`Cpuid`], for environments where some leaves are unreliable.
+
+use core::arch::x86_64::CpuidResult;
+use std::fmt;
+
+use crate::{Cpuid, CpuidReader, HostCpuidReader};
+
+/// The hypervisor leaf, reporting the hypervisor vendor and highest hypervisor leaf.
+const HYPERVISOR_LEAF: u32 = 0x4000_0000;
+/// The first extended leaf, reporting the highest extended leaf.
+const EXTENDED_LEAF: u32 = 0x8000_0000;
+/// The leaves of [`Cpuid::LEAVES`] every x86-64 CPU reports, the vendor specific and newer
+/// leaves (e.g. leaf `0x19` or the AMD only leaf `0x8000_001F`) may be unsupported.
+const REQUIRED_LEAVES: [u32; 3] = [0x1, 0x8000_0001, 0x8000_0008];
+
+/// Which leaves [`Cpuid::with_options`] queries.
+///
+/// The defaults query the same leaves as [`Cpuid::new`] along with the hypervisor leaf when
+/// running in a virtual machine.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct CpuidOptions {
+    /// Do not query the hypervisor leaf `0x4000_0000` into [`Cpuid::misc`].
+    ///
+    /// Emulators may report time-varying values in the hypervisor leaves.
+    pub skip_hypervisor_leaf: bool,
+    /// Zero leaves above the highest leaf reported by leaf `0` (basic leaves) or `0x8000_0000`
+    /// (extended leaves) rather than querying them.
+    ///
+    /// Querying a leaf above the highest leaf returns unspecified values, on Intel the highest
+    /// basic leaf.
+    pub clamp_to_reported_max: bool,
+}
+
+/// Error type for [`Cpuid::try_new`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum CpuidError {
+    /// The given leaf, which every x86-64 CPU reports, is above the highest leaf reported by leaf
+    /// `0` (basic leaves) or `0x8000_0000` (extended leaves).
+    LeafUnsupported(u32),
+}
+impl fmt::Display for CpuidError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::LeafUnsupported(leaf) => write!(f, "Leaf {leaf:#x} is unsupported"),
+        }
+    }
+}
+impl std::error::Error for CpuidError {}
+
+/// Reads cpuid from another reader, zeroing leaves above the reported highest leaf, see
+/// [`CpuidOptions::clamp_to_reported_max`].
+struct ClampingCpuidReader<'a, R> {
+    reader: &'a mut R,
+    highest_basic_leaf: Option<u32>,
+    highest_extended_leaf: Option<u32>,
+    /// The leaves zeroed rather than read.
+    zeroed: Vec<u32>,
+}
+impl<R: CpuidReader> CpuidReader for ClampingCpuidReader<'_, R> {
+    type Error = R::Error;
+
+    fn read(&mut self, leaf: u32, sub_leaf: u32) -> Result<CpuidResult, Self::Error> {
+        let (base, highest) = match leaf {
+            ..HYPERVISOR_LEAF => (0, &mut self.highest_basic_leaf),
+            EXTENDED_LEAF.. => (EXTENDED_LEAF, &mut self.highest_extended_leaf),
+            _ => return self.reader.read(leaf, sub_leaf),
+        };
+        let highest = match *highest {
+            Some(highest) => highest,
+            None => *highest.insert(self.reader.read(base, 0)?.eax),
+        };
+        if leaf <= highest {
+            self.reader.read(leaf, sub_leaf)
+        } else {
+            self.zeroed.push(leaf);
+            Ok(CpuidResult {
+                eax: 0,
+                ebx: 0,
+                ecx: 0,
+                edx: 0,
+            })
+        }
+    }
+}
+
+impl Cpuid {
+    /// Identical to [`Cpuid::new`] but queries the leaves selected by `options`.
+    ///
+    /// Unlike [`Cpuid::new`], by default (see [`CpuidOptions::default`]) this also queries the
+    /// hypervisor leaf `0x4000_0000` into [`Cpuid::misc`] when running in a virtual machine,
+    /// see [`CpuidOptions::skip_hypervisor_leaf`].
+    #[must_use]
+    pub fn with_options(options: CpuidOptions) -> Self {
+        let Ok(cpuid) = Self::read_live(&mut HostCpuidReader, |reader| {
+            Self::from_reader_with_options(reader, options)
+        });
+        cpuid
+    }
+
+    /// Identical to [`Cpuid::new`] but does not query a leaf above the highest leaf reported,
+    /// where `cpuid` returns unspecified values.
+    ///
+    /// The unsupported leaves are zeroed and not present (see [`Cpuid::leaf_present`]), as with
+    /// [`CpuidOptions::clamp_to_reported_max`].
+    ///
+    /// # Errors
+    ///
+    /// When a leaf every x86-64 CPU reports (leaf `1`, `0x8000_0001` or `0x8000_0008`) is above
+    /// the highest leaf reported by leaf `0` or `0x8000_0000`, the first such leaf, as then the
+    /// highest leaves are unreliable.
+    pub fn try_new() -> Result<Self, CpuidError> {
+        let options = CpuidOptions {
+            skip_hypervisor_leaf: true,
+            clamp_to_reported_max: true,
+        };
+        Self::with_options(options).require_present()
+    }
+
+    /// Returns `self` when every leaf every x86-64 CPU reports is present (see
+    /// [`Cpuid::leaf_present`]), otherwise the first absent leaf.
+    fn require_present(self) -> Result<Self, CpuidError> {
+        match REQUIRED_LEAVES
+            .into_iter()
+            .find(|leaf| !self.leaf_present(*leaf))
+        {
+            Some(leaf) => Err(CpuidError::LeafUnsupported(leaf)),
+            None => Ok(self),
+        }
+    }
+
+    /// Identical to [`Cpuid::from_reader`] but queries the leaves selected by `options`.
+    ///
+    /// # Errors
+    ///
+    /// When `reader` errors reading any of the selected leaves.
+    pub fn from_reader_with_options<R: CpuidReader>(
+        reader: &mut R,
+        options: CpuidOptions,
+    ) -> Result<Self, R::Error> {
+        let mut cpuid = if options.clamp_to_reported_max {
+            let mut clamping = ClampingCpuidReader {
+                reader,
+                highest_basic_leaf: None,
+                highest_extended_leaf: None,
+                zeroed: Vec::new(),
+            };
+            let mut cpuid = Self::from_reader(&mut clamping)?;
+            cpuid.mark_absent(&clamping.zeroed);
+            cpuid
+        } else {
+            Self::from_reader(reader)?
+        };
+        if !options.skip_hypervisor_leaf && cpuid.is_virtual_machine() {
+            let CpuidResult { eax, ebx, ecx, edx } = reader.read(HYPERVISOR_LEAF, 0)?;
+            cpuid
+                .misc
+                .insert((HYPERVISOR_LEAF, 0), (eax, ebx, ecx, edx));
+        }
+        Ok(cpuid)
+    }
+
+    /// If `leaf` was queried, rather than zero-filled because it is above the highest leaf
+    /// reported (see [`CpuidOptions::clamp_to_reported_max`]).
+    ///
+    /// This tells apart an unsupported leaf from a supported leaf with every feature clear. A
+    /// leaf not in [`Cpuid::LEAVES`] is present when it is in [`Cpuid::misc`].
+    #[must_use]
+    pub fn leaf_present(&self, leaf: u32) -> bool {
+        match Self::LEAVES.iter().position(|present| *present == leaf) {
+            Some(i) => self.absent_leaves & (1 << i) == 0,
+            None => self.misc.keys().any(|(present, _)| *present == leaf),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FnCpuidReader;
+
+    /// Reads `options` from a CPU reporting highest leaves `0x7` and `0x8000_0001`, returning
+    /// the queried leaves.
+    fn read(options: CpuidOptions) -> (Cpuid, Vec<(u32, u32)>) {
+        let mut queried = Vec::new();
+        let mut reader = FnCpuidReader(|leaf, sub_leaf| {
+            queried.push((leaf, sub_leaf));
+            let eax = match leaf {
+                0 => 0x7,
+                EXTENDED_LEAF => 0x8000_0001,
+                _ => u32::MAX,
+            };
+            CpuidResult {
+                eax,
+                ebx: u32::MAX,
+                ecx: u32::MAX,
+                edx: u32::MAX,
+            }
+        });
+        let Ok(cpuid) = Cpuid::from_reader_with_options(&mut reader, options);
+        (cpuid, queried)
+    }
+
+    #[test]
+    fn default() {
+        let (cpuid, queried) = read(CpuidOptions::default());
+        assert_eq!(
+            cpuid.misc.get(&(HYPERVISOR_LEAF, 0)),
+            Some(&(u32::MAX, u32::MAX, u32::MAX, u32::MAX))
+        );
+        assert_eq!(cpuid.leaf0x0d_cpuid_feature_bits.bits, u32::MAX);
+        assert!(queried.contains(&(0xD, 1)));
+        assert!(queried.contains(&(0x8000_001F, 0)));
+    }
+    #[test]
+    fn skip_hypervisor_leaf() {
+        let (cpuid, queried) = read(CpuidOptions {
+            skip_hypervisor_leaf: true,
+            ..CpuidOptions::default()
+        });
+        assert!(cpuid.misc.is_empty());
+        assert!(!queried.contains(&(HYPERVISOR_LEAF, 0)));
+    }
+    #[test]
+    fn clamp_to_reported_max() {
+        let (cpuid, queried) = read(CpuidOptions {
+            clamp_to_reported_max: true,
+            ..CpuidOptions::default()
+        });
+        // Within the reported highest leaves.
+        assert_eq!(
+            cpuid.leaf0x07_extended_features.sub_leaf0.ebx.bits,
+            u32::MAX
+        );
+        assert_eq!(
+            cpuid
+                .leaf0x8000_0001_highest_function_parameter_an_manufacturer_id
+                .edx
+                .bits,
+            u32::MAX
+        );
+        // Above the reported highest leaves.
+        assert_eq!(cpuid.leaf0x0d_cpuid_feature_bits.bits, 0);
+        assert_eq!(cpuid.leaf0x19_cpuid_feature_bits.bits, 0);
+        assert_eq!(
+            cpuid
+                .leaf0x8000_0008_virtual_and_physical_address_sizes
+                .eax
+                .0,
+            0
+        );
+        assert_eq!(cpuid.leaf0x8000_001F_cpuid_feature_bits.bits, 0);
+        assert!(!queried.contains(&(0xD, 1)));
+        assert!(!queried.contains(&(0x8000_0008, 0)));
+        assert!(queried.contains(&(EXTENDED_LEAF, 0)));
+        // The hypervisor leaf is not clamped.
+        assert!(cpuid.misc.contains_key(&(HYPERVISOR_LEAF, 0)));
+    }
+    #[test]
+    fn try_new() {
+        let options = CpuidOptions {
+            skip_hypervisor_leaf: true,
+            clamp_to_reported_max: true,
+        };
+        let (cpuid, _) = read(options);
+        assert_eq!(
+            cpuid.require_present(),
+            Err(CpuidError::LeafUnsupported(0x8000_0008))
+        );
+        // An Intel CPU without the newer and AMD only leaves.
+        let mut reader = FnCpuidReader(|leaf, _| {
+            let word = |s: &[u8; 4]| u32::from_le_bytes(*s);
+            match leaf {
+                0 => CpuidResult {
+                    eax: 0x16,
+                    ebx: word(b"Genu"),
+                    ecx: word(b"ntel"),
+                    edx: word(b"ineI"),
+                },
+                EXTENDED_LEAF => CpuidResult {
+                    eax: 0x8000_0008,
+                    ebx: 0,
+                    ecx: 0,
+                    edx: 0,
+                },
+                _ => CpuidResult {
+                    eax: u32::MAX,
+                    ebx: u32::MAX,
+                    ecx: u32::MAX,
+                    edx: u32::MAX,
+                },
+            }
+        });
+        let Ok(intel) = Cpuid::from_reader_with_options(&mut reader, options);
+        let intel = intel.require_present().unwrap();
+        assert!(!intel.leaf_present(0x17) && !intel.leaf_present(0x19));
+        assert!(!intel.leaf_present(0x8000_001F));
+        assert_eq!(intel.leaf0x8000_001F_cpuid_feature_bits.bits, 0);
+        assert!(intel.leaf_present(0x14) && intel.leaf_present(0x8000_0008));
+
+        let mut reader = FnCpuidReader(|leaf, _| CpuidResult {
+            eax: match leaf {
+                0 => 0x19,
+                EXTENDED_LEAF => 0x8000_001F,
+                _ => 0,
+            },
+            ebx: 0,
+            ecx: 0,
+            edx: 0,
+        });
+        let Ok(cpuid) = Cpuid::from_reader_with_options(&mut reader, options);
+        assert_eq!(cpuid.clone().require_present(), Ok(cpuid));
+
+        match Cpuid::try_new() {
+            Ok(host) => assert!(REQUIRED_LEAVES.iter().all(|leaf| host.leaf_present(*leaf))),
+            Err(CpuidError::LeafUnsupported(leaf)) => {
+                assert!(!Cpuid::with_options(options).leaf_present(leaf));
+            }
+        }
+    }
+    #[test]
+    fn leaf_present() {
+        // Leaf 6 is supported but empty.
+        let mut reader = FnCpuidReader(|leaf, _| {
+            let word = |s: &[u8; 4]| u32::from_le_bytes(*s);
+            match leaf {
+                0 => CpuidResult {
+                    eax: 0x7,
+                    ebx: word(b"Genu"),
+                    ecx: word(b"ntel"),
+                    edx: word(b"ineI"),
+                },
+                6 => CpuidResult {
+                    eax: 0,
+                    ebx: 0,
+                    ecx: 0,
+                    edx: 0,
+                },
+                _ => CpuidResult {
+                    eax: if leaf == EXTENDED_LEAF {
+                        0x8000_0008
+                    } else {
+                        u32::MAX
+                    },
+                    ebx: u32::MAX,
+                    ecx: u32::MAX,
+                    edx: u32::MAX,
+                },
+            }
+        });
+        let options = CpuidOptions {
+            skip_hypervisor_leaf: true,
+            clamp_to_reported_max: true,
+        };
+        let Ok(cpuid) = Cpuid::from_reader_with_options(&mut reader, options);
+        assert!(cpuid.leaf_present(6));
+        assert!(cpuid.leaf_present(0x8000_0008));
+        assert!(!cpuid.leaf_present(0xD));
+        assert!(!cpuid.leaf_present(0x8000_001F));
+        assert!(!cpuid.leaf_present(HYPERVISOR_LEAF));
+        // An out of range leaf and a supported empty leaf are both zero.
+        assert_eq!(cpuid.leaf0x0d_cpuid_feature_bits.bits, 0);
+        assert_eq!(
+            cpuid
+                .leaf0x06_thermal_and_power_management
+                .features
+                .eax
+                .bits,
+            0
+        );
+
+        let loaded =
+            serde_json::from_str::<Cpuid>(&serde_json::to_string(&cpuid).unwrap()).unwrap();
+        assert_eq!(loaded, cpuid);
+        assert!(loaded.leaf_present(6));
+        assert!(!loaded.leaf_present(0xD));
+
+        // Queried leaves without clamping are all present.
+        assert!(Cpuid::default().leaf_present(0xD));
+    }
+}
@@ -0,0 +1,338 @@
+//! Applying partial JSON updates to [`Cpuid`].
+
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::OnceLock;
+
+use serde_json::Value;
+
+use crate::bitflags_util::{from_binary_string, to_binary_string};
+use crate::features::FLAGS_REGISTERS;
+use crate::Cpuid;
+
+/// The key which when `true` in a patch object makes the bit flags registers within it replace
+/// rather than merge with the existing registers.
+const REPLACE: &str = "replace";
+/// The path of [`Cpuid::misc`], a map into which a patch may insert leaves.
+const MISC: &str = "/misc";
+
+/// Error type for [`Cpuid::apply_patch`].
+#[derive(Debug)]
+pub enum PatchError {
+    /// The patch is not valid JSON, or applying it produced an invalid [`Cpuid`].
+    Json(serde_json::Error),
+    /// The patch is not a JSON object.
+    NotAnObject,
+    /// The patch contains a field which [`Cpuid`] does not.
+    UnknownField(String),
+}
+impl fmt::Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Json(err) => write!(f, "Invalid patch: {err}"),
+            Self::NotAnObject => write!(f, "Patch is not a JSON object"),
+            Self::UnknownField(field) => write!(f, "Unknown field `{field}`"),
+        }
+    }
+}
+impl std::error::Error for PatchError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Json(err) => Some(err),
+            Self::NotAnObject | Self::UnknownField(_) => None,
+        }
+    }
+}
+impl From<serde_json::Error> for PatchError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Json(err)
+    }
+}
+
+impl Cpuid {
+    /// Overlays a partial JSON object, in the same format [`Cpuid`] serializes to, onto `self`.
+    ///
+    /// Only the fields present in `patch` are changed. Bit flags registers (those of
+    /// [`Cpuid::active_features`]) are merged (OR'd) with the existing register unless the object
+    /// containing them (or any object above it) has `"replace": true`, in which case they
+    /// replace the existing register. Leaves not in [`Cpuid::misc`] are inserted into it.
+    ///
+    /// ```ignore
+    /// cpuid.apply_patch(r#"{
+    ///     "leaf0x06_thermal_and_power_management": {
+    ///         "replace": true,
+    ///         "features": { "ecx": "0000_0000_0000_0000_0000_0000_0000_0001" }
+    ///     }
+    /// }"#)?;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// When `patch` is not a JSON object, contains fields [`Cpuid`] does not, or produces an
+    /// invalid [`Cpuid`]. On error `self` is unchanged.
+    pub fn apply_patch(&mut self, patch: &str) -> Result<(), PatchError> {
+        let patch = serde_json::from_str::<Value>(patch)?;
+        if !patch.is_object() {
+            return Err(PatchError::NotAnObject);
+        }
+        let mut base = serde_json::to_value(&*self)?;
+        merge(&mut base, &patch, false, "", schema())?;
+        // We deserialize from a string rather than the `Value` as some fields deserialize from
+        // borrowed strings.
+        *self = serde_json::from_str(&base.to_string())?;
+        Ok(())
+    }
+}
+
+/// The fields a patch may contain.
+struct Schema {
+    /// The serialized form of [`Cpuid`] with every field present, including those not serialized
+    /// when blank (see [`crate::FixedString::is_blank`]).
+    fields: Value,
+    /// The paths (the keys from the root each preceded by `/`) of the bit flags registers in
+    /// `fields`.
+    flags_paths: HashSet<String>,
+}
+
+/// Returns the [`Schema`] of [`Cpuid`].
+fn schema() -> &'static Schema {
+    static SCHEMA: OnceLock<Schema> = OnceLock::new();
+    SCHEMA.get_or_init(|| {
+        let mut named = Cpuid::default()
+            .with_manufacturer("GenuineIntel")
+            .expect("12 byte manufacturer id");
+        named
+            .set_brand_string("Intel(R) Core(TM)")
+            .expect("brand string under 48 bytes");
+        let mut flags = named.clone();
+        for register in &FLAGS_REGISTERS {
+            *(register.get_mut)(&mut flags) = u32::MAX;
+        }
+        let serialize = |cpuid| serde_json::to_value(cpuid).expect("Cpuid serializes to JSON");
+        let fields = serialize(flags);
+        let mut flags_paths = HashSet::new();
+        differing_paths(&serialize(named), &fields, "", &mut flags_paths);
+        Schema {
+            fields,
+            flags_paths,
+        }
+    })
+}
+
+/// Inserts the paths below `path` of the values differing between `a` and `b` into `paths`.
+fn differing_paths(a: &Value, b: &Value, path: &str, paths: &mut HashSet<String>) {
+    match (a, b) {
+        (Value::Object(a), Value::Object(b)) => {
+            for (key, a) in a {
+                if let Some(b) = b.get(key) {
+                    differing_paths(a, b, &format!("{path}/{key}"), paths);
+                }
+            }
+        }
+        _ if a != b => {
+            paths.insert(path.to_owned());
+        }
+        _ => {}
+    }
+}
+
+/// Merges `patch` into `base`, the value at `path` of the fields of `schema`.
+fn merge(
+    base: &mut Value,
+    patch: &Value,
+    replace: bool,
+    path: &str,
+    schema: &Schema,
+) -> Result<(), PatchError> {
+    match (base, patch) {
+        (Value::Object(base), Value::Object(patch)) => {
+            let replace = replace || patch.get(REPLACE) == Some(&Value::Bool(true));
+            for (key, value) in patch.iter().filter(|(key, _)| *key != REPLACE) {
+                let field_path = format!("{path}/{key}");
+                if !base.contains_key(key) {
+                    if path == MISC {
+                        base.insert(key.clone(), value.clone());
+                        continue;
+                    }
+                    // A field not serialized when blank.
+                    let skipped = schema
+                        .fields
+                        .pointer(&field_path)
+                        .ok_or_else(|| PatchError::UnknownField(key.clone()))?;
+                    base.insert(key.clone(), skipped.clone());
+                }
+                merge(&mut base[key], value, replace, &field_path, schema)?;
+            }
+        }
+        (Value::String(base), Value::String(patch))
+            if !replace && schema.flags_paths.contains(path) =>
+        {
+            match (from_binary_string(base), from_binary_string(patch)) {
+                // Both are bit flags registers.
+                (Ok(base_bits), Ok(patch_bits)) => *base = to_binary_string(base_bits | patch_bits),
+                _ => base.clone_from(patch),
+            }
+        }
+        (base, patch) => *base = patch.clone(),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Leaf0x1_SubLeaf0_Ecx;
+
+    #[test]
+    fn additive_patch() {
+        let mut cpuid = Cpuid::new();
+        let feature_information = &mut cpuid
+            .leaf0x01_process_info_and_feature_bits
+            .feature_information;
+        feature_information.ecx = Leaf0x1_SubLeaf0_Ecx::avx;
+        let edx = feature_information.edx;
+        let expected_ecx = Leaf0x1_SubLeaf0_Ecx::avx | Leaf0x1_SubLeaf0_Ecx::sse3;
+
+        cpuid
+            .apply_patch(
+                r#"{
+                    "leaf0x00_highest_function_parameter_an_manufacturer_id": {
+                        "highest_calling_parameter": 7
+                    },
+                    "leaf0x01_process_info_and_feature_bits": {
+                        "feature_information": {
+                            "ecx": "0000_0000_0000_0000_0000_0000_0000_0001"
+                        }
+                    }
+                }"#,
+            )
+            .unwrap();
+        assert_eq!(
+            cpuid
+                .leaf0x00_highest_function_parameter_an_manufacturer_id
+                .highest_calling_parameter,
+            7
+        );
+        let feature_information = &cpuid
+            .leaf0x01_process_info_and_feature_bits
+            .feature_information;
+        assert_eq!(feature_information.ecx, expected_ecx);
+        assert_eq!(feature_information.edx, edx);
+
+        // Strings other than bit flags registers are replaced, even when they parse as binary.
+        let mut cpuid = cpuid.with_manufacturer("000000000000").unwrap();
+        cpuid
+            .apply_patch(
+                r#"{
+                    "leaf0x00_highest_function_parameter_an_manufacturer_id": {
+                        "manufacturer_id": "000000000001"
+                    }
+                }"#,
+            )
+            .unwrap();
+        assert_eq!(
+            cpuid
+                .leaf0x00_highest_function_parameter_an_manufacturer_id
+                .manufacturer_id
+                .0,
+            *b"000000000001"
+        );
+    }
+    #[test]
+    fn replacing_patch() {
+        let mut cpuid = Cpuid::new();
+        let feature_information = &mut cpuid
+            .leaf0x01_process_info_and_feature_bits
+            .feature_information;
+        feature_information.ecx = Leaf0x1_SubLeaf0_Ecx::avx;
+        let edx = feature_information.edx;
+
+        cpuid
+            .apply_patch(
+                r#"{
+                    "leaf0x01_process_info_and_feature_bits": {
+                        "replace": true,
+                        "feature_information": {
+                            "ecx": "0000_0000_0000_0000_0000_0000_0000_0001"
+                        }
+                    }
+                }"#,
+            )
+            .unwrap();
+        let feature_information = &cpuid
+            .leaf0x01_process_info_and_feature_bits
+            .feature_information;
+        assert_eq!(feature_information.ecx, Leaf0x1_SubLeaf0_Ecx::sse3);
+        assert_eq!(feature_information.edx, edx);
+    }
+    #[test]
+    fn misc_patch() {
+        let mut cpuid = Cpuid::default();
+        cpuid.misc.insert((0x4000_0000, 0), (1, 2, 3, 4));
+        cpuid
+            .apply_patch(
+                r#"{
+                    "misc": {
+                        "0x40000000,0x0": [5, 6, 7, 8],
+                        "0x40000001,0x0": [1, 0, 0, 0]
+                    }
+                }"#,
+            )
+            .unwrap();
+        assert_eq!(cpuid.misc.get(&(0x4000_0000, 0)), Some(&(5, 6, 7, 8)));
+        assert_eq!(cpuid.misc.get(&(0x4000_0001, 0)), Some(&(1, 0, 0, 0)));
+
+        // Inserted leaves must still be valid.
+        assert!(matches!(
+            cpuid.apply_patch(r#"{ "misc": { "0x40000002": [1, 0, 0, 0] } }"#),
+            Err(PatchError::Json(_))
+        ));
+    }
+    #[test]
+    fn blank_field_patch() {
+        let patch = r#"{
+            "leaf0x00_highest_function_parameter_an_manufacturer_id": {
+                "manufacturer_id": "GenuineIntel"
+            },
+            "leaf0x8000_0002_brand_string": {
+                "brand_string": "Intel(R) Xeon(R) Platinum 8259CL CPU @ 2.50GHz  "
+            }
+        }"#;
+        let mut anonymized = Cpuid::new();
+        anonymized.anonymize();
+        for mut cpuid in [Cpuid::default(), anonymized] {
+            cpuid.apply_patch(patch).unwrap();
+            assert_eq!(
+                cpuid
+                    .leaf0x00_highest_function_parameter_an_manufacturer_id
+                    .manufacturer_id
+                    .0,
+                *b"GenuineIntel"
+            );
+            assert_eq!(
+                cpuid.brand_string().as_deref(),
+                Some("Intel(R) Xeon(R) Platinum 8259CL CPU @ 2.50GHz")
+            );
+        }
+    }
+    #[test]
+    fn invalid_patch() {
+        let mut cpuid = Cpuid::new();
+        let before = cpuid.clone();
+        assert!(matches!(
+            cpuid.apply_patch(r#"{ "leaf0x01_process_info_and_feature_bit": {} }"#),
+            Err(PatchError::UnknownField(field)) if field == "leaf0x01_process_info_and_feature_bit"
+        ));
+        assert!(matches!(
+            cpuid.apply_patch("[]"),
+            Err(PatchError::NotAnObject)
+        ));
+        assert!(matches!(
+            cpuid.apply_patch(
+                r#"{ "leaf0x06_thermal_and_power_management": { "features": { "eax": 1 } } }"#
+            ),
+            Err(PatchError::Json(_))
+        ));
+        assert_eq!(cpuid, before);
+    }
+}
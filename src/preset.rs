@@ -0,0 +1,463 @@
+//! Templates of well-known CPU models, as a starting point without capturing hardware.
+
+use core::arch::x86_64::CpuidResult;
+
+use crate::features::FLAGS_REGISTERS;
+use crate::Cpuid;
+
+/// A well-known CPU model, named as QEMU names its CPU model of it.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum CpuModel {
+    /// Intel Nehalem (family 6 model 26), QEMU's `Nehalem`.
+    Nehalem,
+    /// Intel Haswell (family 6 model 60), QEMU's `Haswell`.
+    Haswell,
+    /// Intel Skylake Xeon (family 6 model 85), QEMU's `Skylake-Server`.
+    SkylakeServer,
+    /// AMD Zen 2 EPYC (family 23 model 49), QEMU's `EPYC-Rome`.
+    EpycRome,
+    /// AMD Zen 3 EPYC (family 25 model 1), QEMU's `EPYC-Milan`.
+    EpycMilan,
+}
+impl CpuModel {
+    /// Every model.
+    pub const ALL: [CpuModel; 5] = [
+        CpuModel::Nehalem,
+        CpuModel::Haswell,
+        CpuModel::SkylakeServer,
+        CpuModel::EpycRome,
+        CpuModel::EpycMilan,
+    ];
+
+    /// Returns the name of QEMU's CPU model of it, e.g. `"Skylake-Server"`.
+    #[must_use]
+    pub fn qemu_name(self) -> &'static str {
+        self.definition().qemu_name
+    }
+
+    fn definition(self) -> &'static Preset {
+        match self {
+            Self::Nehalem => &NEHALEM,
+            Self::Haswell => &HASWELL,
+            Self::SkylakeServer => &SKYLAKE_SERVER,
+            Self::EpycRome => &EPYC_ROME,
+            Self::EpycMilan => &EPYC_MILAN,
+        }
+    }
+}
+
+/// The values of a [`CpuModel`] which are not zero.
+struct Preset {
+    qemu_name: &'static str,
+    manufacturer_id: &'static [u8; 12],
+    /// Leaf 1 `eax`, the family, model and stepping.
+    signature: u32,
+    /// Leaf 0 `eax`.
+    max_leaf: u32,
+    /// Leaf `0x8000_0000` `eax`.
+    max_extended_leaf: u32,
+    brand_string: &'static str,
+    /// The features set, by the leaf defining them.
+    features: &'static [(u32, &'static [&'static str])],
+}
+
+const INTEL: &[u8; 12] = b"GenuineIntel";
+const AMD: &[u8; 12] = b"AuthenticAMD";
+
+/// The leaf 1 `edx` features of every model, the Intel models also set `vme`.
+const LEAF1_EDX: &[&str] = &[
+    "fpu", "de", "pse", "tsc", "msr", "pae", "mce", "cx8", "apic", "sep", "mtrr", "pge", "mca",
+    "cmov", "pat", "pse_36", "clfsh", "mmx", "fxsr", "sse", "sse2",
+];
+/// The leaf 1 `ecx` features of `Haswell` and `Skylake-Server`.
+const INTEL_LEAF1_ECX: &[&str] = &[
+    "sse3",
+    "pclmulqdq",
+    "ssse3",
+    "fma",
+    "cx16",
+    "pcid",
+    "sse4_1",
+    "sse4_2",
+    "x2apic",
+    "movbe",
+    "popcnt",
+    "tsc_deadline",
+    "aes",
+    "xsave",
+    "avx",
+    "f16c",
+    "rdrnd",
+];
+/// The leaf 1 `ecx` features of `EPYC-Rome`, `EPYC-Milan` adds `pcid`.
+const AMD_LEAF1_ECX: &[&str] = &[
+    "sse3",
+    "pclmulqdq",
+    "monitor",
+    "ssse3",
+    "fma",
+    "cx16",
+    "sse4_1",
+    "sse4_2",
+    "movbe",
+    "popcnt",
+    "aes",
+    "xsave",
+    "avx",
+    "f16c",
+    "rdrnd",
+];
+/// The leaf `0x8000_0001` features of `EPYC-Rome` and `EPYC-Milan`.
+const AMD_LEAF0X8000_0001: &[&str] = &[
+    "syscall",
+    "nx",
+    "mmxext",
+    "fxsr_opt",
+    "pdpe1gb",
+    "rdtscp",
+    "lm",
+    "lahf",
+    "svm",
+    "cr8_legacy",
+    "abm",
+    "sse4a",
+    "missalignsse",
+    "_3dnowprefetch",
+    "osvw",
+    "topoext",
+    "perfctr_core",
+];
+
+const NEHALEM: Preset = Preset {
+    qemu_name: "Nehalem",
+    manufacturer_id: INTEL,
+    // Family 6 model 26 stepping 3.
+    signature: 0x0001_06A3,
+    max_leaf: 0xB,
+    max_extended_leaf: 0x8000_0008,
+    brand_string: "Intel Core i7 9xx (Nehalem Class Core i7)",
+    features: &[
+        (1, LEAF1_EDX),
+        (1, &["vme"]),
+        (1, &["sse3", "ssse3", "cx16", "sse4_1", "sse4_2", "popcnt"]),
+        (0x8000_0001, &["syscall", "nx", "lm", "lahf"]),
+    ],
+};
+const HASWELL: Preset = Preset {
+    qemu_name: "Haswell",
+    manufacturer_id: INTEL,
+    // Family 6 model 60 stepping 4.
+    signature: 0x0003_06C4,
+    max_leaf: 0xD,
+    max_extended_leaf: 0x8000_0008,
+    brand_string: "Intel Core Processor (Haswell)",
+    features: &[
+        (1, LEAF1_EDX),
+        (1, &["vme"]),
+        (1, INTEL_LEAF1_ECX),
+        (6, &["always_running_apic_timer_capability"]),
+        (
+            7,
+            &[
+                "fsgsbase", "bmi1", "hle", "avx2", "smep", "bmi2", "erms", "invpcid", "rtm",
+            ],
+        ),
+        (0xD, &["xsaveopt"]),
+        (
+            0x8000_0001,
+            &["syscall", "nx", "rdtscp", "lm", "lahf", "abm"],
+        ),
+    ],
+};
+const SKYLAKE_SERVER: Preset = Preset {
+    qemu_name: "Skylake-Server",
+    manufacturer_id: INTEL,
+    // Family 6 model 85 stepping 4.
+    signature: 0x0005_0654,
+    max_leaf: 0xD,
+    max_extended_leaf: 0x8000_0008,
+    brand_string: "Intel Xeon Processor (Skylake)",
+    features: &[
+        (1, LEAF1_EDX),
+        (1, &["vme"]),
+        (1, INTEL_LEAF1_ECX),
+        (6, &["always_running_apic_timer_capability"]),
+        (
+            7,
+            &[
+                "fsgsbase",
+                "IA32_TSC_ADJUST",
+                "bmi1",
+                "hle",
+                "avx2",
+                "smep",
+                "bmi2",
+                "erms",
+                "invpcid",
+                "rtm",
+                "mpx",
+                "avx512_f",
+                "avx512_dq",
+                "rdseed",
+                "adx",
+                "smap",
+                "clflushopt",
+                "clwb",
+                "avx512_cd",
+                "avx512_bw",
+                "avx512_vl",
+                "pku",
+            ],
+        ),
+        (0xD, &["xsaveopt", "xsavec", "xgetbv_ecx1"]),
+        (
+            0x8000_0001,
+            &[
+                "syscall",
+                "nx",
+                "pdpe1gb",
+                "rdtscp",
+                "lm",
+                "lahf",
+                "abm",
+                "_3dnowprefetch",
+            ],
+        ),
+    ],
+};
+const EPYC_ROME: Preset = Preset {
+    qemu_name: "EPYC-Rome",
+    manufacturer_id: AMD,
+    // Family 23 model 49 stepping 0.
+    signature: 0x0083_0F10,
+    max_leaf: 0xD,
+    max_extended_leaf: 0x8000_001E,
+    brand_string: "AMD EPYC-Rome Processor",
+    features: &[
+        (1, LEAF1_EDX),
+        (1, AMD_LEAF1_ECX),
+        (6, &["always_running_apic_timer_capability"]),
+        (
+            7,
+            &[
+                "fsgsbase",
+                "bmi1",
+                "avx2",
+                "smep",
+                "bmi2",
+                "rdseed",
+                "adx",
+                "smap",
+                "clflushopt",
+                "clwb",
+                "sha",
+                "umip",
+                "rdpid",
+            ],
+        ),
+        (0xD, &["xsaveopt", "xsavec", "xgetbv_ecx1", "xss"]),
+        (0x8000_0001, AMD_LEAF0X8000_0001),
+        (
+            0x8000_0008,
+            &[
+                "clzero",
+                "xrstor_fp_err",
+                "wbnoinvd",
+                "ibpb",
+                "single_thread_ibp",
+            ],
+        ),
+    ],
+};
+const EPYC_MILAN: Preset = Preset {
+    qemu_name: "EPYC-Milan",
+    manufacturer_id: AMD,
+    // Family 25 model 1 stepping 1.
+    signature: 0x00A0_0F11,
+    max_leaf: 0xD,
+    max_extended_leaf: 0x8000_001E,
+    brand_string: "AMD EPYC-Milan Processor",
+    features: &[
+        (1, LEAF1_EDX),
+        (1, AMD_LEAF1_ECX),
+        (1, &["pcid"]),
+        (6, &["always_running_apic_timer_capability"]),
+        (
+            7,
+            &[
+                "fsgsbase",
+                "bmi1",
+                "avx2",
+                "smep",
+                "bmi2",
+                "erms",
+                "invpcid",
+                "rdseed",
+                "adx",
+                "smap",
+                "clflushopt",
+                "clwb",
+                "sha",
+                "umip",
+                "pku",
+                "vaes",
+                "vpclmulqdq",
+                "rdpid",
+                "fsrm",
+            ],
+        ),
+        (0xD, &["xsaveopt", "xsavec", "xgetbv_ecx1", "xss"]),
+        (0x8000_0001, AMD_LEAF0X8000_0001),
+        (
+            0x8000_0008,
+            &[
+                "clzero",
+                "xrstor_fp_err",
+                "wbnoinvd",
+                "ibpb",
+                "ibrs",
+                "single_thread_ibp",
+                "ssbd",
+            ],
+        ),
+    ],
+};
+
+impl Cpuid {
+    /// Returns a template of `model` with the manufacturer id, family, model, stepping, brand
+    /// string and features which QEMU's CPU model of it advertises.
+    ///
+    /// The address sizes are QEMU's defaults of 40 physical and 48 linear bits and the CLFLUSH
+    /// line size is 64 bytes. Every other value, notably the topology, is zero for the VMM to
+    /// configure.
+    ///
+    /// # Panics
+    ///
+    /// When the brand string of a preset exceeds 48 bytes, which the `presets` test checks never
+    /// occurs.
+    #[must_use]
+    pub fn preset(model: CpuModel) -> Cpuid {
+        let preset = model.definition();
+        let vendor = |i: usize| {
+            u32::from_le_bytes([
+                preset.manufacturer_id[i],
+                preset.manufacturer_id[i + 1],
+                preset.manufacturer_id[i + 2],
+                preset.manufacturer_id[i + 3],
+            ])
+        };
+        let (ebx, ecx, edx) = (vendor(0), vendor(8), vendor(4));
+        let mut cpuid = Cpuid::from_fn(|leaf, _| match leaf {
+            0 => CpuidResult {
+                eax: preset.max_leaf,
+                ebx,
+                ecx,
+                edx,
+            },
+            // The CLFLUSH line size, in 8 byte units.
+            1 => CpuidResult {
+                eax: preset.signature,
+                ebx: 8 << 8,
+                ecx: 0,
+                edx: 0,
+            },
+            // 40 physical and 48 linear address bits.
+            0x8000_0008 => CpuidResult {
+                eax: 0x3028,
+                ebx: 0,
+                ecx: 0,
+                edx: 0,
+            },
+            _ => CpuidResult {
+                eax: 0,
+                ebx: 0,
+                ecx: 0,
+                edx: 0,
+            },
+        });
+
+        for (leaf, features) in preset.features {
+            for register in FLAGS_REGISTERS
+                .iter()
+                .filter(|register| register.leaf == *leaf)
+            {
+                for (_, bit) in register
+                    .flags
+                    .iter()
+                    .filter(|(flag, _)| features.contains(flag))
+                {
+                    *(register.get_mut)(&mut cpuid) |= bit;
+                }
+            }
+        }
+
+        cpuid
+            .set_brand_string(preset.brand_string)
+            .expect("preset brand strings are under 48 bytes");
+        // AMD repeats the manufacturer id in leaf `0x8000_0000`, it is reserved on Intel.
+        let extended = if preset.manufacturer_id == AMD {
+            (preset.max_extended_leaf, ebx, ecx, edx)
+        } else {
+            (preset.max_extended_leaf, 0, 0, 0)
+        };
+        cpuid.misc.insert((0x8000_0000, 0), extended);
+        cpuid
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::X86_64Level;
+
+    #[test]
+    fn presets() {
+        let expected = [
+            (X86_64Level::V2, INTEL, (6, 26, 3)),
+            (X86_64Level::V3, INTEL, (6, 60, 4)),
+            (X86_64Level::V4, INTEL, (6, 85, 4)),
+            (X86_64Level::V3, AMD, (23, 49, 0)),
+            (X86_64Level::V3, AMD, (25, 1, 1)),
+        ];
+        for (model, (level, manufacturer_id, (family, display_model, stepping))) in
+            CpuModel::ALL.into_iter().zip(expected)
+        {
+            let preset = model.definition();
+            let cpuid = Cpuid::preset(model);
+            assert!(cpuid.decode_warnings().is_empty(), "{model:?}");
+            assert_eq!(cpuid.x86_64_level(), Some(level), "{model:?}");
+
+            let leaf0 = &cpuid.leaf0x00_highest_function_parameter_an_manufacturer_id;
+            assert_eq!(&leaf0.manufacturer_id.0, manufacturer_id);
+            let version = &cpuid
+                .leaf0x01_process_info_and_feature_bits
+                .processor_version_information;
+            assert_eq!(
+                u32::from(version.family_id()) + u32::from(version.extended_family_id()),
+                family
+            );
+            assert_eq!(
+                (u32::from(version.extended_model_id()) << 4) + u32::from(version.model()),
+                display_model
+            );
+            assert_eq!(u32::from(version.stepping_id()), stepping);
+
+            assert_eq!(cpuid.brand_string().as_deref(), Some(preset.brand_string));
+            assert_eq!(cpuid.max_phys_addr(), 40);
+            // QEMU sets `vme` on its Intel models only.
+            assert_eq!(
+                cpuid.has_feature_in_leaf("vme", 1),
+                manufacturer_id == INTEL,
+                "{model:?}"
+            );
+            for (leaf, features) in preset.features {
+                for feature in *features {
+                    assert!(
+                        cpuid.has_feature_in_leaf(feature, *leaf),
+                        "{model:?} {feature}"
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,197 @@
+//! Shareable reports of [`Cpuid`], e.g. for pasting into issues.
+
+use core::arch::x86_64::CpuidResult;
+
+use crate::features::{Register, FLAGS_REGISTERS};
+use crate::Cpuid;
+
+impl Cpuid {
+    /// Returns a GitHub flavored markdown table of every register of every leaf and sub-leaf
+    /// described by `self` (see [`Cpuid::to_leaf_map`]) which the CPU reports supported (see
+    /// [`Cpuid::leaf_reported`]), with columns `Leaf | Register | Value (hex) | Active Features`.
+    ///
+    /// Leaves are written as `leaf:sub-leaf` (e.g. `0x7:0x1`) and active features are the comma
+    /// separated names of the features set in the register.
+    #[must_use]
+    pub fn to_markdown(&self) -> String {
+        let rows = self
+            .to_leaf_map()
+            .into_iter()
+            .filter(|((leaf, _), _)| self.leaf_reported(*leaf))
+            .flat_map(|((leaf, sub_leaf), result)| {
+                [
+                    (Register::Eax, result.eax),
+                    (Register::Ebx, result.ebx),
+                    (Register::Ecx, result.ecx),
+                    (Register::Edx, result.edx),
+                ]
+                .map(|(register, value)| {
+                    let features = FLAGS_REGISTERS
+                        .iter()
+                        .filter(|flags| {
+                            (flags.leaf, flags.sub_leaf, flags.register)
+                                == (leaf, sub_leaf, register)
+                        })
+                        .flat_map(|flags| flags.flags)
+                        .filter(|(_, bit)| value & bit != 0)
+                        .map(|(name, _)| *name)
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    format!(
+                        "| {leaf:#x}:{sub_leaf:#x} | {register} | {value:#010x} | {features} |\n"
+                    )
+                })
+            });
+        let mut table = String::from(
+            "| Leaf | Register | Value (hex) | Active Features |\n| --- | --- | --- | --- |\n",
+        );
+        table.extend(rows);
+        table
+    }
+
+    /// Returns the registers of `leaf` sub-leaf `sub_leaf` as a line of the raw output of the
+    /// `cpuid` tool (`cpuid -1 -r`) e.g.
+    /// `0x00000001 0x00: eax=0x000906ea ebx=0x00100800 ecx=0x7ffafbff edx=0xbfebfbff`, to
+    /// compare one leaf against it.
+    ///
+    /// Returns `None` when the leaf is not modeled by the fields of `self` or the CPU does not
+    /// report it supported (see [`Cpuid::iter_reported_leaves`]).
+    #[must_use]
+    pub fn leaf_hex(&self, leaf: u32, sub_leaf: u32) -> Option<String> {
+        self.iter_reported_leaves()
+            .find(|(function, index, _)| (*function, *index) == (leaf, sub_leaf))
+            .map(|(_, _, CpuidResult { eax, ebx, ecx, edx })| {
+                format!(
+                    "{leaf:#010x} {sub_leaf:#04x}: eax={eax:#010x} ebx={ebx:#010x} \
+                     ecx={ecx:#010x} edx={edx:#010x}"
+                )
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Leaf0x1_SubLeaf0_Ecx;
+
+    #[test]
+    fn to_markdown() {
+        let mut cpuid = Cpuid::new();
+        cpuid
+            .leaf0x01_process_info_and_feature_bits
+            .feature_information
+            .ecx = Leaf0x1_SubLeaf0_Ecx::sse3 | Leaf0x1_SubLeaf0_Ecx::avx;
+        let markdown = cpuid.to_markdown();
+        let mut lines = markdown.lines();
+        assert_eq!(
+            lines.next(),
+            Some("| Leaf | Register | Value (hex) | Active Features |")
+        );
+        assert_eq!(lines.next(), Some("| --- | --- | --- | --- |"));
+        assert!(lines.any(|line| line == "| 0x1:0x0 | ECX | 0x10000001 | sse3, avx |"));
+        let reported = cpuid
+            .to_leaf_map()
+            .into_keys()
+            .filter(|(leaf, _)| cpuid.leaf_reported(*leaf))
+            .count();
+        assert_eq!(markdown.lines().count(), 2 + 4 * reported);
+    }
+    #[test]
+    fn leaf_hex() {
+        let cpuid = Cpuid::from_fn(|leaf, _| match leaf {
+            0 => CpuidResult {
+                eax: 0x1F,
+                ebx: 0,
+                ecx: 0,
+                edx: 0,
+            },
+            1 => CpuidResult {
+                eax: 0x0009_06EA,
+                ebx: 0x0010_0800,
+                ecx: 0x7FFA_FBFF,
+                edx: 0xBFEB_FBFF,
+            },
+            _ => CpuidResult {
+                eax: 0,
+                ebx: 0,
+                ecx: 0,
+                edx: 0,
+            },
+        });
+        assert_eq!(
+            cpuid.leaf_hex(1, 0).as_deref(),
+            Some("0x00000001 0x00: eax=0x000906ea ebx=0x00100800 ecx=0x7ffafbff edx=0xbfebfbff")
+        );
+        assert_eq!(
+            cpuid.leaf_hex(0x8000_001F, 0).as_deref(),
+            Some("0x8000001f 0x00: eax=0x00000000 ebx=0x00000000 ecx=0x00000000 edx=0x00000000")
+        );
+        assert_eq!(cpuid.leaf_hex(0xB, 0), None);
+        assert_eq!(cpuid.leaf_hex(1, 1), None);
+    }
+    #[test]
+    fn effective_max_leaf() {
+        // A CPU reporting leaf 5 as its highest, answering higher leaves with garbage.
+        let mut cpuid = Cpuid::from_fn(|leaf, _| CpuidResult {
+            eax: if leaf == 0 { 5 } else { 0xDEAD_BEEF },
+            ebx: 0xDEAD_BEEF,
+            ecx: 0xDEAD_BEEF,
+            edx: 0xDEAD_BEEF,
+        });
+        assert_eq!(cpuid.effective_max_leaf(), 5);
+        assert!(cpuid.leaf_reported(1) && !cpuid.leaf_reported(7));
+        assert!(cpuid.leaf_reported(0x8000_0001));
+        assert_eq!(
+            cpuid
+                .iter_reported_leaves()
+                .map(|(leaf, sub_leaf, _)| (leaf, sub_leaf))
+                .collect::<Vec<_>>(),
+            [
+                (0, 0),
+                (1, 0),
+                (2, 0),
+                (0x8000_0001, 0),
+                (0x8000_0002, 0),
+                (0x8000_0003, 0),
+                (0x8000_0004, 0),
+                (0x8000_0008, 0),
+                (0x8000_001F, 0)
+            ]
+        );
+        assert_eq!(cpuid.leaf_hex(7, 0), None);
+        assert!(cpuid.leaf_hex(1, 0).is_some());
+
+        let markdown = cpuid.to_markdown();
+        assert!(markdown.lines().any(|line| line.starts_with("| 0x1:0x0 |")));
+        assert!(!markdown.lines().any(|line| line.starts_with("| 0x7:")));
+        assert!(!markdown.lines().any(|line| line.starts_with("| 0x6:")));
+        assert_eq!(markdown.lines().count(), 2 + 4 * 9);
+        // The leaves are still described, e.g. for round trips.
+        assert_eq!(
+            Cpuid::from_leaf_map(&cpuid.to_leaf_map()),
+            Ok(cpuid.clone())
+        );
+
+        // Hypervisor leaves are reported by the hypervisor.
+        cpuid.misc.insert((0x4000_0000, 0), (0x4000_0001, 1, 2, 3));
+        assert!(cpuid.leaf_reported(0x4000_0000) && cpuid.leaf_reported(0x4000_0001));
+        assert!(!cpuid.leaf_reported(0x5000_0000));
+        assert!(cpuid
+            .to_markdown()
+            .lines()
+            .any(|line| line.starts_with("| 0x40000000:0x0 |")));
+
+        // Extended leaves are bounded by leaf 0x8000_0000.
+        cpuid.misc.insert((0x8000_0000, 0), (0x8000_0004, 0, 0, 0));
+        assert!(cpuid.leaf_reported(0x8000_0000) && cpuid.leaf_reported(0x8000_0004));
+        assert!(!cpuid.leaf_reported(0x8000_0008) && !cpuid.leaf_reported(0x8000_001F));
+        assert_eq!(
+            cpuid
+                .iter_reported_leaves()
+                .filter(|(leaf, ..)| *leaf >= 0x8000_0000)
+                .map(|(leaf, ..)| leaf)
+                .collect::<Vec<_>>(),
+            [0x8000_0001, 0x8000_0002, 0x8000_0003, 0x8000_0004]
+        );
+    }
+}
@@ -0,0 +1,107 @@
+//! Checksummed snapshot files of [`Cpuid`].
+
+use std::io::{self, Read, Write};
+
+use crate::Cpuid;
+
+/// Returns the CRC-32 (IEEE 802.3) of `bytes`.
+fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(u32::MAX, |crc, byte| {
+        (0..8).fold(crc ^ u32::from(*byte), |crc, _| {
+            (crc >> 1) ^ (0xEDB8_8320 & (crc & 1).wrapping_neg())
+        })
+    })
+}
+
+impl Cpuid {
+    /// Returns the CRC-32 of the byte form of `self` (see [`Cpuid::to_bytes`]), as prepended by
+    /// [`Cpuid::save`].
+    #[must_use]
+    pub fn checksum(&self) -> u32 {
+        crc32(&self.to_bytes())
+    }
+
+    /// Writes `self` to `writer` as its byte form (see [`Cpuid::to_bytes`]) prepended by its
+    /// little-endian CRC-32, such that [`Cpuid::load`] detects corrupted or truncated snapshots.
+    ///
+    /// The byte form without the checksum remains available through [`Cpuid::to_bytes`]. Like
+    /// the byte form, this does not carry which leaves are present (see [`Cpuid::leaf_present`]),
+    /// every leaf of the loaded [`Cpuid`] is.
+    ///
+    /// # Errors
+    ///
+    /// When writing to `writer` errors.
+    pub fn save<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let bytes = self.to_bytes();
+        writer.write_all(&crc32(&bytes).to_le_bytes())?;
+        writer.write_all(&bytes)
+    }
+
+    /// Reads [`Cpuid`] written by [`Cpuid::save`] from `reader`.
+    ///
+    /// # Errors
+    ///
+    /// When reading from `reader` errors, or with [`io::ErrorKind::InvalidData`] when the
+    /// checksum does not match or the payload cannot be decoded.
+    pub fn load<R: Read>(mut reader: R) -> io::Result<Self> {
+        let mut checksum = [0; 4];
+        reader.read_exact(&mut checksum)?;
+        let checksum = u32::from_le_bytes(checksum);
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        let actual = crc32(&bytes);
+        if actual != checksum {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Checksum mismatch, expected {checksum:#010x} found {actual:#010x}"),
+            ));
+        }
+        Self::try_from(bytes.as_slice())
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crc32_check_value() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+    #[test]
+    fn save_load() {
+        let mut cpuid = Cpuid::default().with_manufacturer("GenuineIntel").unwrap();
+        cpuid
+            .leaf0x8000_0008_virtual_and_physical_address_sizes
+            .eax
+            .set_number_of_physical_address_bits(40);
+        cpuid.misc.insert((0x4000_0000, 0), (1, 2, 3, 4));
+        let mut file = Vec::new();
+        cpuid.save(&mut file).unwrap();
+        assert_eq!(file[..4], cpuid.checksum().to_le_bytes());
+        assert_eq!(file[4..], cpuid.to_bytes());
+        assert_eq!(Cpuid::load(file.as_slice()).unwrap(), cpuid);
+    }
+    #[test]
+    fn load_corrupted() {
+        let mut file = Vec::new();
+        Cpuid::new().save(&mut file).unwrap();
+
+        let mut flipped = file.clone();
+        flipped[Cpuid::ENTRY_BYTES] ^= 1;
+        let err = Cpuid::load(flipped.as_slice()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().starts_with("Checksum mismatch"));
+
+        // A partial write.
+        let truncated = &file[..file.len() - Cpuid::ENTRY_BYTES];
+        let err = Cpuid::load(truncated).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().starts_with("Checksum mismatch"));
+
+        let err = Cpuid::load(&file[..2]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
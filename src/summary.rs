@@ -0,0 +1,711 @@
+//! Summaries of related features which are spread across multiple leaves.
+
+use crate::{
+    Cpuid, ExtendedFeatures, ExtendedFeaturesSubLeaf0, Leaf0x1_SubLeaf0_Ecx, Leaf0x1_SubLeaf0_Edx,
+    Leaf0x6_SubLeaf0_Eax, Leaf0x7_SubLeaf0_Ebx, Leaf0x7_SubLeaf0_Ecx, Leaf0x7_SubLeaf0_Edx,
+    Leaf0x7_SubLeaf1_Eax, Leaf0x8000_0001_SubLeaf0_Ecx, Leaf0x8000_0001_SubLeaf0_Edx,
+    Leaf0x8000_0008_SubLeaf0_Ebx,
+};
+
+/// Hardware random number generator support.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct HardwareRng {
+    /// `rdrand` (leaf 1 `ecx` bit 30), a cryptographically secure pseudo-random number generator
+    /// reseeded from the hardware entropy source.
+    pub rdrand: bool,
+    /// `rdseed` (leaf 7 `ebx` bit 18), the conditioned output of the hardware entropy source.
+    ///
+    /// This is a stronger entropy source than `rdrand` and is intended for seeding other
+    /// generators.
+    pub rdseed: bool,
+}
+
+/// Speculative execution mitigation support.
+///
+/// Intel reports these in leaf 7 `edx` while AMD reports them in leaf `0x8000_0008` `ebx`.
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct SpecMitigations {
+    /// Leaf 7 `edx` bit 9, `IA32_MCU_OPT_CTRL` supports SRBDS mitigation.
+    pub srbds_ctrl: bool,
+    /// Leaf 7 `edx` bit 10, `verw` clears CPU buffers (MDS mitigation).
+    pub md_clear: bool,
+    /// Leaf 7 `edx` bit 26, `IA32_SPEC_CTRL` supports IBRS and `IA32_PRED_CMD` supports IBPB.
+    pub ibrs_ibpb: bool,
+    /// Leaf 7 `edx` bit 27, single thread indirect branch predictors (STIBP).
+    pub stibp: bool,
+    /// Leaf 7 `edx` bit 28, `IA32_FLUSH_CMD` supports L1D flush (L1TF mitigation).
+    pub l1d_flush: bool,
+    /// Leaf 7 `edx` bit 29, the `IA32_ARCH_CAPABILITIES` MSR is present.
+    ///
+    /// This MSR describes further mitigations (e.g. `RDCL_NO`, `IBRS_ALL`, `MDS_NO`) which are
+    /// not reported by cpuid.
+    pub arch_capabilities: bool,
+    /// Leaf 7 `edx` bit 30, the `IA32_CORE_CAPABILITIES` MSR is present.
+    pub core_capabilities: bool,
+    /// Leaf 7 `edx` bit 31, speculative store bypass disable (SSBD).
+    pub ssbd: bool,
+    /// Leaf `0x8000_0008` `ebx` bit 12, indirect branch prediction barrier (IBPB).
+    pub amd_ibpb: bool,
+    /// Leaf `0x8000_0008` `ebx` bit 14, indirect branch restricted speculation (IBRS).
+    pub amd_ibrs: bool,
+    /// Leaf `0x8000_0008` `ebx` bit 15, single thread indirect branch predictors (STIBP).
+    pub amd_stibp: bool,
+    /// Leaf `0x8000_0008` `ebx` bit 17, STIBP is always on.
+    pub amd_stibp_always_on: bool,
+    /// Leaf `0x8000_0008` `ebx` bit 24, speculative store bypass disable (SSBD).
+    pub amd_ssbd: bool,
+    /// Leaf `0x8000_0008` `ebx` bit 25, SSBD through `VIRT_SPEC_CTRL`.
+    pub amd_virt_ssbd: bool,
+    /// Leaf `0x8000_0008` `ebx` bit 26, not vulnerable to speculative store bypass.
+    pub amd_ssb_no: bool,
+}
+
+/// Paging support.
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct PagingCapabilities {
+    /// `pse` (leaf 1 `edx` bit 3), 4MB pages with 32 bit paging.
+    pub pse: bool,
+    /// `pae` (leaf 1 `edx` bit 6), physical address extension.
+    pub pae: bool,
+    /// `pse_36` (leaf 1 `edx` bit 17), 4MB pages with 32 bit paging may address beyond 4GB.
+    pub pse_36: bool,
+    /// `pdpe1gb` (leaf `0x8000_0001` `edx` bit 26), 1GB pages.
+    pub pdpe1gb: bool,
+    /// `_5_level_paging` (leaf 7 `ecx` bit 16), 5 level paging, which Linux names `la57`.
+    pub la57: bool,
+}
+
+/// Cache line flush and write back support, as used for persistent memory.
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct CacheOps {
+    /// `clfsh` (leaf 1 `edx` bit 19), `clflush`.
+    pub clflush: bool,
+    /// `clflushopt` (leaf 7 `ebx` bit 23), `clflush` weakly ordered with other flushes.
+    pub clflushopt: bool,
+    /// `clwb` (leaf 7 `ebx` bit 24), write back a cache line without necessarily evicting it.
+    pub clwb: bool,
+    /// `pccommit` (leaf 7 `ebx` bit 22), `pcommit`.
+    ///
+    /// `pcommit` is deprecated and no processor supports it.
+    pub pcommit: bool,
+    /// The line size flushed by `clflush` in bytes, from leaf 1 `ebx` bits 8 to 15 (in 8 byte
+    /// units). Only valid when `clflush` is supported.
+    pub clflush_line_size_bytes: u16,
+}
+
+/// Idle instruction support, letting a waiting thread release the core until a monitored address
+/// is written.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct IdleCapabilities {
+    /// `monitor` (leaf 1 `ecx` bit 3), `monitor` and `mwait`.
+    pub monitor: bool,
+    /// `monitorx` (leaf `0x8000_0001` `ecx` bit 29), AMD's `monitorx` and `mwaitx`, which
+    /// additionally support a timeout.
+    pub mwaitx: bool,
+}
+
+/// Local APIC timer support, as used when configuring a guest's timers.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct TimerCapabilities {
+    /// `tsc_deadline` (leaf 1 `ecx` bit 24), the local APIC timer supports TSC-deadline mode.
+    pub tsc_deadline: bool,
+    /// `always_running_apic_timer_capability` (leaf 6 `eax` bit 2), the local APIC timer keeps
+    /// running in deep C-states.
+    pub always_running_apic_timer: bool,
+    /// `hypervisor` (leaf 1 `ecx` bit 31), running in a virtual machine, where the timer is
+    /// emulated.
+    pub hypervisor: bool,
+}
+
+/// The x86-64 micro-architecture levels of the System V psABI, each requiring the features of
+/// the previous level.
+///
+/// <https://gitlab.com/x86-psABIs/x86-64-ABI>
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd)]
+pub enum X86_64Level {
+    /// The baseline, e.g. `sse2` and `syscall`.
+    V1,
+    /// e.g. `cx16`, `popcnt` and `sse4_2`.
+    V2,
+    /// e.g. `avx2`, `bmi2` and `fma`.
+    V3,
+    /// `avx512_f`, `avx512_bw`, `avx512_cd`, `avx512_dq` and `avx512_vl`.
+    V4,
+}
+
+/// The features each [`X86_64Level`] requires beyond the previous level.
+///
+/// `osfxsr` and `osxsave` are omitted, as they report what the operating system enabled rather
+/// than what the CPU supports, and `lzcnt` is `abm`.
+const X86_64_LEVELS: [(X86_64Level, &[&str]); 4] = [
+    (
+        X86_64Level::V1,
+        &[
+            "cmov", "cx8", "fpu", "fxsr", "mmx", "syscall", "sse", "sse2", "lm",
+        ],
+    ),
+    (
+        X86_64Level::V2,
+        &[
+            "cx16", "lahf", "popcnt", "sse3", "sse4_1", "sse4_2", "ssse3",
+        ],
+    ),
+    (
+        X86_64Level::V3,
+        &[
+            "avx", "avx2", "bmi1", "bmi2", "f16c", "fma", "abm", "movbe", "xsave",
+        ],
+    ),
+    (
+        X86_64Level::V4,
+        &[
+            "avx512_f",
+            "avx512_bw",
+            "avx512_cd",
+            "avx512_dq",
+            "avx512_vl",
+        ],
+    ),
+];
+
+/// AVX-512 support, a foundation (`avx512_f`) and subfeatures which each require it.
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct Avx512Profile {
+    /// `avx512_f` (leaf 7 `ebx` bit 16), the foundation.
+    pub f: bool,
+    /// `avx512_dq` (leaf 7 `ebx` bit 17), doubleword and quadword instructions.
+    pub dq: bool,
+    /// `avx512_ifma` (leaf 7 `ebx` bit 21), integer fused multiply-add.
+    pub ifma: bool,
+    /// `avx512_pf` (leaf 7 `ebx` bit 26), prefetch instructions (Xeon Phi only).
+    pub pf: bool,
+    /// `avx512_er` (leaf 7 `ebx` bit 27), exponential and reciprocal instructions (Xeon Phi
+    /// only).
+    pub er: bool,
+    /// `avx512_cd` (leaf 7 `ebx` bit 28), conflict detection.
+    pub cd: bool,
+    /// `avx512_bw` (leaf 7 `ebx` bit 30), byte and word instructions.
+    pub bw: bool,
+    /// `avx512_vl` (leaf 7 `ebx` bit 31), vector length extensions.
+    pub vl: bool,
+    /// `avx512_vbmi` (leaf 7 `ecx` bit 1), vector bit manipulation.
+    pub vbmi: bool,
+    /// `avx512_vbmi2` (leaf 7 `ecx` bit 6), vector bit manipulation 2.
+    pub vbmi2: bool,
+    /// `avx512_vnni` (leaf 7 `ecx` bit 11), vector neural network instructions.
+    pub vnni: bool,
+    /// `avx512_bitalg` (leaf 7 `ecx` bit 12), bit algorithms.
+    pub bitalg: bool,
+    /// `avx512_vpopcntdq` (leaf 7 `ecx` bit 14), doubleword and quadword population count.
+    pub vpopcntdq: bool,
+    /// `avx512_4vnniw` (leaf 7 `edx` bit 2), 4 register neural network instructions (Xeon Phi
+    /// only).
+    pub four_vnniw: bool,
+    /// `avx512_4fmaps` (leaf 7 `edx` bit 3), 4 register multiply accumulate single precision
+    /// (Xeon Phi only).
+    pub four_fmaps: bool,
+    /// `avx512_vp2intersect` (leaf 7 `edx` bit 8), vector pair intersection.
+    pub vp2intersect: bool,
+    /// `AVX512_FP16` (leaf 7 `edx` bit 23), half precision instructions.
+    pub fp16: bool,
+    /// `avx512_bf16` (leaf 7 sub-leaf 1 `eax` bit 5), bfloat16 instructions.
+    pub bf16: bool,
+}
+impl Avx512Profile {
+    /// The leaf 7 flags of the subfeatures, every flag of [`Avx512Profile`] other than the
+    /// foundation.
+    pub const SUBFEATURES: ExtendedFeatures = ExtendedFeatures {
+        sub_leaf0: ExtendedFeaturesSubLeaf0 {
+            ebx: Leaf0x7_SubLeaf0_Ebx::avx512_dq
+                .union(Leaf0x7_SubLeaf0_Ebx::avx512_ifma)
+                .union(Leaf0x7_SubLeaf0_Ebx::avx512_pf)
+                .union(Leaf0x7_SubLeaf0_Ebx::avx512_er)
+                .union(Leaf0x7_SubLeaf0_Ebx::avx512_cd)
+                .union(Leaf0x7_SubLeaf0_Ebx::avx512_bw)
+                .union(Leaf0x7_SubLeaf0_Ebx::avx512_vl),
+            ecx: Leaf0x7_SubLeaf0_Ecx::avx512_vbmi
+                .union(Leaf0x7_SubLeaf0_Ecx::avx512_vbmi2)
+                .union(Leaf0x7_SubLeaf0_Ecx::avx512_vnni)
+                .union(Leaf0x7_SubLeaf0_Ecx::avx512_bitalg)
+                .union(Leaf0x7_SubLeaf0_Ecx::avx512_vpopcntdq),
+            edx: Leaf0x7_SubLeaf0_Edx::avx512_4vnniw
+                .union(Leaf0x7_SubLeaf0_Edx::avx512_4fmaps)
+                .union(Leaf0x7_SubLeaf0_Edx::avx512_vp2intersect)
+                .union(Leaf0x7_SubLeaf0_Edx::AVX512_FP16),
+        },
+        sub_leaf1: Leaf0x7_SubLeaf1_Eax::avx512_bf16,
+    };
+
+    /// If any subfeature, other than the foundation, is set.
+    #[must_use]
+    pub fn has_subfeatures(&self) -> bool {
+        *self
+            != Self {
+                f: self.f,
+                ..Self::default()
+            }
+    }
+
+    /// If every subfeature set has the foundation it requires, which a real CPU always does.
+    #[must_use]
+    pub fn is_consistent(&self) -> bool {
+        self.f || !self.has_subfeatures()
+    }
+}
+
+impl Cpuid {
+    /// If 1GB pages are usable (`pdpe1gb`, leaf `0x8000_0001` `edx` bit 26), as used by guests
+    /// backed by 1GB hugepages.
+    #[must_use]
+    pub fn supports_1gb_pages(&self) -> bool {
+        self.leaf0x8000_0001_highest_function_parameter_an_manufacturer_id
+            .edx
+            .contains(Leaf0x8000_0001_SubLeaf0_Edx::pdpe1gb)
+    }
+
+    /// Returns the supported paging capabilities reported across leaves 1, 7 and `0x8000_0001`.
+    #[must_use]
+    pub fn paging_capabilities(&self) -> PagingCapabilities {
+        let edx = self
+            .leaf0x01_process_info_and_feature_bits
+            .feature_information
+            .edx;
+        PagingCapabilities {
+            pse: edx.contains(Leaf0x1_SubLeaf0_Edx::pse),
+            pae: edx.contains(Leaf0x1_SubLeaf0_Edx::pae),
+            pse_36: edx.contains(Leaf0x1_SubLeaf0_Edx::pse_36),
+            pdpe1gb: self.supports_1gb_pages(),
+            la57: self
+                .leaf0x07_extended_features
+                .sub_leaf0
+                .ecx
+                .contains(Leaf0x7_SubLeaf0_Ecx::_5_level_paging),
+        }
+    }
+
+    /// Returns the supported cache line flush instructions reported across leaves 1 and 7.
+    #[must_use]
+    pub fn cache_ops(&self) -> CacheOps {
+        let leaf1 = &self.leaf0x01_process_info_and_feature_bits;
+        let ebx = self.leaf0x07_extended_features.sub_leaf0.ebx;
+        CacheOps {
+            clflush: leaf1
+                .feature_information
+                .edx
+                .contains(Leaf0x1_SubLeaf0_Edx::clfsh),
+            clflushopt: ebx.contains(Leaf0x7_SubLeaf0_Ebx::clflushopt),
+            clwb: ebx.contains(Leaf0x7_SubLeaf0_Ebx::clwb),
+            pcommit: ebx.contains(Leaf0x7_SubLeaf0_Ebx::pccommit),
+            clflush_line_size_bytes: u16::from(leaf1.additional_information.clflush_line_size) * 8,
+        }
+    }
+
+    /// If `monitorx` and `mwaitx` are usable (`monitorx`, leaf `0x8000_0001` `ecx` bit 29), as
+    /// used by guests for timed idling. This is AMD only.
+    #[must_use]
+    pub fn supports_mwaitx(&self) -> bool {
+        self.leaf0x8000_0001_highest_function_parameter_an_manufacturer_id
+            .ecx
+            .contains(Leaf0x8000_0001_SubLeaf0_Ecx::monitorx)
+    }
+
+    /// Returns the supported idle instructions reported across leaves 1 and `0x8000_0001`.
+    #[must_use]
+    pub fn idle_capabilities(&self) -> IdleCapabilities {
+        IdleCapabilities {
+            monitor: self
+                .leaf0x01_process_info_and_feature_bits
+                .feature_information
+                .ecx
+                .contains(Leaf0x1_SubLeaf0_Ecx::monitor),
+            mwaitx: self.supports_mwaitx(),
+        }
+    }
+
+    /// If the local APIC timer supports TSC-deadline mode (`tsc_deadline`, leaf 1 `ecx` bit 24).
+    #[must_use]
+    pub fn tsc_deadline_timer(&self) -> bool {
+        self.leaf0x01_process_info_and_feature_bits
+            .feature_information
+            .ecx
+            .contains(Leaf0x1_SubLeaf0_Ecx::tsc_deadline)
+    }
+
+    /// Returns the local APIC timer capabilities reported across leaves 1 and 6.
+    #[must_use]
+    pub fn timer_capabilities(&self) -> TimerCapabilities {
+        TimerCapabilities {
+            tsc_deadline: self.tsc_deadline_timer(),
+            always_running_apic_timer: self
+                .leaf0x06_thermal_and_power_management
+                .features
+                .eax
+                .contains(Leaf0x6_SubLeaf0_Eax::always_running_apic_timer_capability),
+            hypervisor: self.is_virtual_machine(),
+        }
+    }
+
+    /// Returns the supported AVX-512 subfeatures reported across leaf 7 sub-leaves 0 and 1.
+    #[must_use]
+    pub fn avx512_profile(&self) -> Avx512Profile {
+        let leaf7 = &self.leaf0x07_extended_features;
+        let (ebx, ecx, edx) = (
+            leaf7.sub_leaf0.ebx,
+            leaf7.sub_leaf0.ecx,
+            leaf7.sub_leaf0.edx,
+        );
+        Avx512Profile {
+            f: ebx.contains(Leaf0x7_SubLeaf0_Ebx::avx512_f),
+            dq: ebx.contains(Leaf0x7_SubLeaf0_Ebx::avx512_dq),
+            ifma: ebx.contains(Leaf0x7_SubLeaf0_Ebx::avx512_ifma),
+            pf: ebx.contains(Leaf0x7_SubLeaf0_Ebx::avx512_pf),
+            er: ebx.contains(Leaf0x7_SubLeaf0_Ebx::avx512_er),
+            cd: ebx.contains(Leaf0x7_SubLeaf0_Ebx::avx512_cd),
+            bw: ebx.contains(Leaf0x7_SubLeaf0_Ebx::avx512_bw),
+            vl: ebx.contains(Leaf0x7_SubLeaf0_Ebx::avx512_vl),
+            vbmi: ecx.contains(Leaf0x7_SubLeaf0_Ecx::avx512_vbmi),
+            vbmi2: ecx.contains(Leaf0x7_SubLeaf0_Ecx::avx512_vbmi2),
+            vnni: ecx.contains(Leaf0x7_SubLeaf0_Ecx::avx512_vnni),
+            bitalg: ecx.contains(Leaf0x7_SubLeaf0_Ecx::avx512_bitalg),
+            vpopcntdq: ecx.contains(Leaf0x7_SubLeaf0_Ecx::avx512_vpopcntdq),
+            four_vnniw: edx.contains(Leaf0x7_SubLeaf0_Edx::avx512_4vnniw),
+            four_fmaps: edx.contains(Leaf0x7_SubLeaf0_Edx::avx512_4fmaps),
+            vp2intersect: edx.contains(Leaf0x7_SubLeaf0_Edx::avx512_vp2intersect),
+            fp16: edx.contains(Leaf0x7_SubLeaf0_Edx::AVX512_FP16),
+            bf16: leaf7.sub_leaf1.contains(Leaf0x7_SubLeaf1_Eax::avx512_bf16),
+        }
+    }
+
+    /// Returns the highest x86-64 micro-architecture level whose features, and those of every lower
+    /// level, are set, `None` when the baseline is not.
+    #[must_use]
+    pub fn x86_64_level(&self) -> Option<X86_64Level> {
+        X86_64_LEVELS
+            .iter()
+            .take_while(|(_, features)| features.iter().all(|feature| self.has_feature(feature)))
+            .last()
+            .map(|(level, _)| *level)
+    }
+
+    /// Returns the supported hardware random number generator instructions.
+    #[must_use]
+    pub fn hardware_rng(&self) -> HardwareRng {
+        HardwareRng {
+            rdrand: self
+                .leaf0x01_process_info_and_feature_bits
+                .feature_information
+                .ecx
+                .contains(Leaf0x1_SubLeaf0_Ecx::rdrnd),
+            rdseed: self
+                .leaf0x07_extended_features
+                .sub_leaf0
+                .ebx
+                .contains(Leaf0x7_SubLeaf0_Ebx::rdseed),
+        }
+    }
+
+    /// Returns the speculative execution mitigations reported across leaves 7 and `0x8000_0008`.
+    #[must_use]
+    pub fn speculation_mitigations(&self) -> SpecMitigations {
+        let edx = self.leaf0x07_extended_features.sub_leaf0.edx;
+        let ebx = self.leaf0x8000_0008_virtual_and_physical_address_sizes.ebx;
+        SpecMitigations {
+            srbds_ctrl: edx.contains(Leaf0x7_SubLeaf0_Edx::SRBDS_CTRL),
+            md_clear: edx.contains(Leaf0x7_SubLeaf0_Edx::md_clear),
+            ibrs_ibpb: edx.contains(Leaf0x7_SubLeaf0_Edx::IBRS_IBPB_spec_ctrl),
+            stibp: edx.contains(Leaf0x7_SubLeaf0_Edx::stibp),
+            l1d_flush: edx.contains(Leaf0x7_SubLeaf0_Edx::L1D_FLUSH),
+            arch_capabilities: edx.contains(Leaf0x7_SubLeaf0_Edx::IA32_ARCH_CAPABILITIES),
+            core_capabilities: edx.contains(Leaf0x7_SubLeaf0_Edx::IA32_CORE_CAPABILITIES),
+            ssbd: edx.contains(Leaf0x7_SubLeaf0_Edx::ssbd),
+            amd_ibpb: ebx.contains(Leaf0x8000_0008_SubLeaf0_Ebx::ibpb),
+            amd_ibrs: ebx.contains(Leaf0x8000_0008_SubLeaf0_Ebx::ibrs),
+            amd_stibp: ebx.contains(Leaf0x8000_0008_SubLeaf0_Ebx::single_thread_ibp),
+            amd_stibp_always_on: ebx.contains(Leaf0x8000_0008_SubLeaf0_Ebx::single_thread_ibp_ao),
+            amd_ssbd: ebx.contains(Leaf0x8000_0008_SubLeaf0_Ebx::ssbd),
+            amd_virt_ssbd: ebx.contains(Leaf0x8000_0008_SubLeaf0_Ebx::virt_ssbd),
+            amd_ssb_no: ebx.contains(Leaf0x8000_0008_SubLeaf0_Ebx::ssb_no),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DecodeWarning;
+
+    #[test]
+    fn x86_64_level() {
+        let mut cpuid = Cpuid::from_fn(|_, _| core::arch::x86_64::CpuidResult {
+            eax: 0,
+            ebx: 0,
+            ecx: 0,
+            edx: 0,
+        });
+        assert_eq!(cpuid.x86_64_level(), None);
+        for (level, features) in X86_64_LEVELS {
+            for feature in features {
+                cpuid.set_feature(feature).unwrap();
+            }
+            assert_eq!(cpuid.x86_64_level(), Some(level));
+        }
+        // Each level requires the lower levels.
+        cpuid.disable_feature("popcnt").unwrap();
+        assert_eq!(cpuid.x86_64_level(), Some(X86_64Level::V1));
+        cpuid.disable_feature("lm").unwrap();
+        assert_eq!(cpuid.x86_64_level(), None);
+    }
+    #[test]
+    fn hardware_rng() {
+        let mut cpuid = Cpuid::new();
+        for (rdrand, rdseed) in [(false, false), (true, false), (false, true), (true, true)] {
+            cpuid
+                .leaf0x01_process_info_and_feature_bits
+                .feature_information
+                .ecx
+                .set(Leaf0x1_SubLeaf0_Ecx::rdrnd, rdrand);
+            cpuid
+                .leaf0x07_extended_features
+                .sub_leaf0
+                .ebx
+                .set(Leaf0x7_SubLeaf0_Ebx::rdseed, rdseed);
+            assert_eq!(cpuid.hardware_rng(), HardwareRng { rdrand, rdseed });
+        }
+    }
+    #[test]
+    fn avx512_profile() {
+        let mut cpuid = Cpuid::new();
+        let leaf7 = &mut cpuid.leaf0x07_extended_features;
+        leaf7.sub_leaf0.ebx = Leaf0x7_SubLeaf0_Ebx::empty();
+        leaf7.sub_leaf0.ecx = Leaf0x7_SubLeaf0_Ecx::empty();
+        leaf7.sub_leaf0.edx = Leaf0x7_SubLeaf0_Edx::empty();
+        leaf7.sub_leaf1 = Leaf0x7_SubLeaf1_Eax::empty();
+        assert_eq!(cpuid.avx512_profile(), Avx512Profile::default());
+        assert!(cpuid.avx512_profile().is_consistent());
+
+        let leaf7 = &mut cpuid.leaf0x07_extended_features;
+        leaf7.sub_leaf0.ebx = Leaf0x7_SubLeaf0_Ebx::avx512_f | Leaf0x7_SubLeaf0_Ebx::avx512_vl;
+        leaf7.sub_leaf0.edx = Leaf0x7_SubLeaf0_Edx::AVX512_FP16;
+        leaf7.sub_leaf1 = Leaf0x7_SubLeaf1_Eax::avx512_bf16;
+        let profile = cpuid.avx512_profile();
+        assert_eq!(
+            profile,
+            Avx512Profile {
+                f: true,
+                vl: true,
+                fp16: true,
+                bf16: true,
+                ..Default::default()
+            }
+        );
+        assert!(profile.has_subfeatures() && profile.is_consistent());
+
+        // A subfeature without the foundation.
+        cpuid
+            .leaf0x07_extended_features
+            .sub_leaf0
+            .ebx
+            .remove(Leaf0x7_SubLeaf0_Ebx::avx512_f);
+        assert!(!cpuid.avx512_profile().is_consistent());
+        assert!(cpuid
+            .decode_warnings()
+            .contains(&DecodeWarning::Avx512WithoutFoundation));
+
+        // The subfeature flags are every flag but the foundation.
+        cpuid.leaf0x07_extended_features = Avx512Profile::SUBFEATURES;
+        let profile = cpuid.avx512_profile();
+        assert_eq!(
+            profile,
+            Avx512Profile {
+                f: false,
+                dq: true,
+                ifma: true,
+                pf: true,
+                er: true,
+                cd: true,
+                bw: true,
+                vl: true,
+                vbmi: true,
+                vbmi2: true,
+                vnni: true,
+                bitalg: true,
+                vpopcntdq: true,
+                four_vnniw: true,
+                four_fmaps: true,
+                vp2intersect: true,
+                fp16: true,
+                bf16: true,
+            }
+        );
+    }
+    #[test]
+    fn idle_capabilities() {
+        let mut cpuid = Cpuid::new().with_manufacturer("AuthenticAMD").unwrap();
+        cpuid
+            .leaf0x01_process_info_and_feature_bits
+            .feature_information
+            .ecx
+            .remove(Leaf0x1_SubLeaf0_Ecx::monitor);
+        let ecx = &mut cpuid
+            .leaf0x8000_0001_highest_function_parameter_an_manufacturer_id
+            .ecx;
+        ecx.remove(Leaf0x8000_0001_SubLeaf0_Ecx::monitorx);
+        assert!(!cpuid.supports_mwaitx());
+        assert_eq!(cpuid.idle_capabilities(), IdleCapabilities::default());
+
+        cpuid
+            .leaf0x8000_0001_highest_function_parameter_an_manufacturer_id
+            .ecx
+            .insert(Leaf0x8000_0001_SubLeaf0_Ecx::monitorx);
+        assert!(cpuid.supports_mwaitx());
+        assert_eq!(
+            cpuid.idle_capabilities(),
+            IdleCapabilities {
+                monitor: false,
+                mwaitx: true
+            }
+        );
+        let warning = DecodeWarning::AmdFeatureOnIntel("monitorx");
+        assert!(!cpuid.decode_warnings().contains(&warning));
+
+        let cpuid = cpuid.with_manufacturer("GenuineIntel").unwrap();
+        assert!(cpuid.decode_warnings().contains(&warning));
+    }
+    #[test]
+    fn timer_capabilities() {
+        let mut cpuid = Cpuid::new();
+        let ecx = &mut cpuid
+            .leaf0x01_process_info_and_feature_bits
+            .feature_information
+            .ecx;
+        ecx.remove(Leaf0x1_SubLeaf0_Ecx::tsc_deadline | Leaf0x1_SubLeaf0_Ecx::hypervisor);
+        cpuid.leaf0x06_thermal_and_power_management.features.eax = Leaf0x6_SubLeaf0_Eax::empty();
+        assert!(!cpuid.tsc_deadline_timer());
+        assert_eq!(cpuid.timer_capabilities(), TimerCapabilities::default());
+
+        let ecx = &mut cpuid
+            .leaf0x01_process_info_and_feature_bits
+            .feature_information
+            .ecx;
+        ecx.insert(Leaf0x1_SubLeaf0_Ecx::tsc_deadline | Leaf0x1_SubLeaf0_Ecx::hypervisor);
+        cpuid.leaf0x06_thermal_and_power_management.features.eax =
+            Leaf0x6_SubLeaf0_Eax::always_running_apic_timer_capability;
+        assert!(cpuid.tsc_deadline_timer());
+        assert_eq!(
+            cpuid.timer_capabilities(),
+            TimerCapabilities {
+                tsc_deadline: true,
+                always_running_apic_timer: true,
+                hypervisor: true,
+            }
+        );
+    }
+    #[test]
+    fn cache_ops() {
+        let mut cpuid = Cpuid::new();
+        let leaf1 = &mut cpuid.leaf0x01_process_info_and_feature_bits;
+        leaf1.feature_information.edx = Leaf0x1_SubLeaf0_Edx::clfsh;
+        leaf1.additional_information.clflush_line_size = 8;
+        cpuid.leaf0x07_extended_features.sub_leaf0.ebx = Leaf0x7_SubLeaf0_Ebx::empty();
+        assert_eq!(
+            cpuid.cache_ops(),
+            CacheOps {
+                clflush: true,
+                clflush_line_size_bytes: 64,
+                ..Default::default()
+            }
+        );
+
+        for (flag, expected) in [
+            (
+                Leaf0x7_SubLeaf0_Ebx::clflushopt,
+                CacheOps {
+                    clflushopt: true,
+                    ..Default::default()
+                },
+            ),
+            (
+                Leaf0x7_SubLeaf0_Ebx::clwb,
+                CacheOps {
+                    clwb: true,
+                    ..Default::default()
+                },
+            ),
+            (
+                Leaf0x7_SubLeaf0_Ebx::pccommit,
+                CacheOps {
+                    pcommit: true,
+                    ..Default::default()
+                },
+            ),
+        ] {
+            let leaf1 = &mut cpuid.leaf0x01_process_info_and_feature_bits;
+            leaf1.feature_information.edx = Leaf0x1_SubLeaf0_Edx::empty();
+            leaf1.additional_information.clflush_line_size = 0;
+            cpuid.leaf0x07_extended_features.sub_leaf0.ebx = flag;
+            assert_eq!(cpuid.cache_ops(), expected);
+        }
+    }
+    #[test]
+    fn speculation_mitigations() {
+        let mut cpuid = Cpuid::new();
+        cpuid.leaf0x07_extended_features.sub_leaf0.edx =
+            Leaf0x7_SubLeaf0_Edx::md_clear | Leaf0x7_SubLeaf0_Edx::IA32_ARCH_CAPABILITIES;
+        cpuid.leaf0x8000_0008_virtual_and_physical_address_sizes.ebx =
+            Leaf0x8000_0008_SubLeaf0_Ebx::ibpb | Leaf0x8000_0008_SubLeaf0_Ebx::ssbd;
+        assert_eq!(
+            cpuid.speculation_mitigations(),
+            SpecMitigations {
+                md_clear: true,
+                arch_capabilities: true,
+                amd_ibpb: true,
+                amd_ssbd: true,
+                ..Default::default()
+            }
+        );
+    }
+    #[test]
+    fn paging_capabilities() {
+        let mut cpuid = Cpuid::new();
+        cpuid
+            .leaf0x01_process_info_and_feature_bits
+            .feature_information
+            .edx = Leaf0x1_SubLeaf0_Edx::pae | Leaf0x1_SubLeaf0_Edx::pse_36;
+        cpuid
+            .leaf0x8000_0001_highest_function_parameter_an_manufacturer_id
+            .edx = Leaf0x8000_0001_SubLeaf0_Edx::pdpe1gb;
+        cpuid.leaf0x07_extended_features.sub_leaf0.ecx = Leaf0x7_SubLeaf0_Ecx::empty();
+        assert!(cpuid.supports_1gb_pages());
+        assert_eq!(
+            cpuid.paging_capabilities(),
+            PagingCapabilities {
+                pae: true,
+                pse_36: true,
+                pdpe1gb: true,
+                ..Default::default()
+            }
+        );
+
+        cpuid
+            .leaf0x01_process_info_and_feature_bits
+            .feature_information
+            .edx = Leaf0x1_SubLeaf0_Edx::pse;
+        cpuid
+            .leaf0x8000_0001_highest_function_parameter_an_manufacturer_id
+            .edx = Leaf0x8000_0001_SubLeaf0_Edx::empty();
+        cpuid.leaf0x07_extended_features.sub_leaf0.ecx = Leaf0x7_SubLeaf0_Ecx::_5_level_paging;
+        assert!(!cpuid.supports_1gb_pages());
+        assert_eq!(
+            cpuid.paging_capabilities(),
+            PagingCapabilities {
+                pse: true,
+                la57: true,
+                ..Default::default()
+            }
+        );
+    }
+}
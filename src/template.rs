@@ -0,0 +1,232 @@
+//! Building minimal guest templates from a host.
+
+use std::fmt;
+
+use crate::features::FLAGS_REGISTERS;
+use crate::Cpuid;
+
+/// The features each feature requires, such that enabling a feature without those it requires
+/// gives a template no real CPU reports.
+///
+/// Only direct dependencies are listed, see [`Cpuid::minimal_for`] for the transitive closure.
+pub(crate) const FEATURE_DEPENDENCIES: &[(&str, &[&str])] = &[
+    ("sse", &["fxsr"]),
+    ("sse2", &["sse"]),
+    ("sse3", &["sse2"]),
+    ("ssse3", &["sse3"]),
+    ("sse4_1", &["ssse3"]),
+    ("sse4_2", &["sse4_1"]),
+    ("pclmulqdq", &["sse2"]),
+    ("aes", &["sse2"]),
+    ("sha", &["ssse3"]),
+    ("gfni", &["sse2"]),
+    ("x2apic", &["apic"]),
+    ("lm", &["pae"]),
+    ("xsaveopt", &["xsave"]),
+    ("xsavec", &["xsave"]),
+    ("xss", &["xsave"]),
+    ("avx", &["xsave", "sse4_2"]),
+    ("fma", &["avx"]),
+    ("f16c", &["avx"]),
+    ("avx2", &["avx"]),
+    ("vaes", &["aes", "avx"]),
+    ("vpclmulqdq", &["pclmulqdq", "avx"]),
+    ("avx_vnni", &["avx2"]),
+    ("avx512_f", &["avx2", "fma", "f16c"]),
+    ("avx512_dq", &["avx512_f"]),
+    ("avx512_ifma", &["avx512_f"]),
+    ("avx512_pf", &["avx512_f"]),
+    ("avx512_er", &["avx512_f"]),
+    ("avx512_cd", &["avx512_f"]),
+    ("avx512_bw", &["avx512_f"]),
+    ("avx512_vl", &["avx512_f"]),
+    ("avx512_vbmi", &["avx512_f"]),
+    ("avx512_vbmi2", &["avx512_f"]),
+    ("avx512_vnni", &["avx512_f"]),
+    ("avx512_bitalg", &["avx512_f"]),
+    ("avx512_vpopcntdq", &["avx512_f"]),
+    ("avx512_4vnniw", &["avx512_f"]),
+    ("avx512_4fmaps", &["avx512_f"]),
+    ("avx512_vp2intersect", &["avx512_f"]),
+    ("avx512_bf16", &["avx512_f"]),
+    ("AVX512_FP16", &["avx512_f"]),
+    ("amx_bf16", &["amx_tile"]),
+    ("amx_int8", &["amx_tile"]),
+];
+
+/// Error type for [`Cpuid::minimal_for`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum BuildError {
+    /// No feature has the given name.
+    UnknownFeature(String),
+    /// The host does not support the given feature, requested or required by one requested.
+    UnsupportedFeature(&'static str),
+}
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownFeature(name) => write!(f, "Unknown feature {name}"),
+            Self::UnsupportedFeature(name) => write!(f, "Host does not support feature {name}"),
+        }
+    }
+}
+impl std::error::Error for BuildError {}
+
+impl Cpuid {
+    /// Returns the minimal template `host` can run exposing exactly `features` and the features
+    /// they require (e.g. `avx2` requires `avx`), to reduce a guest's attack and migration
+    /// surface.
+    ///
+    /// Every other feature is clear. The values [`Cpuid::covers`] requires equal (e.g. the
+    /// manufacturer id), the highest leaf and the address sizes are those of `host`, every other
+    /// scalar is zero.
+    ///
+    /// # Errors
+    ///
+    /// When a feature is unknown or `host` does not support it.
+    pub fn minimal_for(host: &Cpuid, features: &[&str]) -> Result<Cpuid, BuildError> {
+        let mut template = Self::default();
+
+        let mut pending = features
+            .iter()
+            .map(|name| {
+                FLAGS_REGISTERS
+                    .iter()
+                    .flat_map(|register| register.flags)
+                    .map(|(flag, _)| *flag)
+                    .find(|flag| flag == name)
+                    .ok_or_else(|| BuildError::UnknownFeature(String::from(*name)))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        while let Some(name) = pending.pop() {
+            if template.has_feature(name) {
+                continue;
+            }
+            // A name may be defined in multiple registers, it is set in those the host sets it.
+            let mut supported = false;
+            for register in &FLAGS_REGISTERS {
+                let host_bits = (register.get)(host);
+                for (_, bit) in register.flags.iter().filter(|(flag, _)| *flag == name) {
+                    if host_bits & bit != 0 {
+                        *(register.get_mut)(&mut template) |= bit;
+                        supported = true;
+                    }
+                }
+            }
+            if !supported {
+                return Err(BuildError::UnsupportedFeature(name));
+            }
+            if let Some((_, dependencies)) = FEATURE_DEPENDENCIES
+                .iter()
+                .find(|(feature, _)| *feature == name)
+            {
+                pending.extend_from_slice(dependencies);
+            }
+        }
+
+        let (leaf0, host_leaf0) = (
+            &mut template.leaf0x00_highest_function_parameter_an_manufacturer_id,
+            &host.leaf0x00_highest_function_parameter_an_manufacturer_id,
+        );
+        leaf0.manufacturer_id = host_leaf0.manufacturer_id.clone();
+        leaf0.highest_calling_parameter = host_leaf0.highest_calling_parameter;
+
+        let (leaf1, host_leaf1) = (
+            &mut template.leaf0x01_process_info_and_feature_bits,
+            &host.leaf0x01_process_info_and_feature_bits,
+        );
+        leaf1.processor_version_information = host_leaf1.processor_version_information.clone();
+        leaf1.additional_information.brand_index = host_leaf1.additional_information.brand_index;
+        leaf1.additional_information.clflush_line_size =
+            host_leaf1.additional_information.clflush_line_size;
+        template
+            .leaf0x8000_0002_brand_string
+            .clone_from(&host.leaf0x8000_0002_brand_string);
+
+        let (eax, host_eax) = (
+            &mut template
+                .leaf0x8000_0008_virtual_and_physical_address_sizes
+                .eax,
+            &host.leaf0x8000_0008_virtual_and_physical_address_sizes.eax,
+        );
+        eax.set_number_of_physical_address_bits(host_eax.number_of_physical_address_bits());
+        eax.set_number_of_linear_address_bits(host_eax.number_of_linear_address_bits());
+
+        Ok(template)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Leaf0x1_SubLeaf0_Ecx, Leaf0x1_SubLeaf0_Edx, Leaf0x7_SubLeaf0_Ebx};
+
+    /// Returns the host with `avx2` and the features it requires.
+    fn avx2_host() -> Cpuid {
+        let mut host = Cpuid::new();
+        let leaf1 = &mut host
+            .leaf0x01_process_info_and_feature_bits
+            .feature_information;
+        leaf1.ecx |= Leaf0x1_SubLeaf0_Ecx::avx
+            | Leaf0x1_SubLeaf0_Ecx::xsave
+            | Leaf0x1_SubLeaf0_Ecx::sse3
+            | Leaf0x1_SubLeaf0_Ecx::ssse3
+            | Leaf0x1_SubLeaf0_Ecx::sse4_1
+            | Leaf0x1_SubLeaf0_Ecx::sse4_2;
+        leaf1.edx |=
+            Leaf0x1_SubLeaf0_Edx::sse | Leaf0x1_SubLeaf0_Edx::sse2 | Leaf0x1_SubLeaf0_Edx::fxsr;
+        host.leaf0x07_extended_features.sub_leaf0.ebx |= Leaf0x7_SubLeaf0_Ebx::avx2;
+        host
+    }
+
+    #[test]
+    fn minimal_avx2() {
+        let host = avx2_host();
+        let template = Cpuid::minimal_for(&host, &["avx2"]).unwrap();
+        assert_eq!(
+            template.active_features_sorted(),
+            ["avx", "avx2", "fxsr", "sse", "sse2", "sse3", "sse4_1", "sse4_2", "ssse3", "xsave"]
+        );
+        assert!(template.decode_warnings().is_empty());
+        assert!(host.covers(&template));
+        assert!(template.diff(&host).is_empty());
+        assert_eq!(
+            template.max_phys_addr(),
+            host.leaf0x8000_0008_virtual_and_physical_address_sizes
+                .eax
+                .number_of_physical_address_bits()
+        );
+    }
+    #[test]
+    fn minimal_errors() {
+        let mut host = avx2_host();
+        assert_eq!(
+            Cpuid::minimal_for(&host, &["avx3"]),
+            Err(BuildError::UnknownFeature(String::from("avx3")))
+        );
+        // A dependency the host lacks.
+        host.leaf0x01_process_info_and_feature_bits
+            .feature_information
+            .ecx
+            .remove(Leaf0x1_SubLeaf0_Ecx::xsave);
+        assert_eq!(
+            Cpuid::minimal_for(&host, &["avx2"]),
+            Err(BuildError::UnsupportedFeature("xsave"))
+        );
+    }
+    #[test]
+    fn dependencies_named() {
+        let named = |name: &str| {
+            FLAGS_REGISTERS
+                .iter()
+                .flat_map(|register| register.flags)
+                .any(|(flag, _)| *flag == name)
+        };
+        for (feature, dependencies) in FEATURE_DEPENDENCIES {
+            assert!(named(feature), "{feature}");
+            for dependency in *dependencies {
+                assert!(named(dependency), "{dependency}");
+            }
+        }
+    }
+}
@@ -0,0 +1,68 @@
+//! YAML templates, e.g. for keeping templates alongside Ansible or Kubernetes configuration.
+
+use crate::Cpuid;
+
+impl Cpuid {
+    /// Returns `self` serialized as YAML, in the same form as JSON.
+    ///
+    /// # Errors
+    ///
+    /// When serialization fails.
+    pub fn to_yaml(&self) -> Result<String, serde_yaml::Error> {
+        serde_yaml::to_string(self)
+    }
+
+    /// Deserializes a [`Cpuid`] from YAML, see [`Cpuid::to_yaml`].
+    ///
+    /// # Errors
+    ///
+    /// When `s` is not a YAML serialized [`Cpuid`].
+    pub fn from_yaml(s: &str) -> Result<Self, serde_yaml::Error> {
+        serde_yaml::from_str(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Leaf0x1_SubLeaf0_Ecx, Leaf0x7_SubLeaf0_Ecx};
+
+    #[test]
+    fn yaml() {
+        let mut cpuid = Cpuid::new();
+        cpuid
+            .leaf0x01_process_info_and_feature_bits
+            .feature_information
+            .ecx = Leaf0x1_SubLeaf0_Ecx::sse3 | Leaf0x1_SubLeaf0_Ecx::avx;
+        cpuid.leaf0x07_extended_features.sub_leaf0.ecx = Leaf0x7_SubLeaf0_Ecx::umip;
+        cpuid.misc.insert((0xB, 1), (1, 2, 3, 4));
+        cpuid
+            .leaf0x01_process_info_and_feature_bits
+            .processor_version_information
+            .set_reserved(1 << 31)
+            .unwrap();
+
+        let yaml = cpuid.to_yaml().unwrap();
+        // Bit flag registers are binary strings, most significant bit first.
+        assert!(
+            yaml.contains("0001_0000_0000_0000_0000_0000_0000_0001"),
+            "{yaml}"
+        );
+        assert!(
+            yaml.contains("0000_0000_0000_0000_0000_0000_0000_0100"),
+            "{yaml}"
+        );
+        let deserialized = Cpuid::from_yaml(&yaml).unwrap();
+        assert_eq!(deserialized, cpuid);
+        assert_eq!(
+            deserialized
+                .leaf0x01_process_info_and_feature_bits
+                .feature_information
+                .ecx,
+            Leaf0x1_SubLeaf0_Ecx::sse3 | Leaf0x1_SubLeaf0_Ecx::avx
+        );
+        assert!(
+            Cpuid::from_yaml("leaf0x00_highest_function_parameter_an_manufacturer_id: 1").is_err()
+        );
+    }
+}